@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use http_parse::{Request, RequestRef};
+use std::hint::black_box;
+
+// A browser-sized head, where header scanning dominates.
+const LARGE_REQUEST: &str = "GET /wp-content/uploads/2010/03/hello-kitty-darth-vader-pink.jpg HTTP/1.1\r\nHost: www.kittyhell.com\r\nUser-Agent: Mozilla/5.0 (Macintosh; U; Intel Mac OS X 10.6; ja-JP-mac; rv:1.9.2.3) Gecko/20100401 Firefox/3.6.3 Pathtraq/0.9\r\nAccept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\nAccept-Language: ja,en-us;q=0.7,en;q=0.3\r\nAccept-Encoding: gzip,deflate\r\nAccept-Charset: Shift_JIS,utf-8;q=0.7,*;q=0.7\r\nKeep-Alive: 115\r\nConnection: keep-alive\r\nCookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.x; __utmz=xxxxxxxxx.xxxxxxxxxx.x.x.utmccn=(referral)|utmcsr=reader.livedoor.com|utmcct=/reader/|utmcmd=referral\r\nX-Request-Id: 6f1b8c2e-53a4-4d1e-9a77-0c5d2b8e4f10\r\nX-Forwarded-For: 203.0.113.7, 10.0.0.1\r\nX-Forwarded-Proto: https\r\nCache-Control: max-age=0\r\nReferer: https://www.kittyhell.com/2010/03/\r\nDNT: 1\r\n\r\n";
+
+const GET_REQUEST: &str = "GET /api/v1/health?verbose=1 HTTP/1.1\r\nHost: backend.internal\r\nUser-Agent: lb-healthcheck/1.0\r\nAccept: */*\r\nX-Forwarded-For: 10.0.0.1\r\nConnection: keep-alive\r\n\r\n";
+
+fn parse_get(c: &mut Criterion) {
+    // The same request with bare `\n` line endings is declined by the fast
+    // path, so it measures the generic parser on identical content.
+    let generic_request: String = GET_REQUEST.replace("\r\n", "\n");
+
+    let mut group = c.benchmark_group("parse_get");
+    group.bench_function("fast_path", |b| {
+	b.iter(|| {
+	    let mut request: Request = Request::new();
+	    request.parse_from_str(black_box(GET_REQUEST)).unwrap();
+	    request
+	})
+    });
+    group.bench_function("generic_path", |b| {
+	b.iter(|| {
+	    let mut request: Request = Request::new();
+	    request.parse_from_str(black_box(&generic_request)).unwrap();
+	    request
+	})
+    });
+    group.bench_function("borrowed", |b| {
+	b.iter(|| RequestRef::parse(black_box(GET_REQUEST)).unwrap().headers().len())
+    });
+    group.bench_function("httparse", |b| b.iter(|| parse_with_httparse(black_box(GET_REQUEST))));
+    group.finish();
+}
+
+fn parse_large(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_large");
+    group.throughput(Throughput::Bytes(LARGE_REQUEST.len() as u64));
+    group.bench_function("owned", |b| b.iter(|| Request::parse(black_box(LARGE_REQUEST)).unwrap()));
+    group.bench_function("borrowed", |b| {
+	b.iter(|| RequestRef::parse(black_box(LARGE_REQUEST)).unwrap().headers().len())
+    });
+    group.bench_function("httparse", |b| b.iter(|| parse_with_httparse(black_box(LARGE_REQUEST))));
+    group.finish();
+}
+
+// The baseline: `httparse` only finds the boundaries and copies nothing, so
+// it bounds what the borrowed parser can reach.
+fn parse_with_httparse(input: &str) -> usize {
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut request = httparse::Request::new(&mut headers);
+    request.parse(input.as_bytes()).unwrap();
+    request.headers.len()
+}
+
+criterion_group!(benches, parse_get, parse_large);
+criterion_main!(benches);
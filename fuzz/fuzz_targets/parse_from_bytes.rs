@@ -0,0 +1,18 @@
+#![no_main]
+
+use http_parse::{ParseMode, ParserConfig, Request};
+use libfuzzer_sys::fuzz_target;
+
+// Run with `cargo +nightly fuzz run parse_from_bytes` from the repository root.
+fuzz_target!(|data: &[u8]| {
+    let mut strict: ParserConfig = ParserConfig::new();
+    strict.set_mode(ParseMode::Strict);
+    for config in [ParserConfig::new(), strict] {
+	if let Ok(request) = Request::parse_from_bytes_with_config(data, &config) {
+	    // Anything that parses must also serialize and parse again.
+	    let _ = Request::parse_from_bytes(&request.to_bytes());
+	    let _ = request.to_chunked_bytes(7);
+	    let _ = (request.host(), request.content_length(), request.range(), request.decoded_body());
+	}
+    }
+});
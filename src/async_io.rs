@@ -0,0 +1,122 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::chunked::{chunk_size, parse_trailer};
+use crate::reader::{body_length, io_error, unexpected_eof};
+use crate::{headers, HeaderMap, ParseError, ParserConfig, Request};
+
+// Async counterpart of `reader::read_request`, with the same framing and
+// limits. Wrap sockets in a `tokio::io::BufReader`, as the head is read one
+// byte at a time.
+pub(crate) async fn read_request<R: AsyncRead + Unpin>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+    let mut request: Request = read_head(reader, config).await?;
+    read_body(reader, &mut request).await?;
+    Ok(request)
+}
+
+pub(crate) async fn read_head<R: AsyncRead + Unpin>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+    let mut head: Vec<u8> = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+	config.check_head_size(head.len() + 1)?;
+	head.push(read_byte(reader).await?);
+    }
+    let mut request: Request = Request::new();
+    request.set_config(*config);
+    request.parse_from_str(&String::from_utf8_lossy(&head))?;
+    Ok(request)
+}
+
+pub(crate) async fn read_body<R: AsyncRead + Unpin>(reader: &mut R, request: &mut Request) -> Result<(), ParseError> {
+    let config: ParserConfig = *request.config();
+    let body: Vec<u8> = if headers::is_chunked(&request.headers) {
+	let (body, trailers) = read_chunked(reader, &config).await?;
+	request.trailers = trailers;
+	body
+    } else if let Some(length) = body_length(request, &config)? {
+	read_exact(reader, length).await?
+    } else {
+	Vec::new()
+    };
+    request.set_body_bytes(&body);
+    Ok(())
+}
+
+pub(crate) async fn write_request<W: AsyncWrite + Unpin>(request: &Request, writer: &mut W) -> std::io::Result<usize> {
+    let bytes: Vec<u8> = request.to_bytes();
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(bytes.len())
+}
+
+async fn read_chunked<R: AsyncRead + Unpin>(reader: &mut R, config: &ParserConfig) -> Result<(Vec<u8>, HeaderMap), ParseError> {
+    let mut body: Vec<u8> = Vec::new();
+    loop {
+	let size: usize = chunk_size(&read_line(reader, config).await?, body.len(), config)?;
+	if size == 0 {
+	    break;
+	}
+	body.extend_from_slice(&read_exact(reader, size).await?);
+	let end: String = read_line(reader, config).await?;
+	if !end.is_empty() {
+	    return Err(ParseError::InvalidChunk(end));
+	}
+    }
+    let mut trailers: HeaderMap = HeaderMap::new();
+    loop {
+	let line: String = read_line(reader, config).await?;
+	if line.is_empty() {
+	    break;
+	}
+	parse_trailer(&line, &mut trailers, config)?;
+    }
+    Ok((body, trailers))
+}
+
+async fn read_exact<R: AsyncRead + Unpin>(reader: &mut R, length: usize) -> Result<Vec<u8>, ParseError> {
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes).await.map_err(io_error)?;
+    if bytes.len() < length {
+	return Err(unexpected_eof());
+    }
+    Ok(bytes)
+}
+
+async fn read_line<R: AsyncRead + Unpin>(reader: &mut R, config: &ParserConfig) -> Result<String, ParseError> {
+    let mut line: Vec<u8> = Vec::new();
+    while !line.ends_with(b"\r\n") {
+	config.check_line_length(line.len() + 1)?;
+	line.push(read_byte(reader).await?);
+    }
+    line.truncate(line.len() - 2);
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+async fn read_byte<R: AsyncRead + Unpin>(reader: &mut R) -> Result<u8, ParseError> {
+    match reader.read_u8().await {
+	Ok(byte) => Ok(byte),
+	Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Err(unexpected_eof()),
+	Err(err) => Err(io_error(err)),
+    }
+}
+
+#[cfg(test)]
+mod test_async_io {
+    use crate::{ParseError, Request};
+
+    #[tokio::test]
+    async fn test_parse_from_async_reader() {
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\nPUT / HTTP/1.1\r\nContent-Length: 2\r\n\r\nhi";
+	assert_eq!(Request::parse_from_async_reader(&mut reader).await.unwrap().body(), "hello");
+	assert_eq!(Request::parse_from_async_reader(&mut reader).await.unwrap().body(), "hi");
+	assert_eq!(Request::parse_from_async_reader(&mut reader).await.err(), Some(ParseError::Io("unexpected end of stream".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_write_to_async() {
+	let request: Request = Request::builder().path("/upload").header("Host", "localhost").body("data").build().unwrap();
+	let mut written: Vec<u8> = Vec::new();
+	let length: usize = request.write_to_async(&mut written).await.unwrap();
+	assert_eq!(length, written.len());
+	let mut reader: &[u8] = &written;
+	assert_eq!(Request::parse_from_async_reader(&mut reader).await.unwrap().path(), "/upload");
+    }
+}
@@ -0,0 +1,90 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{base64, ParseError};
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Authorization {
+    Basic { user: String, password: String },
+    Bearer(String),
+    // Any other scheme, with its credentials left as sent.
+    Other(String, String),
+}
+impl Authorization {
+    pub fn parse(value: &str) -> Result<Authorization, ParseError> {
+	let invalid = || ParseError::InvalidAuthorization(value.to_string());
+	let (scheme, credentials) = value.trim().split_once(' ').unwrap_or((value.trim(), ""));
+	let credentials: &str = credentials.trim();
+	if scheme.is_empty() {
+	    return Err(invalid());
+	}
+	if scheme.eq_ignore_ascii_case("basic") {
+	    let decoded: Vec<u8> = base64::decode(credentials).ok_or_else(invalid)?;
+	    let decoded: String = String::from_utf8(decoded).map_err(|_| invalid())?;
+	    let (user, password) = decoded.split_once(':').ok_or_else(invalid)?;
+	    Ok(Authorization::Basic { user: user.to_string(), password: password.to_string() })
+	} else if scheme.eq_ignore_ascii_case("bearer") {
+	    if !is_token68(credentials) {
+		return Err(invalid());
+	    }
+	    Ok(Authorization::Bearer(credentials.to_string()))
+	} else {
+	    Ok(Authorization::Other(scheme.to_string(), credentials.to_string()))
+	}
+    }
+
+    pub fn basic(user: &str, password: &str) -> Authorization {
+	Authorization::Basic { user: user.to_string(), password: password.to_string() }
+    }
+
+    pub fn bearer(token: &str) -> Authorization {
+	Authorization::Bearer(token.to_string())
+    }
+}
+impl FromStr for Authorization {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Authorization, ParseError> {
+	Authorization::parse(value)
+    }
+}
+impl fmt::Display for Authorization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    Authorization::Basic { user, password } => write!(f, "Basic {}", base64::encode(format!("{}:{}", user, password).as_bytes())),
+	    Authorization::Bearer(token) => write!(f, "Bearer {}", token),
+	    Authorization::Other(scheme, credentials) if credentials.is_empty() => write!(f, "{}", scheme),
+	    Authorization::Other(scheme, credentials) => write!(f, "{} {}", scheme, credentials),
+	}
+    }
+}
+
+// token68 from RFC 9110 section 11.2.
+fn is_token68(value: &str) -> bool {
+    let data: &str = value.trim_end_matches('=');
+    !data.is_empty() && data.bytes().all(|byte| byte.is_ascii_alphanumeric() || b"-._~+/".contains(&byte))
+}
+
+#[cfg(test)]
+mod test_authorization {
+    use super::Authorization;
+    use crate::ParseError;
+
+    #[test]
+    fn test_parse() {
+	assert_eq!(Authorization::parse("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="), Ok(Authorization::basic("Aladdin", "open sesame")));
+	assert_eq!(Authorization::parse("basic dTpwOnc="), Ok(Authorization::basic("u", "p:w")));
+	assert_eq!(Authorization::parse("Bearer mF_9.B5f-4.1JqM"), Ok(Authorization::bearer("mF_9.B5f-4.1JqM")));
+	assert_eq!(Authorization::parse("Digest username=\"a\", realm=\"b\""), Ok(Authorization::Other("Digest".to_string(), "username=\"a\", realm=\"b\"".to_string())));
+	for value in ["", "Basic !!!", "Basic dXNlcg==", "Bearer", "Bearer a b"] {
+	    assert_eq!(Authorization::parse(value), Err(ParseError::InvalidAuthorization(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_display() {
+	assert_eq!(Authorization::basic("Aladdin", "open sesame").to_string(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+	assert_eq!(Authorization::bearer("abc").to_string(), "Bearer abc");
+    }
+}
@@ -0,0 +1,60 @@
+use crate::prelude::*;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Standard base64 (RFC 4648 section 4) with padding.
+pub(crate) fn encode(input: &[u8]) -> String {
+    let mut encoded: String = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+	let bytes: [u8; 3] = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+	let group: u32 = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+	for i in 0..4 {
+	    if i <= chunk.len() {
+		encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+	    } else {
+		encoded.push('=');
+	    }
+	}
+    }
+    encoded
+}
+
+// Decodes padded standard base64. Returns None on any malformed input.
+pub(crate) fn decode(input: &str) -> Option<Vec<u8>> {
+    let bytes: &[u8] = input.as_bytes();
+    if !bytes.len().is_multiple_of(4) {
+	return None;
+    }
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len() / 4 * 3);
+    for (index, chunk) in bytes.chunks(4).enumerate() {
+	let padding: usize = chunk.iter().rev().take_while(|byte| **byte == b'=').count();
+	if padding > 2 || (padding > 0 && index != bytes.len() / 4 - 1) {
+	    return None;
+	}
+	let mut group: u32 = 0;
+	for byte in &chunk[..4 - padding] {
+	    let value: usize = ALPHABET.iter().position(|c| c == byte)?;
+	    group = group << 6 | value as u32;
+	}
+	group <<= 6 * padding;
+	decoded.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test_base64 {
+    use super::{decode, encode};
+
+    #[test]
+    fn test_round_trip() {
+	for (plain, encoded) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")] {
+	    assert_eq!(encode(plain.as_bytes()), encoded);
+	    assert_eq!(decode(encoded), Some(plain.as_bytes().to_vec()));
+	}
+	assert_eq!(decode("Zm9"), None);
+	assert_eq!(decode("Zm=v"), None);
+	assert_eq!(decode("Zg==Zg=="), None);
+	assert_eq!(decode("Z!=="), None);
+    }
+}
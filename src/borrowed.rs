@@ -0,0 +1,265 @@
+use crate::prelude::*;
+use crate::chunked::{self, Decoder};
+use crate::{headers, scan, url, validate, HeaderMap, Method, ParseError, ParseWarning, ParserConfig, Query, Request, Uri, Version};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct HeaderRef<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+impl<'a> HeaderRef<'a> {
+    pub fn name(&self) -> &'a str {
+	self.name
+    }
+
+    pub fn value(&self) -> &'a str {
+	self.value
+    }
+}
+
+// A request parsed without copying: every field borrows from the input
+// buffer. Query pairs are left percent-encoded, and a chunked body is left
+// encoded; `to_request` decodes both the same way `Request::parse` does.
+#[derive(Clone, Debug)]
+pub struct RequestRef<'a> {
+    method: Method,
+    full_path: &'a str,
+    path: &'a str,
+    query: Vec<(&'a str, &'a str)>,
+    version: Version,
+    headers: Vec<HeaderRef<'a>>,
+    body: &'a [u8],
+    config: ParserConfig,
+}
+impl<'a> RequestRef<'a> {
+    pub fn parse(input: &'a str) -> Result<RequestRef<'a>, ParseError> {
+	RequestRef::parse_with_config(input, &ParserConfig::default())
+    }
+
+    pub fn parse_with_config(input: &'a str, config: &ParserConfig) -> Result<RequestRef<'a>, ParseError> {
+	let (head, body) = match scan::find_head_end(input.as_bytes()) {
+	    Some(end) => (&input[..end], &input.as_bytes()[end + 4..]),
+	    None => (input.trim_end_matches("\r\n"), &[][..]),
+	};
+	let mut lines = scan::lines(head, true);
+
+	let request_line: &str = lines.next().unwrap_or("");
+	let mut parts = request_line.split(' ');
+	let (method, full_path, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+	    (Some(method), Some(full_path), Some(version), None) if !method.is_empty() => (method, full_path, version),
+	    _ => return Err(ParseError::InvalidRequestLine(request_line.to_string())),
+	};
+	config.check_uri(full_path)?;
+	let method: Method = method.parse()?;
+	let version: Version = version.parse()?;
+
+	// Other target forms are validated by `Uri`, then the origin part is
+	// sliced out of the input so nothing is copied.
+	let origin: &'a str = if full_path.starts_with('/') {
+	    full_path
+	} else {
+	    let uri: Uri = Uri::parse(full_path)?;
+	    match full_path.find("://") {
+		Some(start) => {
+		    let rest: &'a str = &full_path[start + 3..];
+		    &rest[rest.find(['/', '?', '#']).unwrap_or(rest.len())..]
+		}
+		None if uri.is_asterisk() => full_path,
+		None => "",
+	    }
+	};
+	let origin: &'a str = origin.split('#').next().unwrap_or("");
+	let (path, query_string) = match origin.split_once('?') {
+	    Some((path, query_string)) => (path, Some(query_string)),
+	    None => (origin, None),
+	};
+	let path: &'a str = if path.is_empty() && full_path.contains("://") { "/" } else { path };
+	let mut query: Vec<(&'a str, &'a str)> = Vec::new();
+	if let Some(query_string) = query_string {
+	    for pair in query_string.split('&') {
+		match pair.split_once('=') {
+		    Some((name, value)) if !value.contains('=') => query.push((name, value)),
+		    _ => return Err(ParseError::MalformedQuery(pair.to_string())),
+		}
+	    }
+	}
+
+	let mut headers: Vec<HeaderRef<'a>> = Vec::new();
+	for (count, line) in lines.enumerate() {
+	    config.check_header_line(count, line)?;
+	    // Unfolding would mean copying the value out of the input.
+	    if headers::is_obs_fold(line) {
+		return Err(ParseError::ObsoleteLineFolding(line.to_string()));
+	    }
+	    match headers::split_header_line(line, true) {
+		Some((name, value)) => headers.push(HeaderRef { name, value }),
+		None => return Err(ParseError::MalformedHeader(line.to_string())),
+	    }
+	}
+
+	// The body is framed as in `Request::parse`: chunked coding runs to the
+	// end of the trailers, Content-Length caps it and must be valid and fully
+	// present, and otherwise it runs to the end of the input.
+	let transfer_encoding: Option<&HeaderRef<'a>> = headers.iter().rfind(|header| header.name.eq_ignore_ascii_case("transfer-encoding"));
+	let body: &'a [u8] = match transfer_encoding {
+	    _ if body.is_empty() => body,
+	    Some(header) if header.value.rsplit(',').next().unwrap_or("").trim().eq_ignore_ascii_case("chunked") => {
+		match Decoder::new().decode(body, config)? {
+		    Some(length) => &body[..length],
+		    None => return Err(ParseError::InvalidChunk("unexpected end of body".to_string())),
+		}
+	    }
+	    Some(_) => body,
+	    None => match headers.iter().find(|header| header.name.eq_ignore_ascii_case("content-length")) {
+		Some(header) => {
+		    let length: usize = usize::try_from(headers::parse_content_length(header.value)?).unwrap_or(usize::MAX);
+		    config.check_body_size(length)?;
+		    body.get(..length).ok_or_else(|| ParseError::IncompleteBody(format!("{} of {} bytes", body.len(), length)))?
+		}
+		None => body,
+	    },
+	};
+	config.check_body_size(body.len())?;
+	Ok(RequestRef {
+	    method,
+	    full_path,
+	    path,
+	    query,
+	    version,
+	    headers,
+	    body,
+	    config: *config,
+	})
+    }
+
+    pub fn method(&self) -> &Method {
+	&self.method
+    }
+
+    pub fn full_path(&self) -> &'a str {
+	self.full_path
+    }
+
+    pub fn path(&self) -> &'a str {
+	self.path
+    }
+
+    pub fn query(&self) -> &Vec<(&'a str, &'a str)> {
+	&self.query
+    }
+
+    // Raw values of a repeated parameter; `tag` also matches `tag[]`.
+    pub fn query_all(&self, name: &str) -> Vec<&'a str> {
+	let name: &str = name.strip_suffix("[]").unwrap_or(name);
+	self.query.iter()
+	    .filter(|(query_name, _)| query_name.strip_suffix("[]").unwrap_or(query_name) == name)
+	    .map(|(_, value)| *value)
+	    .collect()
+    }
+
+    pub fn version(&self) -> Version {
+	self.version
+    }
+
+    pub fn headers(&self) -> &Vec<HeaderRef<'a>> {
+	&self.headers
+    }
+
+    pub fn find_header(&self, name: &str) -> Option<&HeaderRef<'a>> {
+	self.headers.iter().find(|header| header.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn body(&self) -> &'a [u8] {
+	self.body
+    }
+
+    // Copies the borrowed fields into an owned `Request`.
+    pub fn to_request(&self) -> Request {
+	let mut request: Request = Request::new();
+	request.set_config(self.config);
+	request.method = self.method.clone();
+	request.full_path = self.full_path.to_string();
+	request.path = url::url_decode(self.path);
+	request.query = self.query.iter().map(|(name, value)| Query::new(url::decode_query(name), url::decode_query(value))).collect();
+	request.version = self.version;
+	let mut headers: HeaderMap = HeaderMap::new();
+	for header in &self.headers {
+	    if !headers::append_field(&mut headers, header.name, header.value) {
+		request.anomalies.extend(validate::dropped_duplicate(&headers, header.name, header.value));
+		request.warnings.push(ParseWarning::from(ParseError::DuplicateHeader(header.name.to_string())));
+	    }
+	}
+	// The chunked body was checked while parsing.
+	match headers::is_chunked(&headers).then(|| chunked::decode(self.body, &self.config)) {
+	    Some(Ok((body, trailers))) => (request.body, request.trailers) = (body, trailers),
+	    _ => request.body = self.body.to_vec(),
+	}
+	request.headers = headers;
+	request
+    }
+}
+
+#[cfg(test)]
+mod test_request_ref {
+    use super::RequestRef;
+    use crate::{Method, ParseError, Request, Version};
+
+    #[test]
+    fn test_parse() {
+	let input: String = "POST /a%20b?name=hello%20world&x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbody".to_string();
+	let request: RequestRef = RequestRef::parse(&input).unwrap();
+	assert_eq!(*request.method(), Method::POST);
+	assert_eq!(request.path(), "/a%20b");
+	assert_eq!(request.query(), &vec![("name", "hello%20world"), ("x", "1")]);
+	assert_eq!(request.version(), Version::Http11);
+	assert_eq!(request.headers().len(), 2);
+	assert_eq!(request.find_header("content-length").unwrap().value(), "4");
+	assert_eq!(request.body(), b"body");
+	assert_eq!(request.path().as_ptr(), input[5..].as_ptr());
+    }
+
+    #[test]
+    fn test_target_forms() {
+	let request: RequestRef = RequestRef::parse("GET http://example.com/a?b=c HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.path(), "/a");
+	assert_eq!(request.query(), &vec![("b", "c")]);
+	assert_eq!(RequestRef::parse("GET /?t=1&t[]=2 HTTP/1.1\r\n\r\n").unwrap().query_all("t"), vec!["1", "2"]);
+	assert_eq!(RequestRef::parse("CONNECT example.com:443 HTTP/1.1\r\n\r\n").unwrap().path(), "");
+	assert_eq!(RequestRef::parse("OPTIONS * HTTP/1.1\r\n\r\n").unwrap().path(), "*");
+	assert_eq!(RequestRef::parse("GET example.com HTTP/1.1\r\n\r\n").err(), Some(ParseError::InvalidUri("example.com".to_string())));
+    }
+
+    #[test]
+    fn test_to_request() {
+	let request: Request = RequestRef::parse("GET /a%20b?name=hello%20world HTTP/1.0\r\nHost: localhost\r\nhost: other\r\n\r\n").unwrap().to_request();
+	assert_eq!(request.path(), "/a b");
+	assert_eq!(request.find_query("name").unwrap().value(), "hello world");
+	assert_eq!(request.version(), &Version::Http10);
+	assert_eq!(request.headers().len(), 1);
+	assert_eq!(request.warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_body_framing() {
+	let request: RequestRef = RequestRef::parse("POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nhiGET / HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.body(), b"hi");
+	let input: &str = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 1\r\n\r\n2\r\nhi\r\n0\r\nExpires: never\r\n\r\nnext";
+	let request: RequestRef = RequestRef::parse(input).unwrap();
+	assert_eq!(request.body(), b"2\r\nhi\r\n0\r\nExpires: never\r\n\r\n");
+	let request: Request = request.to_request();
+	assert_eq!(request.body(), "hi");
+	assert_eq!(request.trailers().get("expires").unwrap().value(), "never");
+	assert_eq!(RequestRef::parse("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhi").err(), Some(ParseError::InvalidChunk("unexpected end of body".to_string())));
+	assert_eq!(RequestRef::parse("POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\nbody").err(), Some(ParseError::InvalidContentLength("ten".to_string())));
+	assert_eq!(RequestRef::parse("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").err(), Some(ParseError::IncompleteBody("5 of 10 bytes".to_string())));
+    }
+
+    #[test]
+    fn test_errors() {
+	assert_eq!(RequestRef::parse("GET /\r\n\r\n").err(), Some(ParseError::InvalidRequestLine("GET /".to_string())));
+	assert_eq!(RequestRef::parse("BR@W / HTTP/1.1\r\n\r\n").err(), Some(ParseError::UnsupportedMethod("BR@W".to_string())));
+	assert_eq!(RequestRef::parse("GET /?a HTTP/1.1\r\n\r\n").err(), Some(ParseError::MalformedQuery("a".to_string())));
+	assert_eq!(RequestRef::parse("GET / HTTP/1.1\r\nbroken\r\n\r\n").err(), Some(ParseError::MalformedHeader("broken".to_string())));
+	assert_eq!(RequestRef::parse("GET / HTTP/1.1\r\nX-A: 1\r\n 2\r\n\r\n").err(), Some(ParseError::ObsoleteLineFolding(" 2".to_string())));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{lint, BuildError, Encoding, Forwarded, Method, Request, Version};
+
+// Fluent construction of a `Request`; `build` checks that the result can be
+// serialized into a valid message.
+#[derive(Clone)]
+pub struct RequestBuilder {
+    request: Request,
+    has_path: bool,
+}
+impl Default for RequestBuilder {
+    fn default() -> RequestBuilder {
+	RequestBuilder::new()
+    }
+}
+impl RequestBuilder {
+    pub fn new() -> RequestBuilder {
+	RequestBuilder {
+	    request: Request::new(),
+	    has_path: false,
+	}
+    }
+
+    pub fn method(mut self, method: Method) -> RequestBuilder {
+	self.request.set_method(method);
+	self
+    }
+
+    pub fn path(mut self, path: &str) -> RequestBuilder {
+	self.request.set_path(path);
+	self.has_path = true;
+	self
+    }
+
+    pub fn version(mut self, version: Version) -> RequestBuilder {
+	self.request.set_version(version);
+	self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> RequestBuilder {
+	self.request.add_header(name, value);
+	self
+    }
+
+    pub fn basic_auth(mut self, user: &str, password: &str) -> RequestBuilder {
+	self.request.set_basic_auth(user, password);
+	self
+    }
+
+    pub fn bearer_auth(mut self, token: &str) -> RequestBuilder {
+	self.request.set_bearer_auth(token);
+	self
+    }
+
+    // Call after `version`, since the header written depends on it.
+    pub fn keep_alive(mut self, keep_alive: bool) -> RequestBuilder {
+	self.request.set_keep_alive(keep_alive);
+	self
+    }
+
+    pub fn forwarded(mut self, forwarded: &Forwarded) -> RequestBuilder {
+	self.request.append_forwarded(forwarded);
+	self
+    }
+
+    // Call after `version`, since the entry names the protocol version.
+    pub fn via(mut self, received_by: &str) -> RequestBuilder {
+	self.request.append_via(received_by);
+	self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn date_now(mut self) -> RequestBuilder {
+	self.request.set_date_now();
+	self
+    }
+
+    pub fn query(mut self, name: &str, value: &str) -> RequestBuilder {
+	self.request.add_query(name, value);
+	self
+    }
+
+    pub fn body(mut self, body: &str) -> RequestBuilder {
+	self.request.set_body(body);
+	self
+    }
+
+    pub fn body_bytes(mut self, body: &[u8]) -> RequestBuilder {
+	self.request.set_body_bytes(body);
+	self
+    }
+
+    // Sent after the body when written with `to_chunked_bytes`.
+    pub fn trailer(mut self, name: &str, value: &str) -> RequestBuilder {
+	self.request.set_trailer(name, value);
+	self
+    }
+
+    pub fn body_compressed(mut self, body: &[u8], encoding: Encoding) -> RequestBuilder {
+	self.request.set_body_compressed(body, encoding);
+	self
+    }
+
+    pub fn build(self) -> Result<Request, BuildError> {
+	if let Method::Extension(method) = &self.request.method {
+	    if method.is_empty() || !method.bytes().all(lint::is_token_char) {
+		return Err(BuildError::InvalidMethod(method.clone()));
+	    }
+	}
+	if !self.has_path || self.request.path.is_empty() {
+	    return Err(BuildError::MissingPath);
+	}
+	if self.request.path.chars().any(|c| c.is_whitespace() || c.is_control()) {
+	    return Err(BuildError::InvalidPath(self.request.path.clone()));
+	}
+	for header in self.request.headers.iter().chain(self.request.trailers.iter()) {
+	    if header.name().is_empty() || !header.name().bytes().all(lint::is_token_char) {
+		return Err(BuildError::InvalidHeaderName(header.name().clone()));
+	    }
+	    if header.value().bytes().any(|byte| byte == b'\r' || byte == b'\n' || byte == 0) {
+		return Err(BuildError::InvalidHeaderValue(header.name().clone()));
+	    }
+	}
+	Ok(self.request)
+    }
+}
+
+#[cfg(test)]
+mod test_request_builder {
+    use crate::{BuildError, Method, Request, Version};
+
+    #[test]
+    fn test_build() {
+	let request: Request = Request::builder()
+	    .method(Method::POST)
+	    .path("/api")
+	    .header("Accept", "*/*")
+	    .query("k", "v")
+	    .body("data")
+	    .build()
+	    .unwrap();
+	assert_eq!(*request.method(), Method::POST);
+	assert_eq!(request.build(), "POST /api?k=v HTTP/1.1\r\nAccept: */*\r\nContent-Length: 4\r\n\r\ndata");
+    }
+
+    #[test]
+    fn test_auth() {
+	let request: Request = Request::builder().path("/").bearer_auth("abc").build().unwrap();
+	assert_eq!(request.find_header("Authorization").unwrap().value(), "Bearer abc");
+	let request: Request = Request::builder().path("/").basic_auth("u", "p").build().unwrap();
+	assert_eq!(request.find_header("Authorization").unwrap().value(), "Basic dTpw");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_body_compressed() {
+	let request: Request = Request::builder().path("/").body_compressed(b"data", crate::Encoding::Gzip).build().unwrap();
+	assert_eq!(request.find_header("Content-Encoding").unwrap().value(), "gzip");
+	assert_eq!(request.decoded_body().unwrap().as_ref(), b"data");
+    }
+
+    #[test]
+    fn test_keep_alive() {
+	let request: Request = Request::builder().path("/").keep_alive(false).build().unwrap();
+	assert_eq!(request.build(), "GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+	let request: Request = Request::builder().path("/").version(Version::Http10).keep_alive(true).build().unwrap();
+	assert_eq!(request.build(), "GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_date_now() {
+	let request: Request = Request::builder().path("/").date_now().build().unwrap();
+	assert!(request.date().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_validation() {
+	assert_eq!(Request::builder().method(Method::GET).build().err(), Some(BuildError::MissingPath));
+	assert_eq!(Request::builder().method(Method::Extension("BAD METHOD".to_string())).path("/").build().err(), Some(BuildError::InvalidMethod("BAD METHOD".to_string())));
+	assert_eq!(Request::builder().path("/a b").build().err(), Some(BuildError::InvalidPath("/a b".to_string())));
+	assert_eq!(Request::builder().path("/").header("Bad Name", "x").build().err(), Some(BuildError::InvalidHeaderName("Bad Name".to_string())));
+	assert_eq!(Request::builder().path("/").header("X-Injected", "a\r\nHost: evil").build().err(), Some(BuildError::InvalidHeaderValue("X-Injected".to_string())));
+    }
+}
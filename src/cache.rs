@@ -0,0 +1,486 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::media_type::{is_token, unquote};
+use crate::{HttpDate, ParseError, Request, Response};
+
+// Status codes a cache may assign a heuristic lifetime (RFC 9110 section
+// 15.1).
+const HEURISTICALLY_CACHEABLE: [u16; 12] = [200, 203, 204, 206, 300, 301, 308, 404, 405, 410, 414, 501];
+
+// A `Cache-Control` header. Directive names are lowercased and arguments
+// unquoted; unknown directives are kept so the value round-trips.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct CacheControl {
+    directives: Vec<(String, Option<String>)>,
+}
+impl CacheControl {
+    pub fn new() -> CacheControl {
+	CacheControl { directives: Vec::new() }
+    }
+
+    pub fn parse(value: &str) -> Result<CacheControl, ParseError> {
+	let invalid = || ParseError::InvalidCacheControl(value.to_string());
+	let mut directives: Vec<(String, Option<String>)> = Vec::new();
+	let mut remaining: &str = value.trim();
+	while !remaining.is_empty() {
+	    let end: usize = remaining.find([',', '=']).unwrap_or(remaining.len());
+	    let name: &str = remaining[..end].trim();
+	    remaining = &remaining[end..];
+	    let argument: Option<String> = match remaining.strip_prefix('=') {
+		Some(after) => {
+		    let after: &str = after.trim_start();
+		    if let Some(quoted) = after.strip_prefix('"') {
+			let (unquoted, length) = unquote(quoted).ok_or_else(invalid)?;
+			remaining = &quoted[length..];
+			Some(unquoted)
+		    } else {
+			let end: usize = after.find(',').unwrap_or(after.len());
+			if !is_token(after[..end].trim()) {
+			    return Err(invalid());
+			}
+			remaining = &after[end..];
+			Some(after[..end].trim().to_string())
+		    }
+		}
+		None => None,
+	    };
+	    let rest: &str = remaining.trim_start();
+	    remaining = match rest.strip_prefix(',') {
+		Some(rest) => rest.trim_start(),
+		None if rest.is_empty() => rest,
+		None => return Err(invalid()),
+	    };
+	    // Empty list elements are allowed by the `#rule` list syntax.
+	    if name.is_empty() && argument.is_none() {
+		continue;
+	    }
+	    if !is_token(name) {
+		return Err(invalid());
+	    }
+	    directives.push((name.to_ascii_lowercase(), argument));
+	}
+	Ok(CacheControl { directives })
+    }
+
+    pub fn directives(&self) -> &Vec<(String, Option<String>)> {
+	&self.directives
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+	self.directives.iter().any(|(directive, _)| directive.eq_ignore_ascii_case(name))
+    }
+
+    // The argument of a directive; None when it is absent or has none.
+    pub fn directive(&self, name: &str) -> Option<&str> {
+	self.directives.iter().find(|(directive, _)| directive.eq_ignore_ascii_case(name)).and_then(|(_, argument)| argument.as_deref())
+    }
+
+    pub fn set_directive(&mut self, name: &str, argument: Option<&str>) {
+	let argument: Option<String> = argument.map(str::to_string);
+	match self.directives.iter_mut().find(|(directive, _)| directive.eq_ignore_ascii_case(name)) {
+	    Some(directive) => directive.1 = argument,
+	    None => self.directives.push((name.to_ascii_lowercase(), argument)),
+	}
+    }
+
+    pub fn remove_directive(&mut self, name: &str) {
+	self.directives.retain(|(directive, _)| !directive.eq_ignore_ascii_case(name));
+    }
+
+    pub fn max_age(&self) -> Option<u64> {
+	self.seconds("max-age")
+    }
+
+    pub fn s_maxage(&self) -> Option<u64> {
+	self.seconds("s-maxage")
+    }
+
+    pub fn min_fresh(&self) -> Option<u64> {
+	self.seconds("min-fresh")
+    }
+
+    // `max-stale` may be sent without a limit, meaning any staleness.
+    pub fn max_stale(&self) -> Option<u64> {
+	match self.contains("max-stale") {
+	    true => Some(self.seconds("max-stale").unwrap_or(u64::MAX)),
+	    false => None,
+	}
+    }
+
+    pub fn no_cache(&self) -> bool {
+	self.contains("no-cache")
+    }
+
+    pub fn no_store(&self) -> bool {
+	self.contains("no-store")
+    }
+
+    pub fn no_transform(&self) -> bool {
+	self.contains("no-transform")
+    }
+
+    pub fn only_if_cached(&self) -> bool {
+	self.contains("only-if-cached")
+    }
+
+    pub fn must_revalidate(&self) -> bool {
+	self.contains("must-revalidate")
+    }
+
+    pub fn is_public(&self) -> bool {
+	self.contains("public")
+    }
+
+    pub fn is_private(&self) -> bool {
+	self.contains("private")
+    }
+
+    pub fn immutable(&self) -> bool {
+	self.contains("immutable")
+    }
+
+    fn seconds(&self, name: &str) -> Option<u64> {
+	self.directive(name).and_then(CacheControl::delta_seconds)
+    }
+
+    // delta-seconds is 1*DIGIT; values too large for a u64 saturate.
+    fn delta_seconds(value: &str) -> Option<u64> {
+	let value: &str = value.trim();
+	if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+	    return None;
+	}
+	Some(value.parse().unwrap_or(u64::MAX))
+    }
+}
+impl FromStr for CacheControl {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<CacheControl, ParseError> {
+	CacheControl::parse(value)
+    }
+}
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	let directives: Vec<String> = self.directives.iter()
+	    .map(|(name, argument)| match argument {
+		Some(argument) if is_token(argument) => format!("{}={}", name, argument),
+		Some(argument) => format!("{}=\"{}\"", name, argument.replace('\\', "\\\\").replace('"', "\\\"")),
+		None => name.clone(),
+	    })
+	    .collect();
+	write!(f, "{}", directives.join(", "))
+    }
+}
+
+// An entity tag, as sent in `ETag` and the `If-Match`/`If-None-Match` lists.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+impl ETag {
+    pub fn strong(tag: &str) -> ETag {
+	ETag { tag: tag.to_string(), weak: false }
+    }
+
+    pub fn weak(tag: &str) -> ETag {
+	ETag { tag: tag.to_string(), weak: true }
+    }
+
+    pub fn parse(value: &str) -> Result<ETag, ParseError> {
+	match parse_etag(value.trim()) {
+	    Some((etag, "")) => Ok(etag),
+	    _ => Err(ParseError::InvalidETag(value.to_string())),
+	}
+    }
+
+    // The opaque tag, without quotes or the weak prefix.
+    pub fn tag(&self) -> &str {
+	&self.tag
+    }
+
+    pub fn is_weak(&self) -> bool {
+	self.weak
+    }
+
+    // Strong comparison: both tags are strong and identical. Used by
+    // `If-Match` and range requests.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+	!self.weak && !other.weak && self.tag == other.tag
+    }
+
+    // Weak comparison: the tags are identical, either may be weak. Used by
+    // `If-None-Match`.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+	self.tag == other.tag
+    }
+}
+impl FromStr for ETag {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<ETag, ParseError> {
+	ETag::parse(value)
+    }
+}
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self.weak {
+	    true => write!(f, "W/\"{}\"", self.tag),
+	    false => write!(f, "\"{}\"", self.tag),
+	}
+    }
+}
+
+// The value of `If-Match` or `If-None-Match`: `*` or a list of entity tags.
+#[derive(PartialEq, Clone, Debug)]
+pub enum ETagMatch {
+    Any,
+    Tags(Vec<ETag>),
+}
+impl ETagMatch {
+    pub fn parse(value: &str) -> Result<ETagMatch, ParseError> {
+	let invalid = || ParseError::InvalidETag(value.to_string());
+	let mut remaining: &str = value.trim();
+	if remaining == "*" {
+	    return Ok(ETagMatch::Any);
+	}
+	let mut tags: Vec<ETag> = Vec::new();
+	while !remaining.is_empty() {
+	    if let Some(rest) = remaining.strip_prefix(',') {
+		remaining = rest.trim_start();
+		continue;
+	    }
+	    let (etag, rest) = parse_etag(remaining).ok_or_else(invalid)?;
+	    let rest: &str = rest.trim_start();
+	    if !rest.is_empty() && !rest.starts_with(',') {
+		return Err(invalid());
+	    }
+	    tags.push(etag);
+	    remaining = rest;
+	}
+	if tags.is_empty() {
+	    return Err(invalid());
+	}
+	Ok(ETagMatch::Tags(tags))
+    }
+
+    // `If-Match` semantics: `*` or a strongly matching tag.
+    pub fn matches_strong(&self, etag: &ETag) -> bool {
+	match self {
+	    ETagMatch::Any => true,
+	    ETagMatch::Tags(tags) => tags.iter().any(|tag| tag.strong_eq(etag)),
+	}
+    }
+
+    // `If-None-Match` semantics: `*` or a weakly matching tag.
+    pub fn matches_weak(&self, etag: &ETag) -> bool {
+	match self {
+	    ETagMatch::Any => true,
+	    ETagMatch::Tags(tags) => tags.iter().any(|tag| tag.weak_eq(etag)),
+	}
+    }
+}
+impl FromStr for ETagMatch {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<ETagMatch, ParseError> {
+	ETagMatch::parse(value)
+    }
+}
+impl fmt::Display for ETagMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ETagMatch::Any => write!(f, "*"),
+	    ETagMatch::Tags(tags) => write!(f, "{}", tags.iter().map(ETag::to_string).collect::<Vec<String>>().join(", ")),
+	}
+    }
+}
+
+// Expiry math for a stored response (RFC 9111 section 4.2). Times are when
+// the request was sent and the response received, and a response that came
+// without a Date header should be given one on receipt.
+impl Response {
+    // How long the response stays fresh after its Date, in seconds: s-maxage
+    // for a shared cache, then max-age, then Expires, then a tenth of the time
+    // since Last-Modified for heuristically cacheable statuses. None when
+    // nothing gives a lifetime.
+    pub fn freshness_lifetime(&self, shared: bool) -> Option<u64> {
+	let cache_control: CacheControl = self.cache_control().ok().flatten().unwrap_or_default();
+	if let Some(seconds) = cache_control.s_maxage().filter(|_| shared) {
+	    return Some(seconds);
+	}
+	if let Some(seconds) = cache_control.max_age() {
+	    return Some(seconds);
+	}
+	let date: Option<HttpDate> = self.date().ok().flatten();
+	if let Some(expires) = self.find_header("expires") {
+	    // An invalid Expires means already expired.
+	    return match (HttpDate::parse(expires.value()), date) {
+		(Ok(expires), Some(date)) => Some(expires.unix_seconds().saturating_sub(date.unix_seconds())),
+		(Err(_), _) => Some(0),
+		(Ok(_), None) => None,
+	    };
+	}
+	let heuristic: bool = HEURISTICALLY_CACHEABLE.contains(&self.status().as_u16()) || cache_control.is_public();
+	match (self.last_modified().ok().flatten(), date) {
+	    (Some(last_modified), Some(date)) if heuristic => Some(date.unix_seconds().saturating_sub(last_modified.unix_seconds()) / 10),
+	    _ => None,
+	}
+    }
+
+    // The age of the response at `now`, in seconds, allowing for the Age
+    // header of upstream caches and for the time the request took.
+    pub fn current_age(&self, request_time: HttpDate, response_time: HttpDate, now: HttpDate) -> u64 {
+	let age: u64 = self.find_header("age").and_then(|header| CacheControl::delta_seconds(header.value())).unwrap_or(0);
+	let date: u64 = self.date().ok().flatten().map_or(response_time.unix_seconds(), |date| date.unix_seconds());
+	let apparent_age: u64 = response_time.unix_seconds().saturating_sub(date);
+	let response_delay: u64 = response_time.unix_seconds().saturating_sub(request_time.unix_seconds());
+	let corrected_initial_age: u64 = apparent_age.max(age.saturating_add(response_delay));
+	corrected_initial_age.saturating_add(now.unix_seconds().saturating_sub(response_time.unix_seconds()))
+    }
+
+    // Whether the response may answer `request` at `now` without being
+    // validated with the origin. `no-cache` on either side requires
+    // validation; the request's max-age, min-fresh and max-stale are
+    // honoured, the last only when the response allows serving it stale.
+    pub fn is_fresh(&self, request: &Request, shared: bool, request_time: HttpDate, response_time: HttpDate, now: HttpDate) -> bool {
+	let response_directives: CacheControl = self.cache_control().ok().flatten().unwrap_or_default();
+	let request_directives: CacheControl = request.cache_control().ok().flatten().unwrap_or_default();
+	if response_directives.no_cache() || request_directives.no_cache() {
+	    return false;
+	}
+	let lifetime: u64 = self.freshness_lifetime(shared).unwrap_or(0);
+	let age: u64 = self.current_age(request_time, response_time, now);
+	if request_directives.max_age().is_some_and(|max_age| age > max_age) {
+	    return false;
+	}
+	let needed: u64 = age.saturating_add(request_directives.min_fresh().unwrap_or(0));
+	if lifetime > needed {
+	    return true;
+	}
+	let revalidate: bool = response_directives.must_revalidate() || (shared && response_directives.contains("proxy-revalidate"));
+	!revalidate && request_directives.max_stale().is_some_and(|max_stale| needed - lifetime <= max_stale)
+    }
+}
+
+// Parses one entity tag at the start of `value` and returns the rest.
+// etagc is any visible character except the double quote.
+fn parse_etag(value: &str) -> Option<(ETag, &str)> {
+    let (weak, quoted) = match value.strip_prefix("W/") {
+	Some(quoted) => (true, quoted),
+	None => (false, value),
+    };
+    let quoted: &str = quoted.strip_prefix('"')?;
+    let end: usize = quoted.find('"')?;
+    let tag: &str = &quoted[..end];
+    if tag.bytes().any(|byte| byte <= b' ' || byte == 0x7F) {
+	return None;
+    }
+    Some((ETag { tag: tag.to_string(), weak }, &quoted[end + 1..]))
+}
+
+#[cfg(test)]
+mod test_cache {
+    use super::{CacheControl, ETag, ETagMatch};
+    use crate::{HttpDate, ParseError, Request, Response};
+
+    #[test]
+    fn test_cache_control() {
+	let cache_control: CacheControl = CacheControl::parse("Public, max-age=3600, no-cache=\"Set-Cookie, Vary\",, must-revalidate, max-stale").unwrap();
+	assert!(cache_control.is_public());
+	assert!(!cache_control.is_private());
+	assert!(cache_control.no_cache());
+	assert!(cache_control.must_revalidate());
+	assert_eq!(cache_control.max_age(), Some(3600));
+	assert_eq!(cache_control.max_stale(), Some(u64::MAX));
+	assert_eq!(cache_control.s_maxage(), None);
+	assert_eq!(cache_control.directive("no-cache"), Some("Set-Cookie, Vary"));
+	assert_eq!(cache_control.to_string(), "public, max-age=3600, no-cache=\"Set-Cookie, Vary\", must-revalidate, max-stale");
+
+	let mut cache_control: CacheControl = CacheControl::new();
+	cache_control.set_directive("no-store", None);
+	cache_control.set_directive("max-age", Some("0"));
+	cache_control.set_directive("max-age", Some("60"));
+	assert_eq!(cache_control.to_string(), "no-store, max-age=60");
+	assert_eq!(CacheControl::parse("max-age=soon").unwrap().max_age(), None);
+
+	for value in ["max-age=\"1", "no cache", "max-age=a b", "=5"] {
+	    assert_eq!(CacheControl::parse(value), Err(ParseError::InvalidCacheControl(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_freshness() {
+	let at = HttpDate::from_unix_seconds;
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:01:40 GMT\r\nCache-Control: max-age=60, s-maxage=600\r\nExpires: Thu, 01 Jan 1970 00:16:40 GMT\r\nAge: 10\r\n\r\n").unwrap();
+	assert_eq!(response.freshness_lifetime(false), Some(60));
+	assert_eq!(response.freshness_lifetime(true), Some(600));
+	// Sent at 100, received at 102 and served at 130: the Age plus the
+	// request delay beats the apparent age of 2.
+	assert_eq!(response.current_age(at(100), at(102), at(130)), 40);
+
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:01:40 GMT\r\nExpires: Thu, 01 Jan 1970 00:03:20 GMT\r\n\r\n").unwrap();
+	assert_eq!(response.freshness_lifetime(false), Some(100));
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:01:40 GMT\r\nExpires: 0\r\n\r\n").unwrap();
+	assert_eq!(response.freshness_lifetime(false), Some(0));
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:16:40 GMT\r\nLast-Modified: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n").unwrap();
+	assert_eq!(response.freshness_lifetime(false), Some(100));
+	let response: Response = Response::parse("HTTP/1.1 201 Created\r\nDate: Thu, 01 Jan 1970 00:16:40 GMT\r\nLast-Modified: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n").unwrap();
+	assert_eq!(response.freshness_lifetime(false), None);
+    }
+
+    #[test]
+    fn test_is_fresh() {
+	let at = HttpDate::from_unix_seconds;
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:01:40 GMT\r\nCache-Control: max-age=60\r\n\r\n").unwrap();
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	assert!(response.is_fresh(&request, false, at(100), at(100), at(159)));
+	assert!(!response.is_fresh(&request, false, at(100), at(100), at(160)));
+
+	let fresh = |cache_control: &str, now: u64| {
+	    let request: Request = Request::parse(&format!("GET / HTTP/1.1\r\nHost: a\r\nCache-Control: {}\r\n\r\n", cache_control)).unwrap();
+	    response.is_fresh(&request, false, at(100), at(100), at(now))
+	};
+	assert!(!fresh("no-cache", 100));
+	assert!(!fresh("max-age=10", 120));
+	assert!(!fresh("min-fresh=30", 140));
+	assert!(fresh("max-stale=30", 185));
+	assert!(!fresh("max-stale=30", 195));
+	assert!(fresh("max-stale", 10000));
+
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:01:40 GMT\r\nCache-Control: max-age=60, must-revalidate\r\n\r\n").unwrap();
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\nCache-Control: max-stale\r\n\r\n").unwrap();
+	assert!(!response.is_fresh(&request, false, at(100), at(100), at(170)));
+    }
+
+    #[test]
+    fn test_etag() {
+	assert_eq!(ETag::parse("\"xyzzy\""), Ok(ETag::strong("xyzzy")));
+	assert_eq!(ETag::parse(" W/\"r2d2\" "), Ok(ETag::weak("r2d2")));
+	assert_eq!(ETag::weak("r2d2").to_string(), "W/\"r2d2\"");
+	assert!(ETag::strong("1").strong_eq(&ETag::strong("1")));
+	assert!(!ETag::weak("1").strong_eq(&ETag::strong("1")));
+	assert!(ETag::weak("1").weak_eq(&ETag::strong("1")));
+	assert!(!ETag::weak("1").weak_eq(&ETag::weak("2")));
+	for value in ["xyzzy", "\"a", "w/\"a\"", "\"a\"b", "\"a b\""] {
+	    assert_eq!(ETag::parse(value), Err(ParseError::InvalidETag(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_etag_match() {
+	assert_eq!(ETagMatch::parse(" * "), Ok(ETagMatch::Any));
+	let tags: ETagMatch = ETagMatch::parse("\"a,b\", W/\"c\"").unwrap();
+	assert_eq!(tags, ETagMatch::Tags(vec![ETag::strong("a,b"), ETag::weak("c")]));
+	assert_eq!(tags.to_string(), "\"a,b\", W/\"c\"");
+	assert!(tags.matches_strong(&ETag::strong("a,b")));
+	assert!(!tags.matches_strong(&ETag::strong("c")));
+	assert!(tags.matches_weak(&ETag::strong("c")));
+	assert!(ETagMatch::Any.matches_strong(&ETag::weak("anything")));
+	for value in ["", "\"a\" \"b\"", "*, \"a\""] {
+	    assert_eq!(ETagMatch::parse(value), Err(ParseError::InvalidETag(value.to_string())));
+	}
+    }
+}
@@ -0,0 +1,164 @@
+use crate::prelude::*;
+use crate::{headers, scan, HeaderMap, ParseError, ParseMode, ParserConfig};
+
+// Parses a chunk-size line, ignoring chunk extensions, and checks that the
+// chunk still fits in the body limit. The size is 1*HEXDIG; signs and
+// padding are rejected, and only whitespace before a `;` may follow it.
+pub(crate) fn chunk_size(line: &str, received: usize, config: &ParserConfig) -> Result<usize, ParseError> {
+    let invalid = || ParseError::InvalidChunk(line.to_string());
+    let digits: usize = line.bytes().take_while(u8::is_ascii_hexdigit).count();
+    let rest: &str = line[digits..].trim_start_matches([' ', '\t']);
+    if digits == 0 || !(line.len() == digits || rest.starts_with(';')) {
+	return Err(invalid());
+    }
+    let size: usize = usize::from_str_radix(&line[..digits], 16).map_err(|_| invalid())?;
+    config.check_body_size(received.checked_add(size).ok_or_else(invalid)?)?;
+    Ok(size)
+}
+
+// Adds a trailer field line from after the last chunk.
+pub(crate) fn parse_trailer(line: &str, trailers: &mut HeaderMap, config: &ParserConfig) -> Result<(), ParseError> {
+    match headers::split_header_line(line, config.mode() == ParseMode::Strict) {
+	Some((name, value)) => {
+	    trailers.append(name, value);
+	    Ok(())
+	}
+	None => Err(ParseError::MalformedHeader(line.to_string())),
+    }
+}
+
+// Encodes `body` in chunks of at most `chunk_size` bytes, followed by the
+// last chunk and the trailer section.
+pub(crate) fn encode(body: &[u8], chunk_size: usize, trailers: &HeaderMap) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for chunk in body.chunks(chunk_size.max(1)) {
+	bytes.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+	bytes.extend_from_slice(chunk);
+	bytes.extend_from_slice(b"\r\n");
+    }
+    let mut end: String = "0\r\n".to_string();
+    headers::write_header_lines(&mut end, trailers, None);
+    end.push_str("\r\n");
+    bytes.extend_from_slice(end.as_bytes());
+    bytes
+}
+
+// Decodes a chunked body that should be complete in `bytes`, returning it
+// with its trailers.
+pub(crate) fn decode(bytes: &[u8], config: &ParserConfig) -> Result<(Vec<u8>, HeaderMap), ParseError> {
+    let mut decoder: Decoder = Decoder::new();
+    match decoder.decode(bytes, config)? {
+	Some(_) => Ok(decoder.into_parts()),
+	None => Err(ParseError::InvalidChunk("unexpected end of body".to_string())),
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum State {
+    Size,
+    Data(usize),
+    DataEnd,
+    Trailers,
+    Done,
+}
+
+// Decodes a chunked body as it arrives. Each call to `decode` is handed
+// everything received since the head and resumes where the last one stopped.
+pub(crate) struct Decoder {
+    state: State,
+    offset: usize,
+    body: Vec<u8>,
+    trailers: HeaderMap,
+}
+impl Decoder {
+    pub(crate) fn new() -> Decoder {
+	Decoder {
+	    state: State::Size,
+	    offset: 0,
+	    body: Vec::new(),
+	    trailers: HeaderMap::new(),
+	}
+    }
+
+    // The number of bytes the encoded body spans once its last chunk and
+    // trailers are in, or None while more input is needed.
+    pub(crate) fn decode(&mut self, bytes: &[u8], config: &ParserConfig) -> Result<Option<usize>, ParseError> {
+	loop {
+	    match self.state {
+		State::Size => {
+		    let line: String = match self.line(bytes, config)? {
+			Some(line) => line,
+			None => return Ok(None),
+		    };
+		    let size: usize = chunk_size(&line, self.body.len(), config)?;
+		    self.state = if size == 0 { State::Trailers } else { State::Data(size) };
+		}
+		State::Data(remaining) => {
+		    let available: &[u8] = &bytes[self.offset..];
+		    let taken: usize = remaining.min(available.len());
+		    self.body.extend_from_slice(&available[..taken]);
+		    self.offset += taken;
+		    if taken < remaining {
+			self.state = State::Data(remaining - taken);
+			return Ok(None);
+		    }
+		    self.state = State::DataEnd;
+		}
+		State::DataEnd => match self.line(bytes, config)? {
+		    Some(line) if line.is_empty() => self.state = State::Size,
+		    Some(line) => return Err(ParseError::InvalidChunk(line)),
+		    None => return Ok(None),
+		},
+		State::Trailers => match self.line(bytes, config)? {
+		    Some(line) if line.is_empty() => self.state = State::Done,
+		    Some(line) => parse_trailer(&line, &mut self.trailers, config)?,
+		    None => return Ok(None),
+		},
+		State::Done => return Ok(Some(self.offset)),
+	    }
+	}
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<u8>, HeaderMap) {
+	(self.body, self.trailers)
+    }
+
+    fn line(&mut self, bytes: &[u8], config: &ParserConfig) -> Result<Option<String>, ParseError> {
+	let rest: &[u8] = &bytes[self.offset..];
+	match scan::find_crlf(rest) {
+	    Some(end) => {
+		config.check_line_length(end + 2)?;
+		self.offset += end + 2;
+		Ok(Some(String::from_utf8_lossy(&rest[..end]).into_owned()))
+	    }
+	    None => {
+		config.check_line_length(rest.len())?;
+		Ok(None)
+	    }
+	}
+    }
+}
+
+#[cfg(test)]
+mod test_chunked {
+    use super::Decoder;
+    use crate::prelude::*;
+    use crate::{ParseError, ParserConfig};
+
+    #[test]
+    fn test_decoder() {
+	let input: &[u8] = b"5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nExpires: never\r\n\r\nnext";
+	let config: ParserConfig = ParserConfig::new();
+	let mut decoder: Decoder = Decoder::new();
+	for end in 0..input.len() - 4 {
+	    assert_eq!(decoder.decode(&input[..end], &config), Ok(None));
+	}
+	assert_eq!(decoder.decode(input, &config), Ok(Some(input.len() - 4)));
+	let (body, trailers) = decoder.into_parts();
+	assert_eq!(body, b"hello, world");
+	assert_eq!(&trailers["expires"], "never");
+
+	let mut decoder: Decoder = Decoder::new();
+	assert_eq!(decoder.decode(b"2\r\nabc\r\n0\r\n\r\n", &config), Err(ParseError::InvalidChunk("c".to_string())));
+    }
+}
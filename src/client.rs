@@ -0,0 +1,888 @@
+use core::fmt;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{encoding, headers, reader, CookieJar, HttpDate, Method, ParseError, Request, Response, StatusCode, Uri};
+
+// Unix domain sockets are reached through `send_unix` and
+// `Client::send_unix`, which take the socket path separately, rather than
+// through `unix:` targets. `send` takes `ToSocketAddrs`, which cannot name a
+// path, and a request target has no standard way to carry one next to the
+// HTTP path, so the Host header and target stay what the server expects.
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum ClientError {
+    Io(String),
+    // Only returned when connecting over TLS.
+    Tls(String),
+    Response(ParseError),
+    // More redirects were returned than `max_redirects` allows.
+    TooManyRedirects(usize),
+    // The request does not say where to connect, e.g. it has no host or an
+    // unsupported scheme.
+    InvalidTarget(String),
+}
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ClientError::Io(message) => write!(f, "Connection failed: `{}`", message),
+	    ClientError::Tls(message) => write!(f, "TLS failed: `{}`", message),
+	    ClientError::TooManyRedirects(max) => write!(f, "More than {} redirects", max),
+	    ClientError::InvalidTarget(message) => write!(f, "Invalid target: `{}`", message),
+	    ClientError::Response(err) => write!(f, "Invalid response: {}", err),
+	}
+    }
+}
+impl core::error::Error for ClientError {}
+impl From<ParseError> for ClientError {
+    fn from(err: ParseError) -> ClientError {
+	ClientError::Response(err)
+    }
+}
+
+// Sends `request` over a new TCP connection to `addr` and reads the
+// response. One request is made per connection, so `Connection: close` is
+// sent. The response is read with the request's parser limits.
+pub fn send<A: ToSocketAddrs>(request: &Request, addr: A) -> Result<Response, ClientError> {
+    let stream: TcpStream = TcpStream::connect(addr).map_err(|err| ClientError::Io(err.to_string()))?;
+    exchange(request, stream)
+}
+
+// Like `send`, over TLS. The certificate is checked against the Mozilla root
+// store for the host in the request's target or Host header, which is also
+// sent as SNI.
+#[cfg(feature = "rustls")]
+pub fn send_tls<A: ToSocketAddrs>(request: &Request, addr: A) -> Result<Response, ClientError> {
+    send_tls_with_config(request, addr, default_tls_config())
+}
+
+// Like `send_tls`, with a custom rustls configuration, e.g. to trust a
+// private certificate authority or present a client certificate.
+#[cfg(feature = "rustls")]
+pub fn send_tls_with_config<A: ToSocketAddrs>(request: &Request, addr: A, config: Arc<rustls::ClientConfig>) -> Result<Response, ClientError> {
+    let host: String = request.host()?.ok_or_else(|| ClientError::Tls("request has no host".to_string()))?;
+    let stream: TcpStream = TcpStream::connect(addr).map_err(|err| ClientError::Io(err.to_string()))?;
+    exchange(request, tls_stream(&host, config, stream)?)
+}
+
+// Like `send`, over the Unix domain socket at `path`, e.g.
+// `/var/run/docker.sock`. The Host header is sent as given.
+#[cfg(unix)]
+pub fn send_unix<P: AsRef<Path>>(request: &Request, path: P) -> Result<Response, ClientError> {
+    let stream: UnixStream = UnixStream::connect(path).map_err(|err| ClientError::Io(err.to_string()))?;
+    exchange(request, stream)
+}
+
+#[cfg(feature = "rustls")]
+pub(crate) fn default_tls_config() -> Arc<rustls::ClientConfig> {
+    let roots: rustls::RootCertStore = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    Arc::new(rustls::ClientConfig::builder().with_root_certificates(roots).with_no_client_auth())
+}
+
+#[cfg(feature = "rustls")]
+fn tls_stream<S: Read + Write>(host: &str, config: Arc<rustls::ClientConfig>, stream: S) -> Result<rustls::StreamOwned<rustls::ClientConnection, S>, ClientError> {
+    let tls_error = |err: &dyn fmt::Display| ClientError::Tls(err.to_string());
+    let name: rustls::pki_types::ServerName<'static> = rustls::pki_types::ServerName::try_from(host.trim_start_matches('[').trim_end_matches(']').to_string()).map_err(|err| tls_error(&err))?;
+    let connection: rustls::ClientConnection = rustls::ClientConnection::new(config, name).map_err(|err| tls_error(&err))?;
+    Ok(rustls::StreamOwned::new(connection, stream))
+}
+
+// A client that keeps connections alive between requests and reuses them
+// for later requests to the same scheme, host and port. The target comes
+// from an absolute-form request target, or else from the Host header over
+// plain HTTP; a missing Host header is filled in. Redirects are followed,
+// and cookies set by responses are kept in a jar and sent back. Failed
+// requests are only retried under a `RetryPolicy`. Compressed responses are
+// decoded when the client asked for them with `Accept-Encoding`. On Unix,
+// `send_unix` sends plain HTTP over a socket file instead of TCP.
+//
+//     let client: Client = Client::new();
+//     let response: Response = client.send(&Request::parse("GET http://example.com/ HTTP/1.1\r\n\r\n")?)?;
+pub struct Client {
+    idle: Mutex<Vec<Idle>>,
+    idle_timeout: Duration,
+    max_idle_per_host: usize,
+    max_redirects: usize,
+    cookies: Option<Arc<Mutex<CookieJar>>>,
+    retry: Option<RetryPolicy>,
+    accept_encoding: Option<String>,
+    #[cfg(feature = "rustls")]
+    tls: Arc<rustls::ClientConfig>,
+}
+impl Default for Client {
+    fn default() -> Client {
+	Client::new()
+    }
+}
+impl Client {
+    pub fn new() -> Client {
+	Client {
+	    idle: Mutex::new(Vec::new()),
+	    idle_timeout: Duration::from_secs(90),
+	    max_idle_per_host: 8,
+	    max_redirects: 10,
+	    cookies: Some(Arc::new(Mutex::new(CookieJar::new()))),
+	    retry: None,
+	    accept_encoding: supported_encodings(),
+	    #[cfg(feature = "rustls")]
+	    tls: default_tls_config(),
+	}
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+	self.idle_timeout
+    }
+
+    // How long an unused connection is kept for reuse. Defaults to 90
+    // seconds.
+    pub fn set_idle_timeout(&mut self, idle_timeout: Duration) {
+	self.idle_timeout = idle_timeout;
+    }
+
+    pub fn max_idle_per_host(&self) -> usize {
+	self.max_idle_per_host
+    }
+
+    // How many unused connections are kept for each scheme, host and port.
+    // Defaults to 8; 0 turns pooling off.
+    pub fn set_max_idle_per_host(&mut self, max_idle_per_host: usize) {
+	self.max_idle_per_host = max_idle_per_host;
+    }
+
+    pub fn max_redirects(&self) -> usize {
+	self.max_redirects
+    }
+
+    // How many redirects `send` follows before failing with
+    // `TooManyRedirects`. Defaults to 10; with 0 the redirect response itself
+    // is returned.
+    pub fn set_max_redirects(&mut self, max_redirects: usize) {
+	self.max_redirects = max_redirects;
+    }
+
+    pub fn cookie_jar(&self) -> Option<&Arc<Mutex<CookieJar>>> {
+	self.cookies.as_ref()
+    }
+
+    // The jar cookies are stored in and sent from, e.g. one shared with
+    // another client. None turns cookie handling off; defaults to an empty
+    // jar.
+    pub fn set_cookie_jar(&mut self, cookies: Option<Arc<Mutex<CookieJar>>>) {
+	self.cookies = cookies;
+    }
+
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+	self.retry.as_ref()
+    }
+
+    // When set, failed requests and retryable responses are sent again as
+    // the policy allows. Defaults to None.
+    pub fn set_retry_policy(&mut self, retry: Option<RetryPolicy>) {
+	self.retry = retry;
+    }
+
+    pub fn accept_encoding(&self) -> Option<&String> {
+	self.accept_encoding.as_ref()
+    }
+
+    // Sent as `Accept-Encoding` unless the request has its own, in which
+    // case the response body is left encoded. Defaults to the codings
+    // compiled in (`gzip`, `br`, `deflate`), or None without any.
+    pub fn set_accept_encoding(&mut self, accept_encoding: Option<&str>) {
+	self.accept_encoding = accept_encoding.map(str::to_string);
+    }
+
+    #[cfg(feature = "rustls")]
+    pub fn tls_config(&self) -> &Arc<rustls::ClientConfig> {
+	&self.tls
+    }
+
+    // Used for `https` targets. Defaults to the Mozilla root store.
+    #[cfg(feature = "rustls")]
+    pub fn set_tls_config(&mut self, config: Arc<rustls::ClientConfig>) {
+	self.tls = config;
+    }
+
+    pub fn send(&self, request: &Request) -> Result<Response, ClientError> {
+	self.send_via(request, None)
+    }
+
+    // Connects to the Unix domain socket at `path` rather than the request's
+    // host, e.g. `/var/run/docker.sock`. The Host header is still sent,
+    // defaulting to `localhost`. Redirects to another origin leave the
+    // socket for TCP.
+    #[cfg(unix)]
+    pub fn send_unix<P: AsRef<Path>>(&self, request: &Request, path: P) -> Result<Response, ClientError> {
+	self.send_via(request, Some(path.as_ref()))
+    }
+
+    fn send_via(&self, request: &Request, socket: Option<&Path>) -> Result<Response, ClientError> {
+	let mut request: Request = request.clone();
+	if socket.is_some() && request.host()?.is_none() {
+	    request.set_header("Host", "localhost");
+	}
+	let mut socket: Option<PathBuf> = socket.map(Path::to_path_buf);
+	let mut redirects: usize = 0;
+	loop {
+	    let response: Response = self.send_once(&request, socket.as_deref())?;
+	    let location: String = match response.find_header("location") {
+		Some(location) if self.max_redirects > 0 && is_redirect(response.status()) => location.value().trim().to_string(),
+		_ => return Ok(response),
+	    };
+	    if redirects == self.max_redirects {
+		return Err(ClientError::TooManyRedirects(self.max_redirects));
+	    }
+	    redirects += 1;
+	    let next: Request = redirect(&request, response.status(), &location)?;
+	    if Origin::of(&next)? != Origin::of(&request)? {
+		socket = None;
+	    }
+	    request = next;
+	}
+    }
+
+    fn send_once(&self, request: &Request, socket: Option<&Path>) -> Result<Response, ClientError> {
+	let mut origin: Origin = Origin::of(request)?;
+	origin.socket = socket.map(Path::to_path_buf);
+	let mut request: Request = request.clone();
+	if request.find_header("host").is_none() {
+	    request.set_header("Host", &origin.authority());
+	}
+	let decode: bool = match &self.accept_encoding {
+	    Some(accept_encoding) if request.find_header("accept-encoding").is_none() => {
+		request.set_header("Accept-Encoding", accept_encoding);
+		true
+	    }
+	    _ => false,
+	};
+	if let Some(Ok(jar)) = self.cookies.as_ref().map(|jar| jar.lock()) {
+	    jar.apply_to_origin(&mut request, &origin.host, origin.scheme == "https");
+	}
+	let mut attempt: u32 = 1;
+	let response: Response = loop {
+	    let result: Result<Response, ClientError> = self.exchange_reusing(&origin, &request);
+	    match self.retry.as_ref().and_then(|policy| policy.delay(&request, &result, attempt)) {
+		Some(delay) => thread::sleep(delay),
+		None => break result?,
+	    }
+	    attempt += 1;
+	};
+	let response: Response = if decode { decompress(response, request.config().max_body_size())? } else { response };
+	if let Some(Ok(mut jar)) = self.cookies.as_ref().map(|jar| jar.lock()) {
+	    jar.store_response_from(&response, &origin.host);
+	}
+	Ok(response)
+    }
+
+    fn exchange_reusing(&self, origin: &Origin, request: &Request) -> Result<Response, ClientError> {
+	// The server may have closed a pooled connection while it sat idle;
+	// an idempotent request is then sent again on a new one.
+	if let Some(reader) = self.checkout(origin) {
+	    match self.exchange_pooled(origin, reader, request) {
+		Err(ClientError::Io(_) | ClientError::Response(ParseError::Io(_))) if request.method().is_idempotent() => {}
+		result => return result,
+	    }
+	}
+	let reader: BufReader<Stream> = BufReader::new(self.connect(origin)?);
+	self.exchange_pooled(origin, reader, request)
+    }
+
+    fn connect(&self, origin: &Origin) -> Result<Stream, ClientError> {
+	#[cfg(unix)]
+	if let Some(path) = &origin.socket {
+	    return match origin.scheme.as_str() {
+		"http" => UnixStream::connect(path).map(Stream::Unix).map_err(|err| ClientError::Io(err.to_string())),
+		scheme => Err(ClientError::InvalidTarget(format!("unsupported scheme `{}` over a Unix socket", scheme))),
+	    };
+	}
+	let host: &str = origin.host.trim_start_matches('[').trim_end_matches(']');
+	let connect = || TcpStream::connect((host, origin.port)).map_err(|err| ClientError::Io(err.to_string()));
+	match origin.scheme.as_str() {
+	    "http" => Ok(Stream::Tcp(connect()?)),
+	    #[cfg(feature = "rustls")]
+	    "https" => Ok(Stream::Tls(Box::new(tls_stream(&origin.host, Arc::clone(&self.tls), connect()?)?))),
+	    scheme => Err(ClientError::InvalidTarget(format!("unsupported scheme `{}`", scheme))),
+	}
+    }
+
+    // Sends the request and returns the connection to the pool when the
+    // response leaves it usable.
+    fn exchange_pooled(&self, origin: &Origin, mut reader: BufReader<Stream>, request: &Request) -> Result<Response, ClientError> {
+	let stream: &mut Stream = reader.get_mut();
+	stream.write_all(&request.to_bytes()).and_then(|_| stream.flush()).map_err(|err| ClientError::Io(err.to_string()))?;
+	let response: Response = reader::read_response(&mut reader, request.method(), request.config())?;
+	if request.keep_alive() && response.keep_alive() && is_delimited(request.method(), &response) {
+	    self.checkin(origin, reader);
+	}
+	Ok(response)
+    }
+
+    fn checkout(&self, origin: &Origin) -> Option<BufReader<Stream>> {
+	let mut idle = self.idle.lock().ok()?;
+	idle.retain(|connection| connection.since.elapsed() < self.idle_timeout);
+	let position: usize = idle.iter().rposition(|connection| connection.origin == *origin)?;
+	Some(idle.remove(position).reader)
+    }
+
+    fn checkin(&self, origin: &Origin, reader: BufReader<Stream>) {
+	if let Ok(mut idle) = self.idle.lock() {
+	    idle.retain(|connection| connection.since.elapsed() < self.idle_timeout);
+	    if idle.iter().filter(|connection| connection.origin == *origin).count() < self.max_idle_per_host {
+		idle.push(Idle {
+		    origin: origin.clone(),
+		    reader,
+		    since: Instant::now(),
+		});
+	    }
+	}
+    }
+}
+
+// When and how soon `Client::send` repeats a request. By default only
+// idempotent requests are retried, up to 3 attempts in all, after I/O
+// failures and 429, 502, 503 and 504 responses. The wait doubles from
+// `base_delay` with each attempt, up to `max_delay`, and is randomized
+// between half and all of that; a Retry-After header sets it instead, and a
+// response asking for longer than `max_delay` is returned as is.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    idempotent_only: bool,
+    retry_response: fn(&Response) -> bool,
+    retry_error: fn(&ClientError) -> bool,
+}
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+	RetryPolicy::new()
+    }
+}
+impl RetryPolicy {
+    pub fn new() -> RetryPolicy {
+	RetryPolicy {
+	    max_attempts: 3,
+	    base_delay: Duration::from_millis(100),
+	    max_delay: Duration::from_secs(10),
+	    idempotent_only: true,
+	    retry_response: |response| {
+		matches!(response.status(), StatusCode::TooManyRequests | StatusCode::BadGateway | StatusCode::ServiceUnavailable | StatusCode::GatewayTimeout)
+	    },
+	    retry_error: |err| matches!(err, ClientError::Io(_) | ClientError::Response(ParseError::Io(_))),
+	}
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+	self.max_attempts
+    }
+
+    // The number of times a request is sent, counting the first.
+    pub fn set_max_attempts(&mut self, max_attempts: u32) {
+	self.max_attempts = max_attempts;
+    }
+
+    pub fn base_delay(&self) -> Duration {
+	self.base_delay
+    }
+
+    pub fn set_base_delay(&mut self, base_delay: Duration) {
+	self.base_delay = base_delay;
+    }
+
+    pub fn max_delay(&self) -> Duration {
+	self.max_delay
+    }
+
+    pub fn set_max_delay(&mut self, max_delay: Duration) {
+	self.max_delay = max_delay;
+    }
+
+    pub fn idempotent_only(&self) -> bool {
+	self.idempotent_only
+    }
+
+    // Disable to also retry e.g. POST requests, which the server may already
+    // have acted on.
+    pub fn set_idempotent_only(&mut self, idempotent_only: bool) {
+	self.idempotent_only = idempotent_only;
+    }
+
+    // Decides which responses are retried.
+    pub fn set_retry_response(&mut self, retry_response: fn(&Response) -> bool) {
+	self.retry_response = retry_response;
+    }
+
+    // Decides which errors are retried.
+    pub fn set_retry_error(&mut self, retry_error: fn(&ClientError) -> bool) {
+	self.retry_error = retry_error;
+    }
+
+    // How long to wait before sending the request again after `attempt`
+    // attempts ended in `result`, or None to stop.
+    fn delay(&self, request: &Request, result: &Result<Response, ClientError>, attempt: u32) -> Option<Duration> {
+	if attempt >= self.max_attempts || (self.idempotent_only && !request.method().is_idempotent()) {
+	    return None;
+	}
+	match result {
+	    Ok(response) if (self.retry_response)(response) => match retry_after(response) {
+		Some(delay) if delay > self.max_delay => None,
+		Some(delay) => Some(delay),
+		None => Some(self.backoff(attempt)),
+	    },
+	    Err(err) if (self.retry_error)(err) => Some(self.backoff(attempt)),
+	    _ => None,
+	}
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+	let delay: Duration = self.base_delay.saturating_mul(2u32.saturating_pow(attempt - 1)).min(self.max_delay);
+	let jitter: u64 = RandomState::new().build_hasher().finish() % (delay.as_nanos() as u64 / 2 + 1);
+	delay / 2 + Duration::from_nanos(jitter)
+    }
+}
+
+fn supported_encodings() -> Option<String> {
+    let encodings: Vec<&str> = vec![
+	#[cfg(feature = "gzip")]
+	"gzip",
+	#[cfg(feature = "brotli")]
+	"br",
+	#[cfg(feature = "deflate")]
+	"deflate",
+    ];
+    (!encodings.is_empty()).then(|| encodings.join(", "))
+}
+
+// Removes the response's content codings, leaving at most `max_length`
+// bytes, and updates its headers to describe the decoded body.
+fn decompress(mut response: Response, max_length: usize) -> Result<Response, ClientError> {
+    let content_encoding: String = match response.headers().get_joined("content-encoding") {
+	Some(content_encoding) if !response.body_bytes().is_empty() => content_encoding,
+	_ => return Ok(response),
+    };
+    let body: Vec<u8> = encoding::decode_body(response.body_bytes(), Some(&content_encoding), max_length)?.into_owned();
+    response.remove_header("Content-Encoding");
+    if response.find_header("content-length").is_some() {
+	response.set_header("Content-Length", &body.len().to_string());
+    }
+    response.set_body_bytes(&body);
+    Ok(response)
+}
+
+// The wait a `Retry-After` header asks for, given in seconds or as a date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value: &str = response.find_header("retry-after")?.value().trim();
+    match value.parse::<u64>() {
+	Ok(seconds) => Some(Duration::from_secs(seconds)),
+	Err(_) => {
+	    let date: HttpDate = HttpDate::parse(value).ok()?;
+	    Some(Duration::from_secs(date.unix_seconds().saturating_sub(HttpDate::now().unix_seconds())))
+	}
+    }
+}
+
+// Where a request is sent. Pooled connections are only shared between
+// requests with the same origin; `socket` replaces the host and port as the
+// address to connect to.
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct Origin {
+    scheme: String,
+    host: String,
+    port: u16,
+    socket: Option<PathBuf>,
+}
+impl Origin {
+    fn of(request: &Request) -> Result<Origin, ClientError> {
+	let scheme: String = Uri::parse(request.full_path()).ok().and_then(|uri| uri.scheme().map(str::to_string)).unwrap_or_else(|| "http".to_string());
+	let default_port: u16 = match scheme.as_str() {
+	    "http" => 80,
+	    "https" => 443,
+	    _ => return Err(ClientError::InvalidTarget(format!("unsupported scheme `{}`", scheme))),
+	};
+	let host: String = request.host()?.ok_or_else(|| ClientError::InvalidTarget("request has no host".to_string()))?;
+	let port: u16 = request.port()?.unwrap_or(default_port);
+	Ok(Origin {
+	    scheme,
+	    host,
+	    port,
+	    socket: None,
+	})
+    }
+
+    // `host:port`, leaving out the scheme's default port.
+    fn authority(&self) -> String {
+	match (self.scheme.as_str(), self.port) {
+	    ("http", 80) | ("https", 443) => self.host.clone(),
+	    _ => format!("{}:{}", self.host, self.port),
+	}
+    }
+}
+
+struct Idle {
+    origin: Origin,
+    reader: BufReader<Stream>,
+    since: Instant,
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(feature = "rustls")]
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+	match self {
+	    Stream::Tcp(stream) => stream.read(buf),
+	    #[cfg(feature = "rustls")]
+	    Stream::Tls(stream) => stream.read(buf),
+	    #[cfg(unix)]
+	    Stream::Unix(stream) => stream.read(buf),
+	}
+    }
+}
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+	match self {
+	    Stream::Tcp(stream) => stream.write(buf),
+	    #[cfg(feature = "rustls")]
+	    Stream::Tls(stream) => stream.write(buf),
+	    #[cfg(unix)]
+	    Stream::Unix(stream) => stream.write(buf),
+	}
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+	match self {
+	    Stream::Tcp(stream) => stream.flush(),
+	    #[cfg(feature = "rustls")]
+	    Stream::Tls(stream) => stream.flush(),
+	    #[cfg(unix)]
+	    Stream::Unix(stream) => stream.flush(),
+	}
+    }
+}
+
+fn is_redirect(status: StatusCode) -> bool {
+    matches!(status, StatusCode::MovedPermanently | StatusCode::Found | StatusCode::SeeOther | StatusCode::TemporaryRedirect | StatusCode::PermanentRedirect)
+}
+
+// The request that follows a redirect to `location` (RFC 9110 section 15.4).
+// 303 turns every method but HEAD into a GET, and 301 and 302 turn a POST
+// into a GET, dropping the body; 307 and 308 repeat the request unchanged.
+// Credentials are not sent on to another origin.
+fn redirect(request: &Request, status: StatusCode, location: &str) -> Result<Request, ClientError> {
+    let origin: Origin = Origin::of(request)?;
+    let location: &str = location.split('#').next().unwrap_or("");
+    let target: String = if Uri::parse(location).is_ok_and(|uri| uri.is_absolute()) {
+	location.to_string()
+    } else if location.starts_with("//") {
+	format!("{}:{}", origin.scheme, location)
+    } else if location.starts_with('/') {
+	format!("{}://{}{}", origin.scheme, origin.authority(), location)
+    } else {
+	let path: String = request.uri().map(|uri| uri.path().to_string()).unwrap_or_default();
+	let directory: &str = path.rfind('/').map_or("/", |end| &path[..=end]);
+	format!("{}://{}{}{}", origin.scheme, origin.authority(), directory, location)
+    };
+    let method: Method = match (status, request.method()) {
+	(StatusCode::SeeOther, Method::HEAD) => Method::HEAD,
+	(StatusCode::SeeOther, _) => Method::GET,
+	(StatusCode::MovedPermanently | StatusCode::Found, Method::POST) => Method::GET,
+	(_, method) => method.clone(),
+    };
+
+    let mut next: Request = Request::parse(&format!("{} {} HTTP/1.1\r\n\r\n", method.as_str(), target)).map_err(|_| ClientError::InvalidTarget(location.to_string()))?;
+    next.set_config(*request.config());
+    *next.headers_mut() = request.headers().clone();
+    next.remove_header("host");
+    if method == *request.method() && status != StatusCode::SeeOther {
+	next.set_body_bytes(request.body_bytes());
+    } else {
+	for name in ["content-length", "content-type", "content-encoding", "transfer-encoding"] {
+	    next.remove_header(name);
+	}
+    }
+    if Origin::of(&next)? != origin {
+	for name in ["authorization", "proxy-authorization", "cookie"] {
+	    next.remove_header(name);
+	}
+    }
+    Ok(next)
+}
+
+// Whether the response body ended by its framing rather than by the server
+// closing the connection, so the connection can carry another request.
+fn is_delimited(method: &Method, response: &Response) -> bool {
+    let status: StatusCode = response.status();
+    if status == StatusCode::SwitchingProtocols {
+	return false;
+    }
+    *method == Method::HEAD || status.is_informational() || status == StatusCode::NoContent || status == StatusCode::NotModified
+	|| headers::is_chunked(response.headers()) || response.content_length().is_ok_and(|length| length.is_some())
+}
+
+fn exchange<S: Read + Write>(request: &Request, mut stream: S) -> Result<Response, ClientError> {
+    let mut request: Request = request.clone();
+    request.set_keep_alive(false);
+    request.write_to(&mut stream).and_then(|_| stream.flush()).map_err(|err| ClientError::Io(err.to_string()))?;
+    let mut reader: BufReader<S> = BufReader::new(stream);
+    Ok(reader::read_response(&mut reader, request.method(), request.config())?)
+}
+
+#[cfg(test)]
+mod test_client {
+    use std::io::{BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{send, Client, ClientError, RetryPolicy};
+    use crate::{CookieJar, HttpDate, Method, ParseError, Request, Response, StatusCode};
+
+    // Serves one connection, answering with `response` after reading the
+    // request, and returns the request that was received.
+    fn serve_once(response: &'static [u8]) -> (String, thread::JoinHandle<Request>) {
+	let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let address: String = listener.local_addr().unwrap().to_string();
+	let handle = thread::spawn(move || {
+	    let (stream, _) = listener.accept().unwrap();
+	    let mut reader: BufReader<TcpStream> = BufReader::new(stream);
+	    let request: Request = Request::parse_from_reader(&mut reader).unwrap();
+	    reader.get_mut().write_all(response).unwrap();
+	    request
+	});
+	(address, handle)
+    }
+
+    // Answers one request with each of `responses` in turn, closing each
+    // connection after `per_connection` of them, and returns how many
+    // connections were accepted and the requests received.
+    fn serve<T: AsRef<[u8]> + Send + 'static>(responses: Vec<T>, per_connection: usize) -> (String, thread::JoinHandle<(usize, Vec<Request>)>) {
+	let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let address: String = listener.local_addr().unwrap().to_string();
+	let handle = thread::spawn(move || {
+	    let mut connections: usize = 0;
+	    let mut requests: Vec<Request> = Vec::new();
+	    while requests.len() < responses.len() {
+		let (stream, _) = listener.accept().unwrap();
+		connections += 1;
+		let mut reader: BufReader<TcpStream> = BufReader::new(stream);
+		for _ in 0..per_connection {
+		    let request: Request = match responses.get(requests.len()).and_then(|_| Request::parse_from_reader(&mut reader).ok()) {
+			Some(request) => request,
+			None => break,
+		    };
+		    reader.get_mut().write_all(responses[requests.len()].as_ref()).unwrap();
+		    requests.push(request);
+		}
+	    }
+	    (connections, requests)
+	});
+	(address, handle)
+    }
+
+    fn ok(count: usize) -> Vec<String> {
+	vec!["HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string(); count]
+    }
+
+    #[test]
+    fn test_send() {
+	let (address, handle) = serve_once(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\n");
+	let request: Request = Request::builder().method(Method::POST).path("/items").header("Host", "localhost").body("{}").build().unwrap();
+	let response: Response = send(&request, address.as_str()).unwrap();
+	assert_eq!(response.status(), StatusCode::Created);
+	assert_eq!(response.body(), "ok");
+	let received: Request = handle.join().unwrap();
+	assert_eq!(received.body(), "{}");
+	assert!(!received.keep_alive());
+
+	let (address, handle) = serve_once(b"HTTP/1.0 200 OK\r\n\r\nuntil close");
+	let response: Response = send(&Request::parse("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap(), address.as_str()).unwrap();
+	assert_eq!(response.body(), "until close");
+	handle.join().unwrap();
+
+	let (address, handle) = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort");
+	let response = send(&Request::parse("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap(), address.as_str());
+	assert_eq!(response.err(), Some(ClientError::Response(ParseError::Io("unexpected end of stream".to_string()))));
+	handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_pool() {
+	let mut client: Client = Client::new();
+	let (address, handle) = serve(ok(3), usize::MAX);
+	let request: Request = Request::parse(&format!("GET http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	for _ in 0..3 {
+	    assert_eq!(client.send(&request).unwrap().body(), "ok");
+	}
+	let (connections, requests) = handle.join().unwrap();
+	assert_eq!(connections, 1);
+	assert_eq!(requests[0].find_header("host").unwrap().value(), &address);
+
+	// A pooled connection the server has since closed is replaced.
+	let (address, handle) = serve(ok(3), 1);
+	let request: Request = Request::parse(&format!("GET /a HTTP/1.1\r\nHost: {}\r\n\r\n", address)).unwrap();
+	for _ in 0..3 {
+	    assert_eq!(client.send(&request).unwrap().body(), "ok");
+	}
+	assert_eq!(handle.join().unwrap().0, 3);
+
+	client.set_max_idle_per_host(0);
+	let (address, handle) = serve(ok(2), usize::MAX);
+	let request: Request = Request::parse(&format!("GET http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	for _ in 0..2 {
+	    assert_eq!(client.send(&request).unwrap().body(), "ok");
+	}
+	assert_eq!(handle.join().unwrap().0, 2);
+
+	assert!(matches!(client.send(&Request::parse("GET / HTTP/1.1\r\n\r\n").unwrap()), Err(ClientError::InvalidTarget(_))));
+	assert!(matches!(client.send(&Request::parse("GET ftp://localhost/ HTTP/1.1\r\n\r\n").unwrap()), Err(ClientError::InvalidTarget(_))));
+    }
+
+    #[test]
+    fn test_redirect() {
+	let (other, other_handle) = serve(ok(1), usize::MAX);
+	let (address, handle) = serve(vec![
+	    "HTTP/1.1 307 Temporary Redirect\r\nLocation: /keep\r\nContent-Length: 0\r\n\r\n".to_string(),
+	    "HTTP/1.1 302 Found\r\nLocation: next?page=2#top\r\nContent-Length: 0\r\n\r\n".to_string(),
+	    format!("HTTP/1.1 308 Permanent Redirect\r\nLocation: http://{}/final\r\nContent-Length: 0\r\n\r\n", other),
+	], usize::MAX);
+	let request: Request = Request::builder()
+	    .method(Method::POST)
+	    .path("/dir/form")
+	    .header("Host", &address)
+	    .header("Authorization", "Bearer secret")
+	    .header("Content-Type", "text/plain")
+	    .body("data")
+	    .build()
+	    .unwrap();
+	let client: Client = Client::new();
+	assert_eq!(client.send(&request).unwrap().body(), "ok");
+
+	let (_, requests) = handle.join().unwrap();
+	let summary: Vec<(&str, &str, String)> = requests.iter().map(|request| (request.method().as_str(), request.path().as_str(), request.body().to_string())).collect();
+	assert_eq!(summary, vec![("POST", "/dir/form", "data".to_string()), ("POST", "/keep", "data".to_string()), ("GET", "/next", String::new())]);
+	assert_eq!(requests[2].query()[0].value(), "2");
+	assert!(requests[2].find_header("content-type").is_none());
+	assert!(requests[2].find_header("authorization").is_some());
+	let (_, requests) = other_handle.join().unwrap();
+	assert_eq!((requests[0].method().as_str(), requests[0].path().as_str()), ("GET", "/final"));
+	assert_eq!(requests[0].find_header("host").unwrap().value(), &other);
+	assert!(requests[0].find_header("authorization").is_none());
+
+	let mut client: Client = Client::new();
+	client.set_max_redirects(1);
+	let (address, handle) = serve(vec!["HTTP/1.1 303 See Other\r\nLocation: /a\r\nContent-Length: 0\r\n\r\n".to_string(); 2], usize::MAX);
+	let request: Request = Request::parse(&format!("HEAD http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	assert_eq!(client.send(&request).err(), Some(ClientError::TooManyRedirects(1)));
+	assert_eq!(handle.join().unwrap().1[1].method().as_str(), "HEAD");
+
+	client.set_max_redirects(0);
+	let (address, handle) = serve(vec!["HTTP/1.1 301 Moved Permanently\r\nLocation: /a\r\nContent-Length: 0\r\n\r\n".to_string()], usize::MAX);
+	let request: Request = Request::parse(&format!("GET http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	assert_eq!(client.send(&request).unwrap().status(), StatusCode::MovedPermanently);
+	handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_cookies() {
+	let responses: Vec<String> = vec![
+	    "HTTP/1.1 302 Found\r\nSet-Cookie: id=1\r\nSet-Cookie: site=2; Domain=example.com\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n".to_string(),
+	    "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string(),
+	];
+	let jar: Arc<Mutex<CookieJar>> = Arc::new(Mutex::new(CookieJar::new()));
+	let mut client: Client = Client::new();
+	client.set_cookie_jar(Some(Arc::clone(&jar)));
+	let (address, handle) = serve(responses.clone(), usize::MAX);
+	let request: Request = Request::parse(&format!("GET http://{}/a HTTP/1.1\r\nCookie: own=0\r\n\r\n", address)).unwrap();
+	client.send(&request).unwrap();
+	let (_, requests) = handle.join().unwrap();
+	assert_eq!(requests[0].find_header("cookie").unwrap().value(), "own=0");
+	assert_eq!(requests[1].find_header("cookie").unwrap().value(), "own=0; id=1");
+	assert_eq!(jar.lock().unwrap().cookies().len(), 1);
+
+	client.set_cookie_jar(None);
+	let (address, handle) = serve(responses, usize::MAX);
+	let request: Request = Request::parse(&format!("GET http://{}/a HTTP/1.1\r\n\r\n", address)).unwrap();
+	client.send(&request).unwrap();
+	assert!(handle.join().unwrap().1[1].find_header("cookie").is_none());
+    }
+
+    #[test]
+    fn test_retry() {
+	let mut policy: RetryPolicy = RetryPolicy::new();
+	policy.set_base_delay(Duration::from_millis(1));
+	let mut client: Client = Client::new();
+	client.set_retry_policy(Some(policy.clone()));
+
+	let unavailable: String = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n".to_string();
+	let (address, handle) = serve(vec![unavailable.clone(), String::new(), ok(1).remove(0)], 1);
+	let request: Request = Request::parse(&format!("GET http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	assert_eq!(client.send(&request).unwrap().body(), "ok");
+	assert_eq!(handle.join().unwrap().1.len(), 3);
+
+	let (address, handle) = serve(vec![unavailable.clone()], 1);
+	let request: Request = Request::parse(&format!("POST http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	assert_eq!(client.send(&request).unwrap().status(), StatusCode::ServiceUnavailable);
+	handle.join().unwrap();
+
+	policy.set_idempotent_only(false);
+	policy.set_max_attempts(2);
+	policy.set_retry_response(|response| response.status() == StatusCode::InternalServerError);
+	client.set_retry_policy(Some(policy.clone()));
+	let (address, handle) = serve(vec!["HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".to_string(); 2], usize::MAX);
+	let request: Request = Request::parse(&format!("POST http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap();
+	assert_eq!(client.send(&request).unwrap().status(), StatusCode::InternalServerError);
+	assert_eq!(handle.join().unwrap().1.len(), 2);
+
+	let mut response: Response = Response::new();
+	response.set_status(StatusCode::ServiceUnavailable);
+	let request: Request = Request::new();
+	let policy: RetryPolicy = RetryPolicy::new();
+	let delay: Duration = policy.delay(&request, &Ok(response.clone()), 2).unwrap_or_default();
+	assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+	response.set_header("Retry-After", &HttpDate::from_unix_seconds(HttpDate::now().unix_seconds() + 5).to_string());
+	assert!(policy.delay(&request, &Ok(response.clone()), 1).is_some_and(|delay| delay <= Duration::from_secs(5)));
+	response.set_header("Retry-After", "60");
+	assert_eq!(policy.delay(&request, &Ok(response), 1), None);
+	assert_eq!(policy.delay(&request, &Err(ClientError::Io("reset".to_string())), 3), None);
+	assert_eq!(policy.delay(&request, &Err(ClientError::TooManyRedirects(10)), 1), None);
+    }
+
+    #[test]
+    fn test_decompress() {
+	let mut client: Client = Client::new();
+	client.set_accept_encoding(Some("x-custom"));
+	let (address, handle) = serve(vec!["HTTP/1.1 200 OK\r\nContent-Encoding: identity\r\nContent-Length: 2\r\n\r\nok"; 2], usize::MAX);
+	let response: Response = client.send(&Request::parse(&format!("GET http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap()).unwrap();
+	assert!(response.find_header("content-encoding").is_none());
+	let response: Response = client.send(&Request::parse(&format!("GET http://{}/ HTTP/1.1\r\nAccept-Encoding: identity\r\n\r\n", address)).unwrap()).unwrap();
+	assert!(response.find_header("content-encoding").is_some());
+	let (_, requests) = handle.join().unwrap();
+	assert_eq!(requests[0].find_header("accept-encoding").unwrap().value(), "x-custom");
+
+	#[cfg(feature = "gzip")]
+	{
+	    let body: Vec<u8> = crate::Encoding::Gzip.encode(b"hello, hello, hello");
+	    let mut response: Vec<u8> = format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+	    response.extend_from_slice(&body);
+	    let (address, handle) = serve(vec![response], usize::MAX);
+	    let response: Response = Client::new().send(&Request::parse(&format!("GET http://{}/ HTTP/1.1\r\n\r\n", address)).unwrap()).unwrap();
+	    assert_eq!(response.body(), "hello, hello, hello");
+	    assert!(response.find_header("content-encoding").is_none());
+	    assert_eq!(response.find_header("content-length").unwrap().value(), "19");
+	    assert!(handle.join().unwrap().1[0].find_header("accept-encoding").unwrap().value().contains("gzip"));
+	}
+    }
+}
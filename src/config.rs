@@ -0,0 +1,121 @@
+use crate::prelude::*;
+use crate::ParseError;
+
+// Strict follows RFC 9112 to the letter. Lenient also accepts the sloppiness
+// common in real traffic: bare `\n` line endings, whitespace before a header
+// colon and obsolete line folding, which is joined with a single space.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ParseMode {
+    Strict,
+    Lenient,
+}
+
+// Upper bounds the parsers enforce on untrusted input. Exceeding any of them
+// fails with `ParseError::LimitExceeded` naming the limit, even when parsing
+// leniently.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct ParserConfig {
+    max_headers: usize,
+    max_header_line_length: usize,
+    max_uri_length: usize,
+    max_head_size: usize,
+    max_body_size: usize,
+    mode: ParseMode,
+}
+impl Default for ParserConfig {
+    fn default() -> ParserConfig {
+	ParserConfig::new()
+    }
+}
+impl ParserConfig {
+    pub fn new() -> ParserConfig {
+	ParserConfig {
+	    max_headers: 100,
+	    max_header_line_length: 8 * 1024,
+	    max_uri_length: 8 * 1024,
+	    max_head_size: 64 * 1024,
+	    max_body_size: 8 * 1024 * 1024,
+	    mode: ParseMode::Lenient,
+	}
+    }
+
+    pub fn max_headers(&self) -> usize {
+	self.max_headers
+    }
+
+    // Counts every header line, including duplicates.
+    pub fn set_max_headers(&mut self, max_headers: usize) {
+	self.max_headers = max_headers;
+    }
+
+    pub fn max_header_line_length(&self) -> usize {
+	self.max_header_line_length
+    }
+
+    pub fn set_max_header_line_length(&mut self, max_header_line_length: usize) {
+	self.max_header_line_length = max_header_line_length;
+    }
+
+    pub fn max_uri_length(&self) -> usize {
+	self.max_uri_length
+    }
+
+    pub fn set_max_uri_length(&mut self, max_uri_length: usize) {
+	self.max_uri_length = max_uri_length;
+    }
+
+    pub fn max_head_size(&self) -> usize {
+	self.max_head_size
+    }
+
+    // Upper bound for the request line and headers, including the blank line.
+    pub fn set_max_head_size(&mut self, max_head_size: usize) {
+	self.max_head_size = max_head_size;
+    }
+
+    pub fn max_body_size(&self) -> usize {
+	self.max_body_size
+    }
+
+    // Upper bound for the body; for chunked bodies, after decoding.
+    pub fn set_max_body_size(&mut self, max_body_size: usize) {
+	self.max_body_size = max_body_size;
+    }
+
+    pub fn mode(&self) -> ParseMode {
+	self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ParseMode) {
+	self.mode = mode;
+    }
+
+    pub(crate) fn check_uri(&self, uri: &str) -> Result<(), ParseError> {
+	check(uri.len() <= self.max_uri_length, "max_uri_length")
+    }
+
+    // `count` is the number of header lines seen before this one.
+    pub(crate) fn check_header_line(&self, count: usize, line: &str) -> Result<(), ParseError> {
+	check(count < self.max_headers, "max_headers")?;
+	self.check_line_length(line.len())
+    }
+
+    pub(crate) fn check_line_length(&self, length: usize) -> Result<(), ParseError> {
+	check(length <= self.max_header_line_length, "max_header_line_length")
+    }
+
+    pub(crate) fn check_head_size(&self, size: usize) -> Result<(), ParseError> {
+	check(size <= self.max_head_size, "max_head_size")
+    }
+
+    pub(crate) fn check_body_size(&self, size: usize) -> Result<(), ParseError> {
+	check(size <= self.max_body_size, "max_body_size")
+    }
+}
+
+fn check(within_limit: bool, limit: &str) -> Result<(), ParseError> {
+    match within_limit {
+	true => Ok(()),
+	false => Err(ParseError::LimitExceeded(limit.to_string())),
+    }
+}
@@ -0,0 +1,167 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{HeaderMap, Version};
+
+// The `Keep-Alive` header's hints for how long an idle persistent connection
+// stays open. Unknown or malformed parameters are ignored.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct KeepAlive {
+    timeout: Option<u64>,
+    max: Option<u64>,
+}
+impl KeepAlive {
+    pub fn new() -> KeepAlive {
+	KeepAlive { timeout: None, max: None }
+    }
+
+    pub fn parse(value: &str) -> KeepAlive {
+	let mut keep_alive: KeepAlive = KeepAlive::new();
+	for parameter in value.split(',') {
+	    let (name, value) = match parameter.split_once('=') {
+		Some((name, value)) => (name.trim(), value.trim().trim_matches('"')),
+		None => continue,
+	    };
+	    if name.eq_ignore_ascii_case("timeout") {
+		keep_alive.timeout = value.parse().ok();
+	    } else if name.eq_ignore_ascii_case("max") {
+		keep_alive.max = value.parse().ok();
+	    }
+	}
+	keep_alive
+    }
+
+    // Seconds an idle connection is kept open.
+    pub fn timeout(&self) -> Option<u64> {
+	self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: u64) {
+	self.timeout = Some(timeout);
+    }
+
+    // Requests still allowed on the connection.
+    pub fn max(&self) -> Option<u64> {
+	self.max
+    }
+
+    pub fn set_max(&mut self, max: u64) {
+	self.max = Some(max);
+    }
+}
+impl fmt::Display for KeepAlive {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	let mut parameters: Vec<String> = Vec::new();
+	if let Some(timeout) = self.timeout {
+	    parameters.push(format!("timeout={}", timeout));
+	}
+	if let Some(max) = self.max {
+	    parameters.push(format!("max={}", max));
+	}
+	write!(f, "{}", parameters.join(", "))
+    }
+}
+
+// The lowercased options listed in every `Connection` header.
+pub(crate) fn connection_options(headers: &HeaderMap) -> Vec<String> {
+    headers.get_all("connection").iter()
+	.flat_map(|header| header.value().split(','))
+	.map(|option| option.trim().to_ascii_lowercase())
+	.filter(|option| !option.is_empty())
+	.collect()
+}
+
+// Fields that only describe the HTTP/1.x connection: the fixed hop-by-hop
+// set plus any named in `Connection`. HTTP/2 forbids them (RFC 9113 section
+// 8.2.2).
+pub(crate) fn is_connection_specific(name: &str, options: &[String]) -> bool {
+    ["connection", "keep-alive", "proxy-connection", "transfer-encoding", "upgrade"].iter().any(|hop| name.eq_ignore_ascii_case(hop))
+	|| options.iter().any(|option| name.eq_ignore_ascii_case(option))
+}
+
+// HTTP/1.1 connections persist unless `close` is sent; HTTP/1.0 ones only
+// with an explicit `keep-alive`. HTTP/0.9 never persists.
+pub(crate) fn is_keep_alive(headers: &HeaderMap, version: Version) -> bool {
+    let options: Vec<String> = connection_options(headers);
+    if options.iter().any(|option| option == "close") {
+	return false;
+    }
+    match version {
+	Version::Http09 => false,
+	Version::Http10 => options.iter().any(|option| option == "keep-alive"),
+	_ => true,
+    }
+}
+
+// Rewrites the `Connection` header so `is_keep_alive` yields `keep_alive`,
+// keeping any other options such as `Upgrade`. Only the version's non-default
+// choice is spelled out.
+pub(crate) fn set_keep_alive(headers: &mut HeaderMap, version: Version, keep_alive: bool) {
+    let mut options: Vec<String> = headers.get_all("connection").iter()
+	.flat_map(|header| header.value().split(','))
+	.map(str::trim)
+	.filter(|option| !option.is_empty() && !option.eq_ignore_ascii_case("close") && !option.eq_ignore_ascii_case("keep-alive"))
+	.map(str::to_string)
+	.collect();
+    if !keep_alive {
+	options.push("close".to_string());
+	headers.remove("keep-alive");
+    } else if version == Version::Http10 {
+	options.push("keep-alive".to_string());
+    }
+    if options.is_empty() {
+	headers.remove("connection");
+    } else {
+	headers.insert("Connection", &options.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod test_connection {
+    use super::{is_keep_alive, set_keep_alive, KeepAlive};
+    use crate::{HeaderMap, Version};
+
+    #[test]
+    fn test_keep_alive_header() {
+	let keep_alive: KeepAlive = KeepAlive::parse("timeout=5, max=1000");
+	assert_eq!(keep_alive.timeout(), Some(5));
+	assert_eq!(keep_alive.max(), Some(1000));
+	assert_eq!(keep_alive.to_string(), "timeout=5, max=1000");
+	assert_eq!(KeepAlive::parse("Timeout=\"7\", max=lots, extra"), {
+	    let mut expected: KeepAlive = KeepAlive::new();
+	    expected.set_timeout(7);
+	    expected
+	});
+    }
+
+    #[test]
+    fn test_defaults() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	assert!(is_keep_alive(&headers, Version::Http11));
+	assert!(!is_keep_alive(&headers, Version::Http10));
+	assert!(!is_keep_alive(&headers, Version::Http09));
+	headers.append("Connection", "Keep-Alive");
+	assert!(is_keep_alive(&headers, Version::Http10));
+	headers.append("Connection", "close");
+	assert!(!is_keep_alive(&headers, Version::Http11));
+    }
+
+    #[test]
+    fn test_set_keep_alive() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append("Connection", "Upgrade, close");
+	headers.append("Keep-Alive", "timeout=5");
+	set_keep_alive(&mut headers, Version::Http11, true);
+	assert_eq!(headers.get("connection").unwrap().value(), "Upgrade");
+	set_keep_alive(&mut headers, Version::Http11, false);
+	assert_eq!(headers.get("connection").unwrap().value(), "Upgrade, close");
+	assert!(!headers.contains("keep-alive"));
+
+	let mut headers: HeaderMap = HeaderMap::new();
+	set_keep_alive(&mut headers, Version::Http10, true);
+	assert_eq!(headers.get("connection").unwrap().value(), "keep-alive");
+	assert!(is_keep_alive(&headers, Version::Http10));
+	set_keep_alive(&mut headers, Version::Http11, true);
+	assert!(!headers.contains("connection"));
+    }
+}
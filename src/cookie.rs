@@ -0,0 +1,384 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{Request, Response};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    SameSite::Strict => write!(f, "Strict"),
+	    SameSite::Lax => write!(f, "Lax"),
+	    SameSite::None => write!(f, "None"),
+	}
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<i64>,
+    expires: Option<String>,
+    secure: bool,
+    http_only: bool,
+    // Set by `CookieJar::store_response_from` for a cookie without a Domain:
+    // `domain` then holds the host that set it, which must match exactly.
+    host_only: bool,
+    same_site: Option<SameSite>,
+}
+impl Cookie {
+    pub fn new(name: &str, value: &str) -> Cookie {
+	Cookie {
+	    name: name.to_string(),
+	    value: value.to_string(),
+	    path: None,
+	    domain: None,
+	    max_age: None,
+	    expires: None,
+	    secure: false,
+	    http_only: false,
+	    host_only: false,
+	    same_site: None,
+	}
+    }
+
+    // Parses a `Set-Cookie` header value. Unknown attributes are ignored and
+    // a value without a `name=` pair yields None, as RFC 6265 prescribes.
+    pub fn parse_set_cookie(header: &str) -> Option<Cookie> {
+	let mut parts = header.split(';');
+	let (name, value) = parts.next()?.split_once('=')?;
+	let name: &str = name.trim();
+	if name.is_empty() {
+	    return None;
+	}
+	let mut cookie: Cookie = Cookie::new(name, value.trim());
+	for attribute in parts {
+	    let (key, value) = match attribute.split_once('=') {
+		Some((key, value)) => (key.trim(), value.trim()),
+		None => (attribute.trim(), ""),
+	    };
+	    match key.to_lowercase().as_str() {
+		"path" => cookie.path = Some(value.to_string()),
+		"domain" => cookie.domain = Some(value.trim_start_matches('.').to_string()),
+		"max-age" => cookie.max_age = value.parse().ok(),
+		"expires" => cookie.expires = Some(value.to_string()),
+		"secure" => cookie.secure = true,
+		"httponly" => cookie.http_only = true,
+		"samesite" => {
+		    cookie.same_site = match value.to_lowercase().as_str() {
+			"strict" => Some(SameSite::Strict),
+			"lax" => Some(SameSite::Lax),
+			"none" => Some(SameSite::None),
+			_ => None,
+		    }
+		}
+		_ => {}
+	    }
+	}
+	Some(cookie)
+    }
+
+    // Serializes the cookie with its attributes, as sent in `Set-Cookie`.
+    pub fn build(&self) -> String {
+	let mut cookie: String = format!("{}={}", self.name, self.value);
+	if let Some(path) = &self.path {
+	    cookie.push_str(&format!("; Path={}", path));
+	}
+	if let Some(domain) = self.domain.as_ref().filter(|_| !self.host_only) {
+	    cookie.push_str(&format!("; Domain={}", domain));
+	}
+	if let Some(max_age) = self.max_age {
+	    cookie.push_str(&format!("; Max-Age={}", max_age));
+	}
+	if let Some(expires) = &self.expires {
+	    cookie.push_str(&format!("; Expires={}", expires));
+	}
+	if self.secure {
+	    cookie.push_str("; Secure");
+	}
+	if self.http_only {
+	    cookie.push_str("; HttpOnly");
+	}
+	if let Some(same_site) = self.same_site {
+	    cookie.push_str(&format!("; SameSite={}", same_site));
+	}
+	cookie
+    }
+
+    pub fn name(&self) -> &String {
+	&self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+	self.name = name.to_string();
+    }
+
+    pub fn value(&self) -> &String {
+	&self.value
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+	self.value = value.to_string();
+    }
+
+    pub fn path(&self) -> Option<&String> {
+	self.path.as_ref()
+    }
+
+    pub fn set_path(&mut self, path: &str) {
+	self.path = Some(path.to_string());
+    }
+
+    pub fn domain(&self) -> Option<&String> {
+	self.domain.as_ref()
+    }
+
+    pub fn set_domain(&mut self, domain: &str) {
+	self.domain = Some(domain.to_string());
+    }
+
+    pub fn max_age(&self) -> Option<i64> {
+	self.max_age
+    }
+
+    pub fn set_max_age(&mut self, max_age: i64) {
+	self.max_age = Some(max_age);
+    }
+
+    pub fn expires(&self) -> Option<&String> {
+	self.expires.as_ref()
+    }
+
+    pub fn set_expires(&mut self, expires: &str) {
+	self.expires = Some(expires.to_string());
+    }
+
+    pub fn secure(&self) -> bool {
+	self.secure
+    }
+
+    pub fn set_secure(&mut self, secure: bool) {
+	self.secure = secure;
+    }
+
+    pub fn http_only(&self) -> bool {
+	self.http_only
+    }
+
+    pub fn set_http_only(&mut self, http_only: bool) {
+	self.http_only = http_only;
+    }
+
+    // Whether the cookie is only sent back to the host that set it, rather
+    // than also to its subdomains.
+    pub fn host_only(&self) -> bool {
+	self.host_only
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+	self.same_site
+    }
+
+    pub fn set_same_site(&mut self, same_site: SameSite) {
+	self.same_site = Some(same_site);
+    }
+
+    // The host is the Domain or one of its subdomains (RFC 6265 section
+    // 5.1.3), or exactly the Domain for a host-only cookie.
+    fn matches_domain(&self, host: &str) -> bool {
+	match &self.domain {
+	    Some(domain) => {
+		let (host, domain): (String, String) = (host.to_lowercase(), domain.to_lowercase());
+		host == domain || (!self.host_only && host.strip_suffix(domain.as_str()).is_some_and(|subdomain| subdomain.ends_with('.')))
+	    }
+	    None => true,
+	}
+    }
+
+    fn matches_path(&self, request_path: &str) -> bool {
+	match &self.path {
+	    Some(path) => request_path == path || (request_path.starts_with(path.as_str()) && (path.ends_with('/') || request_path[path.len()..].starts_with('/'))),
+	    None => true,
+	}
+    }
+}
+
+// Parses the pairs of a request `Cookie` header, skipping malformed entries.
+pub(crate) fn parse_cookie_header(header: &str) -> Vec<Cookie> {
+    header.split(';')
+	.filter_map(|pair| pair.split_once('='))
+	.filter(|(name, _)| !name.trim().is_empty())
+	.map(|(name, value)| Cookie::new(name.trim(), value.trim()))
+	.collect()
+}
+
+pub(crate) fn build_cookie_header(cookies: &[Cookie]) -> String {
+    cookies.iter().map(|cookie| format!("{}={}", cookie.name(), cookie.value())).collect::<Vec<String>>().join("; ")
+}
+
+// Keeps cookies received in responses and attaches the matching ones to
+// outgoing requests.
+#[derive(Clone, Default)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+impl CookieJar {
+    pub fn new() -> CookieJar {
+	CookieJar {
+	    cookies: Vec::new(),
+	}
+    }
+
+    pub fn cookies(&self) -> &Vec<Cookie> {
+	&self.cookies
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cookie> {
+	self.cookies.iter().find(|cookie| cookie.name() == name)
+    }
+
+    // Adds or replaces a cookie with the same name, domain and path. A cookie
+    // with a non-positive Max-Age removes the stored one instead.
+    pub fn add(&mut self, cookie: Cookie) {
+	self.cookies.retain(|stored| !(stored.name == cookie.name && stored.domain == cookie.domain && stored.path == cookie.path));
+	if cookie.max_age.is_none_or(|max_age| max_age > 0) {
+	    self.cookies.push(cookie);
+	}
+    }
+
+    pub fn remove(&mut self, name: &str) {
+	self.cookies.retain(|cookie| cookie.name() != name);
+    }
+
+    pub fn store_response(&mut self, response: &Response) {
+	for cookie in response.cookies() {
+	    self.add(cookie);
+	}
+    }
+
+    pub fn apply_to(&self, request: &mut Request) {
+	let path: String = request.path().clone();
+	for cookie in self.cookies.iter().filter(|cookie| cookie.matches_path(&path)) {
+	    request.set_cookie(cookie.name(), cookie.value());
+	}
+    }
+
+    // Stores the cookies set by a response from `host`. A cookie whose Domain
+    // does not cover `host` is rejected, and one without a Domain is only
+    // sent back to `host` itself (RFC 6265 section 5.3, step 6).
+    pub fn store_response_from(&mut self, response: &Response, host: &str) {
+	for mut cookie in response.cookies() {
+	    match cookie.domain {
+		Some(_) if !cookie.matches_domain(host) => continue,
+		Some(_) => {}
+		None => {
+		    cookie.domain = Some(host.to_lowercase());
+		    cookie.host_only = true;
+		}
+	    }
+	    self.add(cookie);
+	}
+    }
+
+    // Like `apply_to`, but only adds the cookies whose Domain covers `host`,
+    // and Secure cookies only when the connection is `secure`.
+    pub fn apply_to_origin(&self, request: &mut Request, host: &str, secure: bool) {
+	let path: String = request.path().clone();
+	let matching = self.cookies.iter().filter(|cookie| cookie.matches_path(&path) && cookie.matches_domain(host) && (secure || !cookie.secure));
+	for cookie in matching {
+	    request.set_cookie(cookie.name(), cookie.value());
+	}
+    }
+}
+
+#[cfg(test)]
+mod test_cookie {
+    use super::{Cookie, CookieJar, SameSite};
+    use crate::{Request, Response};
+
+    #[test]
+    fn test_parse_set_cookie() {
+	let cookie: Cookie = Cookie::parse_set_cookie("id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=3600; Domain=.example.com; Path=/docs; Secure; HttpOnly; SameSite=Lax").unwrap();
+	assert_eq!(cookie.name(), "id");
+	assert_eq!(cookie.value(), "a3fWa");
+	assert_eq!(cookie.expires().unwrap(), "Wed, 21 Oct 2015 07:28:00 GMT");
+	assert_eq!(cookie.max_age(), Some(3600));
+	assert_eq!(cookie.domain().unwrap(), "example.com");
+	assert_eq!(cookie.path().unwrap(), "/docs");
+	assert!(cookie.secure());
+	assert!(cookie.http_only());
+	assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+	assert_eq!(Cookie::parse_set_cookie("no-pair; Path=/"), None);
+    }
+
+    #[test]
+    fn test_build() {
+	let mut cookie: Cookie = Cookie::new("session", "abc");
+	cookie.set_path("/");
+	cookie.set_max_age(60);
+	cookie.set_http_only(true);
+	cookie.set_same_site(SameSite::Strict);
+	assert_eq!(cookie.build(), "session=abc; Path=/; Max-Age=60; HttpOnly; SameSite=Strict");
+	assert_eq!(Cookie::parse_set_cookie(&cookie.build()), Some(cookie));
+    }
+
+    #[test]
+    fn test_jar() {
+	let mut response: Response = Response::new();
+	response.parse_from_str("HTTP/1.1 200 OK\r\nSet-Cookie: a=1; Path=/api\r\nSet-Cookie: b=2\r\n\r\n").unwrap();
+	let mut jar: CookieJar = CookieJar::new();
+	jar.store_response(&response);
+	assert_eq!(jar.cookies().len(), 2);
+
+	let mut request: Request = Request::new();
+	request.set_path("/api/users");
+	jar.apply_to(&mut request);
+	assert_eq!(request.find_header("Cookie").unwrap().value(), "a=1; b=2");
+
+	let mut request: Request = Request::new();
+	request.set_path("/apiary");
+	jar.apply_to(&mut request);
+	assert_eq!(request.find_header("Cookie").unwrap().value(), "b=2");
+
+	let mut expired: Cookie = Cookie::new("b", "");
+	expired.set_max_age(0);
+	jar.add(expired);
+	assert!(jar.get("b").is_none());
+    }
+
+    #[test]
+    fn test_jar_origin() {
+	let mut response: Response = Response::new();
+	response.parse_from_str("HTTP/1.1 200 OK\r\nSet-Cookie: host=1\r\nSet-Cookie: site=2; Domain=Example.com\r\nSet-Cookie: other=3; Domain=example.org\r\nSet-Cookie: secure=4; Secure\r\n\r\n").unwrap();
+	let mut jar: CookieJar = CookieJar::new();
+	jar.store_response_from(&response, "api.example.com");
+	assert!(jar.get("other").is_none());
+	assert_eq!(jar.get("host").unwrap().domain().unwrap(), "api.example.com");
+	assert!(jar.get("host").unwrap().host_only());
+	assert_eq!(jar.get("host").unwrap().build(), "host=1");
+
+	let mut request: Request = Request::new();
+	jar.apply_to_origin(&mut request, "API.example.com", true);
+	assert_eq!(request.find_header("Cookie").unwrap().value(), "host=1; site=2; secure=4");
+
+	let mut request: Request = Request::new();
+	jar.apply_to_origin(&mut request, "www.example.com", false);
+	assert_eq!(request.find_header("Cookie").unwrap().value(), "site=2");
+
+	let mut request: Request = Request::new();
+	jar.apply_to_origin(&mut request, "badexample.com", true);
+	assert!(request.find_header("Cookie").is_none());
+
+	let mut request: Request = Request::new();
+	jar.apply_to_origin(&mut request, "x.api.example.com", true);
+	assert_eq!(request.find_header("Cookie").unwrap().value(), "site=2");
+    }
+}
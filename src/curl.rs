@@ -0,0 +1,64 @@
+use crate::prelude::*;
+use crate::{Method, Request, Version};
+
+// The request target as an absolute URL. Origin-form targets are joined to
+// the Host header over plain `http`, since the scheme is not sent.
+pub(crate) fn url(request: &Request) -> String {
+    let target: String = request.target();
+    if target.starts_with("http://") || target.starts_with("https://") {
+	return target;
+    }
+    let host: &str = request.headers.get("host").map_or("localhost", |header| header.value().as_str());
+    format!("http://{}{}", host, target)
+}
+
+pub(crate) fn to_curl(request: &Request) -> String {
+    let mut args: Vec<String> = vec!["curl".to_string()];
+    match request.method {
+	Method::HEAD => args.push("--head".to_string()),
+	Method::GET if request.body.is_empty() => {}
+	Method::POST if !request.body.is_empty() => {}
+	ref method => args.push(format!("-X {}", quote(method.as_str()))),
+    }
+    match request.version {
+	Version::Http10 => args.push("--http1.0".to_string()),
+	Version::Http2 => args.push("--http2".to_string()),
+	Version::Http3 => args.push("--http3".to_string()),
+	_ => {}
+    }
+    args.push(quote(&url(request)));
+    // curl derives Host from the URL and Content-Length from the data.
+    for header in request.headers.iter().filter(|header| !header.name().eq_ignore_ascii_case("host") && !header.name().eq_ignore_ascii_case("content-length")) {
+	// `Name;` is curl's syntax for sending a header with an empty value.
+	let line: String = if header.value().is_empty() { format!("{};", header.name()) } else { format!("{}: {}", header.name(), header.value()) };
+	args.push(format!("-H {}", quote(&line)));
+    }
+    if !request.body.is_empty() {
+	args.push(format!("--data-binary {}", quote(&String::from_utf8_lossy(&request.body))));
+    }
+    args.join(" ")
+}
+
+// Single-quotes an argument for a POSIX shell.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod test_curl {
+    use crate::Request;
+
+    #[test]
+    fn test_to_curl() {
+	let request: Request = Request::parse("GET /search?q=it's HTTP/1.1\r\nHost: example.com\r\nAccept: */*\r\nX-Empty:\r\n\r\n").unwrap();
+	assert_eq!(request.to_curl(), "curl 'http://example.com/search?q=it%27s' -H 'Accept: */*' -H 'X-Empty;'");
+
+	let request: Request = Request::parse("POST /api HTTP/1.0\r\nHost: example.com\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"a\":\"'\"}").unwrap();
+	assert_eq!(request.to_curl(), "curl --http1.0 'http://example.com/api' -H 'Content-Type: application/json' --data-binary '{\"a\":\"'\\''\"}'");
+
+	let request: Request = Request::parse("DELETE https://example.com/items/1 HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+	assert_eq!(request.to_curl(), "curl -X 'DELETE' 'https://example.com/items/1'");
+	let request: Request = Request::parse("HEAD / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+	assert_eq!(request.to_curl(), "curl --head 'http://example.com/'");
+    }
+}
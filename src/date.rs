@@ -0,0 +1,227 @@
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::prelude::*;
+use crate::ParseError;
+
+const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const LONG_DAYS: [&str; 7] = ["Thursday", "Friday", "Saturday", "Sunday", "Monday", "Tuesday", "Wednesday"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+// A timestamp as carried by `Date`, `Last-Modified` and the conditional
+// request headers, with one-second resolution.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct HttpDate {
+    seconds: u64,
+}
+impl HttpDate {
+    pub fn from_unix_seconds(seconds: u64) -> HttpDate {
+	HttpDate { seconds }
+    }
+
+    pub fn unix_seconds(&self) -> u64 {
+	self.seconds
+    }
+
+    #[cfg(feature = "std")]
+    pub fn now() -> HttpDate {
+	HttpDate::from(SystemTime::now())
+    }
+
+    // Accepts the three formats recipients must understand: IMF-fixdate
+    // (`Sun, 06 Nov 1994 08:49:37 GMT`), the obsolete RFC 850 form
+    // (`Sunday, 06-Nov-94 08:49:37 GMT`) and asctime
+    // (`Sun Nov  6 08:49:37 1994`). Formatting always uses IMF-fixdate.
+    pub fn parse(value: &str) -> Result<HttpDate, ParseError> {
+	let trimmed: &str = value.trim();
+	parse_imf_fixdate(trimmed)
+	    .or_else(|| parse_rfc850(trimmed))
+	    .or_else(|| parse_asctime(trimmed))
+	    .ok_or_else(|| ParseError::InvalidDate(value.to_string()))
+    }
+}
+// Sub-second precision is truncated and times before 1970 clamp to the epoch.
+#[cfg(feature = "std")]
+impl From<SystemTime> for HttpDate {
+    fn from(time: SystemTime) -> HttpDate {
+	HttpDate::from_unix_seconds(time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0))
+    }
+}
+#[cfg(feature = "std")]
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> SystemTime {
+	UNIX_EPOCH + Duration::from_secs(date.seconds)
+    }
+}
+impl FromStr for HttpDate {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<HttpDate, ParseError> {
+	HttpDate::parse(value)
+    }
+}
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	let days: u64 = self.seconds / 86400;
+	let time: u64 = self.seconds % 86400;
+	let (year, month, day) = civil_from_days(days);
+	write!(f, "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT", DAYS[(days % 7) as usize], day, MONTHS[month as usize - 1], year, time / 3600, time / 60 % 60, time % 60)
+    }
+}
+
+fn parse_imf_fixdate(value: &str) -> Option<HttpDate> {
+    let (day_name, rest) = value.split_once(", ")?;
+    if !DAYS.contains(&day_name) {
+	return None;
+    }
+    let parts: Vec<&str> = rest.split(' ').collect();
+    match parts[..] {
+	[day, month, year, time, "GMT"] if day.len() == 2 && year.len() == 4 => from_parts(number(year)?, month_number(month)?, number(day)?, time),
+	_ => None,
+    }
+}
+
+// Two-digit years below 70 are taken to be in the 2000s.
+fn parse_rfc850(value: &str) -> Option<HttpDate> {
+    let (day_name, rest) = value.split_once(", ")?;
+    if !LONG_DAYS.contains(&day_name) {
+	return None;
+    }
+    match rest.split(' ').collect::<Vec<&str>>()[..] {
+	[date, time, "GMT"] => match date.split('-').collect::<Vec<&str>>()[..] {
+	    [day, month, year] if day.len() == 2 && year.len() == 2 => {
+		let year: u64 = number(year)?;
+		from_parts(if year < 70 { year + 2000 } else { year + 1900 }, month_number(month)?, number(day)?, time)
+	    }
+	    _ => None,
+	},
+	_ => None,
+    }
+}
+
+// The day of the month is padded with a space rather than a zero.
+fn parse_asctime(value: &str) -> Option<HttpDate> {
+    let (day_name, rest) = value.split_once(' ')?;
+    if !DAYS.contains(&day_name) || rest.len() != 20 {
+	return None;
+    }
+    let (month, rest) = rest.split_once(' ')?;
+    let (day, rest) = rest.split_at(2);
+    match rest.split(' ').collect::<Vec<&str>>()[..] {
+	["", time, year] if year.len() == 4 => from_parts(number(year)?, month_number(month)?, number(day.trim_start())?, time),
+	_ => None,
+    }
+}
+
+fn from_parts(year: u64, month: u64, day: u64, time: &str) -> Option<HttpDate> {
+    let (hour, minute, second) = match time.split(':').collect::<Vec<&str>>()[..] {
+	[hour, minute, second] if hour.len() == 2 && minute.len() == 2 && second.len() == 2 => (number(hour)?, number(minute)?, number(second)?),
+	_ => return None,
+    };
+    // Leap seconds are folded into the following minute's first second.
+    if year < 1970 || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+	return None;
+    }
+    Some(HttpDate::from_unix_seconds(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second))
+}
+
+fn number(digits: &str) -> Option<u64> {
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+	return None;
+    }
+    digits.parse().ok()
+}
+
+fn month_number(name: &str) -> Option<u64> {
+    MONTHS.iter().position(|month| *month == name).map(|position| position as u64 + 1)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+	2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+	2 => 28,
+	4 | 6 | 9 | 11 => 30,
+	_ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date, after Howard
+// Hinnant's `days_from_civil`.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year: u64 = if month <= 2 { year - 1 } else { year };
+    let era: u64 = year / 400;
+    let year_of_era: u64 = year - era * 400;
+    let day_of_year: u64 = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era: u64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// `1994-11-06T08:49:37.000Z`, as used by HAR files.
+#[cfg(feature = "serde")]
+pub(crate) fn iso8601(date: HttpDate) -> String {
+    let time: u64 = date.seconds % 86400;
+    let (year, month, day) = civil_from_days(date.seconds / 86400);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000Z", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days: u64 = days + 719468;
+    let era: u64 = days / 146097;
+    let day_of_era: u64 = days - era * 146097;
+    let year_of_era: u64 = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year: u64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: u64 = (5 * day_of_year + 2) / 153;
+    let day: u64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: u64 = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year: u64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test_http_date {
+    use super::HttpDate;
+    use crate::ParseError;
+    #[cfg(feature = "std")]
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_parse() {
+	assert_eq!(HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT"), Ok(HttpDate::from_unix_seconds(784111777)));
+	assert_eq!(HttpDate::parse("Thu, 01 Jan 1970 00:00:00 GMT"), Ok(HttpDate::from_unix_seconds(0)));
+	assert_eq!(HttpDate::parse("Tue, 29 Feb 2000 23:59:59 GMT"), Ok(HttpDate::from_unix_seconds(951868799)));
+	for value in ["", "Sun, 06 Nov 1994 08:49:37", "Sun, 6 Nov 1994 08:49:37 GMT", "Sun, 06 Nov 1994 8:49:37 GMT", "Sun, 31 Nov 1994 08:49:37 GMT", "Thu, 29 Feb 2001 00:00:00 GMT", "Sun, 06 Nov 1969 08:49:37 GMT"] {
+	    assert_eq!(HttpDate::parse(value), Err(ParseError::InvalidDate(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_obsolete_formats() {
+	let date: HttpDate = HttpDate::from_unix_seconds(784111777);
+	assert_eq!(HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT"), Ok(date));
+	assert_eq!(HttpDate::parse("Sun Nov  6 08:49:37 1994"), Ok(date));
+	assert_eq!(HttpDate::parse("Thursday, 01-Jan-15 00:00:00 GMT"), Ok(HttpDate::from_unix_seconds(1420070400)));
+	assert_eq!(HttpDate::parse("Thu Jan 15 00:00:00 2015"), Ok(HttpDate::from_unix_seconds(1421280000)));
+	for value in ["Sun, 06-Nov-94 08:49:37 GMT", "Sunday, 06-Nov-1994 08:49:37 GMT", "Sun Nov 6 08:49:37 1994", "Sun Nov  6 08:49:37 94", "Sun Nov 06 08:49:37 1994 GMT"] {
+	    assert_eq!(HttpDate::parse(value), Err(ParseError::InvalidDate(value.to_string())));
+	}
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_system_time() {
+	let time: SystemTime = UNIX_EPOCH + Duration::from_millis(784111777500);
+	assert_eq!(HttpDate::from(time), HttpDate::from_unix_seconds(784111777));
+	assert_eq!(SystemTime::from(HttpDate::from_unix_seconds(60)), UNIX_EPOCH + Duration::from_secs(60));
+	assert_eq!(HttpDate::from(UNIX_EPOCH - Duration::from_secs(1)), HttpDate::from_unix_seconds(0));
+	assert!(HttpDate::now() > HttpDate::from_unix_seconds(784111777));
+    }
+
+    #[test]
+    fn test_display() {
+	assert_eq!(HttpDate::from_unix_seconds(784111777).to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+	assert_eq!(HttpDate::from_unix_seconds(0).to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+	assert_eq!(HttpDate::from_unix_seconds(951868799).to_string(), "Tue, 29 Feb 2000 23:59:59 GMT");
+    }
+}
@@ -0,0 +1,145 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{HeaderMap, Query, Request, Response};
+
+// Headers whose values are credentials or session state.
+const SENSITIVE_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+const BOLD: &str = "\x1B[1m";
+const RESET: &str = "\x1B[0m";
+
+enum Message<'a> {
+    Request(&'a Request),
+    Response(&'a Response),
+}
+
+// A view of a request or response for logs and terminals, made by
+// `redacted` and `colored`. The two can be combined, e.g.
+// `request.redacted().colored()`.
+pub struct MessageDisplay<'a> {
+    message: Message<'a>,
+    redact: bool,
+    color: bool,
+}
+impl<'a> MessageDisplay<'a> {
+    pub(crate) fn request(request: &'a Request) -> MessageDisplay<'a> {
+	MessageDisplay {
+	    message: Message::Request(request),
+	    redact: false,
+	    color: false,
+	}
+    }
+
+    pub(crate) fn response(response: &'a Response) -> MessageDisplay<'a> {
+	MessageDisplay {
+	    message: Message::Response(response),
+	    redact: false,
+	    color: false,
+	}
+    }
+
+    // Masks the values of Authorization, Proxy-Authorization, Cookie and
+    // Set-Cookie.
+    pub fn redacted(mut self) -> MessageDisplay<'a> {
+	self.redact = true;
+	self
+    }
+
+    // Highlights the section titles with ANSI escapes.
+    pub fn colored(mut self) -> MessageDisplay<'a> {
+	self.color = true;
+	self
+    }
+
+    fn title(&self, f: &mut fmt::Formatter, title: &str) -> fmt::Result {
+	if self.color {
+	    writeln!(f, "{}{}:{}", BOLD, title, RESET)
+	} else {
+	    writeln!(f, "{}:", title)
+	}
+    }
+
+    fn write_headers(&self, f: &mut fmt::Formatter, headers: &HeaderMap) -> fmt::Result {
+	if headers.is_empty() {
+	    return Ok(());
+	}
+	self.title(f, "Headers")?;
+	for header in headers {
+	    let value: &str = if self.redact && is_sensitive(header.name()) { "[redacted]" } else { header.value() };
+	    writeln!(f, "  \"{}\": \"{}\"", header.name(), value)?;
+	}
+	Ok(())
+    }
+
+    fn write_query(&self, f: &mut fmt::Formatter, query: &[Query]) -> fmt::Result {
+	if query.is_empty() {
+	    return Ok(());
+	}
+	self.title(f, "Queries")?;
+	for query in query {
+	    writeln!(f, "  \"{}\" = \"{}\"", query.name(), query.value())?;
+	}
+	Ok(())
+    }
+
+    fn write_body(&self, f: &mut fmt::Formatter, body: &[u8]) -> fmt::Result {
+	if body.is_empty() {
+	    return Ok(());
+	}
+	self.title(f, "Body")?;
+	write!(f, "  \"{}\"", String::from_utf8_lossy(body))
+    }
+}
+impl fmt::Display for MessageDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self.message {
+	    Message::Request(request) => {
+		self.title(f, "Request")?;
+		writeln!(f, "  {} {} {}", request.method, request.path, request.version)?;
+		self.write_headers(f, &request.headers)?;
+		self.write_query(f, &request.query)?;
+		self.write_body(f, &request.body)
+	    }
+	    Message::Response(response) => {
+		self.title(f, "Response")?;
+		writeln!(f, "  {} {} {}", response.version(), response.status().as_u16(), response.reason())?;
+		self.write_headers(f, response.headers())?;
+		self.write_body(f, response.body_bytes())
+	    }
+	}
+    }
+}
+
+fn is_sensitive(name: &str) -> bool {
+    SENSITIVE_HEADERS.iter().any(|sensitive| name.eq_ignore_ascii_case(sensitive))
+}
+
+#[cfg(test)]
+mod test_display {
+    use crate::{Request, Response};
+
+    #[test]
+    fn test_display() {
+	let request: Request = Request::parse("GET /a?x=1 HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer secret\r\n\r\n").unwrap();
+	assert_eq!(request.to_string(), "Request:\n  GET /a HTTP/1.1\nHeaders:\n  \"Host\": \"localhost\"\n  \"Authorization\": \"Bearer secret\"\nQueries:\n  \"x\" = \"1\"\n");
+	let redacted: String = request.redacted().to_string();
+	assert!(redacted.contains("\"Authorization\": \"[redacted]\""));
+	assert!(!redacted.contains("secret"));
+	assert!(!redacted.contains('\x1B'));
+	assert!(request.redacted().colored().to_string().starts_with("\x1B[1mRequest:\x1B[0m\n"));
+
+	let mut response: Response = Response::new();
+	response.set_header("Set-Cookie", "session=abc");
+	response.set_body("ok");
+	assert_eq!(response.redacted().to_string(), "Response:\n  HTTP/1.1 200 OK\nHeaders:\n  \"Set-Cookie\": \"[redacted]\"\nBody:\n  \"ok\"");
+    }
+
+    #[test]
+    fn test_debug() {
+	let request: Request = Request::parse("POST /a HTTP/1.1\r\nHost: localhost\r\n\r\nhi").unwrap();
+	let debug: String = format!("{:?}", request);
+	assert!(debug.starts_with("Request { method: POST, target: \"/a\", version: Http11, headers: {\"Host\": \"localhost\"}"));
+	assert!(debug.contains("body: \"hi\""));
+    }
+}
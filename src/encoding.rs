@@ -0,0 +1,173 @@
+use alloc::borrow::Cow;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
+use std::io::{Read, Write};
+
+use crate::prelude::*;
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
+use crate::reader;
+use crate::ParseError;
+
+// A content coding from `Content-Encoding`. Codings other than identity are
+// only available when the matching feature (`gzip`, `deflate`, `brotli`) is
+// enabled.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Encoding {
+    Identity,
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "deflate")]
+    Deflate,
+    #[cfg(feature = "brotli")]
+    Brotli,
+}
+impl Encoding {
+    pub fn as_str(&self) -> &str {
+	match self {
+	    Encoding::Identity => "identity",
+	    #[cfg(feature = "gzip")]
+	    Encoding::Gzip => "gzip",
+	    #[cfg(feature = "deflate")]
+	    Encoding::Deflate => "deflate",
+	    #[cfg(feature = "brotli")]
+	    Encoding::Brotli => "br",
+	}
+    }
+
+    pub fn encode(&self, body: &[u8]) -> Vec<u8> {
+	match self {
+	    Encoding::Identity => body.to_vec(),
+	    #[cfg(feature = "gzip")]
+	    Encoding::Gzip => {
+		let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+		encoder.write_all(body).and_then(|_| encoder.finish()).expect("writing to a Vec cannot fail")
+	    }
+	    #[cfg(feature = "deflate")]
+	    Encoding::Deflate => {
+		let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+		encoder.write_all(body).and_then(|_| encoder.finish()).expect("writing to a Vec cannot fail")
+	    }
+	    #[cfg(feature = "brotli")]
+	    Encoding::Brotli => {
+		let mut encoded: Vec<u8> = Vec::new();
+		let mut encoder = brotli::CompressorWriter::new(&mut encoded, 4096, 5, 22);
+		encoder.write_all(body).expect("writing to a Vec cannot fail");
+		drop(encoder);
+		encoded
+	    }
+	}
+    }
+
+    // Reverses `encode`, producing at most `max_length` bytes so a small
+    // compressed body cannot expand without bound.
+    pub fn decode(&self, body: &[u8], max_length: usize) -> Result<Vec<u8>, ParseError> {
+	match self {
+	    Encoding::Identity => within_limit(body.to_vec(), max_length),
+	    #[cfg(feature = "gzip")]
+	    Encoding::Gzip => read_limited(flate2::read::MultiGzDecoder::new(body), max_length),
+	    // "deflate" is the zlib format, but some servers send raw deflate.
+	    #[cfg(feature = "deflate")]
+	    Encoding::Deflate => read_limited(flate2::read::ZlibDecoder::new(body), max_length).or_else(|_| read_limited(flate2::read::DeflateDecoder::new(body), max_length)),
+	    #[cfg(feature = "brotli")]
+	    Encoding::Brotli => read_limited(brotli::Decompressor::new(body, 4096), max_length),
+	}
+    }
+}
+impl FromStr for Encoding {
+    type Err = ParseError;
+
+    fn from_str(name: &str) -> Result<Encoding, ParseError> {
+	match name.trim().to_ascii_lowercase().as_str() {
+	    "identity" => Ok(Encoding::Identity),
+	    #[cfg(feature = "gzip")]
+	    "gzip" | "x-gzip" => Ok(Encoding::Gzip),
+	    #[cfg(feature = "deflate")]
+	    "deflate" => Ok(Encoding::Deflate),
+	    #[cfg(feature = "brotli")]
+	    "br" => Ok(Encoding::Brotli),
+	    _ => Err(ParseError::UnsupportedEncoding(name.trim().to_string())),
+	}
+    }
+}
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "{}", self.as_str())
+    }
+}
+
+// Undoes every coding listed in a `Content-Encoding` value, last applied
+// first.
+pub(crate) fn decode_body<'a>(body: &'a [u8], content_encoding: Option<&str>, max_length: usize) -> Result<Cow<'a, [u8]>, ParseError> {
+    let encodings: Vec<Encoding> = match content_encoding {
+	Some(value) => value.split(',').filter(|name| !name.trim().is_empty()).map(Encoding::from_str).collect::<Result<Vec<Encoding>, ParseError>>()?,
+	None => Vec::new(),
+    };
+    let mut decoded: Cow<'a, [u8]> = Cow::Borrowed(body);
+    for encoding in encodings.iter().rev().filter(|encoding| **encoding != Encoding::Identity) {
+	decoded = Cow::Owned(encoding.decode(&decoded, max_length)?);
+    }
+    Ok(decoded)
+}
+
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
+fn read_limited<R: Read>(reader: R, max_length: usize) -> Result<Vec<u8>, ParseError> {
+    let mut decoded: Vec<u8> = Vec::new();
+    reader.take(max_length as u64 + 1).read_to_end(&mut decoded).map_err(reader::io_error)?;
+    within_limit(decoded, max_length)
+}
+
+fn within_limit(decoded: Vec<u8>, max_length: usize) -> Result<Vec<u8>, ParseError> {
+    if decoded.len() > max_length {
+	return Err(ParseError::LimitExceeded("max_body_size".to_string()));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test_encoding {
+    use super::{decode_body, Encoding};
+    use crate::ParseError;
+
+    #[test]
+    fn test_identity() {
+	assert_eq!("Identity".parse(), Ok(Encoding::Identity));
+	assert_eq!(decode_body(b"plain", None, 16).unwrap().as_ref(), b"plain");
+	assert_eq!(decode_body(b"plain", Some("identity"), 16).unwrap().as_ref(), b"plain");
+	assert_eq!(decode_body(b"plain", Some("compress"), 16), Err(ParseError::UnsupportedEncoding("compress".to_string())));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+	let encoded: Vec<u8> = Encoding::Gzip.encode(b"hello hello hello");
+	assert_eq!(&encoded[..2], &[0x1f, 0x8b]);
+	assert_eq!(Encoding::Gzip.decode(&encoded, 64), Ok(b"hello hello hello".to_vec()));
+	assert_eq!(Encoding::Gzip.decode(&encoded, 5), Err(ParseError::LimitExceeded("max_body_size".to_string())));
+	assert!(Encoding::Gzip.decode(b"not gzip", 64).is_err());
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn test_deflate() {
+	let encoded: Vec<u8> = Encoding::Deflate.encode(b"hello");
+	assert_eq!(Encoding::Deflate.decode(&encoded, 64), Ok(b"hello".to_vec()));
+	let raw: Vec<u8> = encoded[2..encoded.len() - 4].to_vec();
+	assert_eq!(Encoding::Deflate.decode(&raw, 64), Ok(b"hello".to_vec()));
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli() {
+	assert_eq!("br".parse(), Ok(Encoding::Brotli));
+	let encoded: Vec<u8> = Encoding::Brotli.encode(b"hello");
+	assert_eq!(Encoding::Brotli.decode(&encoded, 64), Ok(b"hello".to_vec()));
+    }
+
+    #[cfg(all(feature = "gzip", feature = "brotli"))]
+    #[test]
+    fn test_stacked_encodings() {
+	let encoded: Vec<u8> = Encoding::Brotli.encode(&Encoding::Gzip.encode(b"hello"));
+	assert_eq!(decode_body(&encoded, Some("gzip, br"), 64).unwrap().as_ref(), b"hello");
+    }
+}
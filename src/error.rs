@@ -0,0 +1,153 @@
+use crate::prelude::*;
+
+use core::fmt;
+
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseError {
+    InvalidRequestLine(String),
+    UnsupportedMethod(String),
+    UnsupportedVersion(String),
+    InvalidStatusLine(String),
+    UnsupportedStatusCode(String),
+    MalformedHeader(String),
+    ObsoleteLineFolding(String),
+    DuplicateHeader(String),
+    MalformedQuery(String),
+    InvalidMediaType(String),
+    InvalidAuthorization(String),
+    InvalidRange(String),
+    InvalidCacheControl(String),
+    InvalidETag(String),
+    InvalidDate(String),
+    InvalidForwarded(String),
+    InvalidHost(String),
+    MissingHost,
+    InvalidUri(String),
+    InvalidContentLength(String),
+    // The body ended before the length its Content-Length declared.
+    IncompleteBody(String),
+    UnsupportedEncoding(String),
+    InvalidChunk(String),
+    InvalidHar(String),
+    InvalidPseudoHeader(String),
+    LimitExceeded(String),
+    Io(String),
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ParseError::InvalidRequestLine(line) => write!(f, "Invalid request line: `{}`", line),
+	    ParseError::UnsupportedMethod(method) => write!(f, "Unsupported method: `{}`", method),
+	    ParseError::UnsupportedVersion(version) => write!(f, "Unsupported HTTP version: `{}`", version),
+	    ParseError::InvalidStatusLine(line) => write!(f, "Invalid status line: `{}`", line),
+	    ParseError::UnsupportedStatusCode(code) => write!(f, "Unsupported status code: `{}`", code),
+	    ParseError::MalformedHeader(line) => write!(f, "Invalid header line: `{}`", line),
+	    ParseError::ObsoleteLineFolding(line) => write!(f, "Obsolete line folding: `{}`", line),
+	    ParseError::DuplicateHeader(name) => write!(f, "Duplicate header: `{}`", name),
+	    ParseError::MalformedQuery(query) => write!(f, "Invalid query: `{}`", query),
+	    ParseError::InvalidMediaType(value) => write!(f, "Invalid media type: `{}`", value),
+	    ParseError::InvalidRange(value) => write!(f, "Invalid range: `{}`", value),
+	    ParseError::InvalidCacheControl(value) => write!(f, "Invalid Cache-Control: `{}`", value),
+	    ParseError::InvalidETag(value) => write!(f, "Invalid entity tag: `{}`", value),
+	    ParseError::InvalidDate(value) => write!(f, "Invalid HTTP date: `{}`", value),
+	    ParseError::InvalidForwarded(value) => write!(f, "Invalid Forwarded header: `{}`", value),
+	    ParseError::InvalidHost(value) => write!(f, "Invalid Host header: `{}`", value),
+	    ParseError::MissingHost => write!(f, "Missing Host header"),
+	    ParseError::InvalidAuthorization(value) => write!(f, "Invalid authorization: `{}`", value),
+	    ParseError::InvalidUri(target) => write!(f, "Invalid request target: `{}`", target),
+	    ParseError::InvalidContentLength(value) => write!(f, "Invalid Content-Length: `{}`", value),
+	    ParseError::IncompleteBody(received) => write!(f, "Incomplete body: `{}`", received),
+	    ParseError::UnsupportedEncoding(name) => write!(f, "Unsupported content coding: `{}`", name),
+	    ParseError::InvalidChunk(line) => write!(f, "Invalid chunk: `{}`", line),
+	    ParseError::InvalidHar(field) => write!(f, "Invalid HAR entry field: `{}`", field),
+	    ParseError::InvalidPseudoHeader(name) => write!(f, "Invalid pseudo-header: `{}`", name),
+	    ParseError::LimitExceeded(limit) => write!(f, "Parser limit exceeded: `{}`", limit),
+	    ParseError::Io(message) => write!(f, "I/O error: `{}`", message),
+	}
+    }
+}
+impl core::error::Error for ParseError {}
+
+// An error that lenient parsing skipped over instead of failing, such as a
+// malformed header line or query, or an unusable Content-Length.
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseWarning {
+    error: ParseError,
+}
+impl ParseWarning {
+    pub fn error(&self) -> &ParseError {
+	&self.error
+    }
+}
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "Skipped in lenient mode: {}", self.error)
+    }
+}
+impl From<ParseError> for ParseWarning {
+    fn from(error: ParseError) -> ParseWarning {
+	ParseWarning { error }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum BuildError {
+    MissingPath,
+    InvalidMethod(String),
+    InvalidPath(String),
+    InvalidHeaderName(String),
+    InvalidHeaderValue(String),
+}
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    BuildError::MissingPath => write!(f, "Request path was not set"),
+	    BuildError::InvalidMethod(method) => write!(f, "Invalid request method: `{}`", method),
+	    BuildError::InvalidPath(path) => write!(f, "Invalid request path: `{}`", path),
+	    BuildError::InvalidHeaderName(name) => write!(f, "Invalid header name: `{}`", name),
+	    BuildError::InvalidHeaderValue(name) => write!(f, "Invalid value for header `{}`", name),
+	}
+    }
+}
+impl core::error::Error for BuildError {}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum BodyError {
+    MissingContentType,
+    UnexpectedContentType(String),
+    Parse(ParseError),
+    Json(String),
+}
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    BodyError::MissingContentType => write!(f, "Missing Content-Type header"),
+	    BodyError::UnexpectedContentType(media_type) => write!(f, "Unexpected Content-Type: `{}`", media_type),
+	    BodyError::Parse(err) => write!(f, "Invalid body: {}", err),
+	    BodyError::Json(message) => write!(f, "Invalid JSON body: `{}`", message),
+	}
+    }
+}
+impl core::error::Error for BodyError {}
+impl From<ParseError> for BodyError {
+    fn from(err: ParseError) -> BodyError {
+	BodyError::Parse(err)
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum QueryError {
+    InvalidValue(String, String),
+    Deserialize(String),
+}
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    QueryError::InvalidValue(name, value) => write!(f, "Invalid value for query parameter `{}`: `{}`", name, value),
+	    QueryError::Deserialize(message) => write!(f, "Invalid query: `{}`", message),
+	}
+    }
+}
+impl core::error::Error for QueryError {}
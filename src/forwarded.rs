@@ -0,0 +1,282 @@
+use core::fmt;
+use core::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+use crate::prelude::*;
+use crate::media_type::{is_token, unquote};
+use crate::{HeaderMap, ParseError};
+
+// Which proxies in front of the server may be believed about the original
+// client. The forwarding headers are written by the client as much as by
+// proxies, so only entries added by trusted proxies can be relied on.
+#[derive(PartialEq, Clone, Debug)]
+pub enum TrustPolicy {
+    // Ignore the forwarding headers.
+    Never,
+    // Believe every entry; only safe when no client can reach the server
+    // directly and every proxy overwrites the headers.
+    All,
+    // The number of proxies between the client and the server, each of which
+    // appends one entry.
+    Hops(usize),
+    // The addresses of the trusted proxies; entries are skipped from the
+    // nearest one back until an address outside this list is found. The
+    // peer address itself must be checked by the caller.
+    Proxies(Vec<IpAddr>),
+}
+
+// One element of a `Forwarded` header (RFC 7239), describing a single proxy
+// hop. Legacy `X-Forwarded-*` headers are read into the same shape.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Forwarded {
+    by: Option<String>,
+    for_node: Option<String>,
+    host: Option<String>,
+    proto: Option<String>,
+}
+impl Forwarded {
+    pub fn new() -> Forwarded {
+	Forwarded {
+	    by: None,
+	    for_node: None,
+	    host: None,
+	    proto: None,
+	}
+    }
+
+    // Parses a whole `Forwarded` header value, one element per hop, nearest
+    // proxy last. Unknown parameters are ignored.
+    pub fn parse_list(value: &str) -> Result<Vec<Forwarded>, ParseError> {
+	let invalid = || ParseError::InvalidForwarded(value.to_string());
+	let mut elements: Vec<Forwarded> = Vec::new();
+	let mut element: Forwarded = Forwarded::new();
+	let mut remaining: &str = value.trim();
+	while !remaining.is_empty() {
+	    let end: usize = remaining.find([',', ';', '=']).unwrap_or(remaining.len());
+	    let name: &str = remaining[..end].trim();
+	    remaining = &remaining[end..];
+	    if let Some(after) = remaining.strip_prefix('=') {
+		let after: &str = after.trim_start();
+		let parameter: String = if let Some(quoted) = after.strip_prefix('"') {
+		    let (unquoted, length) = unquote(quoted).ok_or_else(invalid)?;
+		    remaining = &quoted[length..];
+		    unquoted
+		} else {
+		    let end: usize = after.find([',', ';']).unwrap_or(after.len());
+		    if !is_token(after[..end].trim()) {
+			return Err(invalid());
+		    }
+		    remaining = &after[end..];
+		    after[..end].trim().to_string()
+		};
+		let field: Option<&mut Option<String>> = match name.to_ascii_lowercase().as_str() {
+		    "by" => Some(&mut element.by),
+		    "for" => Some(&mut element.for_node),
+		    "host" => Some(&mut element.host),
+		    "proto" => Some(&mut element.proto),
+		    _ if is_token(name) => None,
+		    _ => return Err(invalid()),
+		};
+		if let Some(field) = field {
+		    // A parameter may appear only once per element.
+		    if field.replace(parameter).is_some() {
+			return Err(invalid());
+		    }
+		}
+	    } else if !name.is_empty() {
+		return Err(invalid());
+	    }
+
+	    let rest: &str = remaining.trim_start();
+	    remaining = if let Some(rest) = rest.strip_prefix(';') {
+		rest.trim_start()
+	    } else if let Some(rest) = rest.strip_prefix(',') {
+		if element != Forwarded::new() {
+		    elements.push(core::mem::take(&mut element));
+		}
+		rest.trim_start()
+	    } else if rest.is_empty() {
+		rest
+	    } else {
+		return Err(invalid());
+	    };
+	}
+	if element != Forwarded::new() {
+	    elements.push(element);
+	}
+	Ok(elements)
+    }
+
+    // The interface that received the request, usually the proxy itself.
+    pub fn by(&self) -> Option<&str> {
+	self.by.as_deref()
+    }
+
+    pub fn set_by(&mut self, by: &str) {
+	self.by = Some(by.to_string());
+    }
+
+    // The node that made the request to the proxy: an address, possibly with
+    // a port, `unknown`, or an obfuscated identifier such as `_hidden`.
+    pub fn for_node(&self) -> Option<&str> {
+	self.for_node.as_deref()
+    }
+
+    pub fn set_for_node(&mut self, for_node: &str) {
+	self.for_node = Some(for_node.to_string());
+    }
+
+    // Sets `for` to an address, bracketing IPv6 as the header requires.
+    pub fn set_for_ip(&mut self, ip: IpAddr) {
+	match ip {
+	    IpAddr::V4(ip) => self.set_for_node(&ip.to_string()),
+	    IpAddr::V6(ip) => self.set_for_node(&format!("[{}]", ip)),
+	}
+    }
+
+    pub fn host(&self) -> Option<&str> {
+	self.host.as_deref()
+    }
+
+    pub fn set_host(&mut self, host: &str) {
+	self.host = Some(host.to_string());
+    }
+
+    pub fn proto(&self) -> Option<&str> {
+	self.proto.as_deref()
+    }
+
+    pub fn set_proto(&mut self, proto: &str) {
+	self.proto = Some(proto.to_string());
+    }
+
+    // The address in `for`, without its port. None for `unknown` and
+    // obfuscated nodes.
+    pub fn client_ip(&self) -> Option<IpAddr> {
+	node_ip(self.for_node.as_deref()?)
+    }
+}
+impl fmt::Display for Forwarded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	let mut pairs: Vec<String> = Vec::new();
+	for (name, value) in [("for", &self.for_node), ("by", &self.by), ("host", &self.host), ("proto", &self.proto)] {
+	    if let Some(value) = value {
+		if is_token(value) {
+		    pairs.push(format!("{}={}", name, value));
+		} else {
+		    pairs.push(format!("{}=\"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+		}
+	    }
+	}
+	write!(f, "{}", pairs.join(";"))
+    }
+}
+
+// Accepts bracketed IPv6 and ports as in `Forwarded`, as well as the bare
+// IPv6 addresses common in `X-Forwarded-For`.
+fn node_ip(node: &str) -> Option<IpAddr> {
+    let node: &str = node.trim();
+    if let Ok(ip) = node.parse::<IpAddr>() {
+	return Some(ip);
+    }
+    if let Some(bracketed) = node.strip_prefix('[') {
+	return bracketed.split(']').next()?.parse::<Ipv6Addr>().ok().map(IpAddr::V6);
+    }
+    node.parse::<SocketAddr>().ok().map(|address| address.ip())
+}
+
+// Every hop recorded in the headers, client first. `Forwarded` takes
+// precedence; an invalid one yields no hops rather than falling back to the
+// legacy headers. Legacy proto and host lists are aligned to the right of
+// the `X-Forwarded-For` list, since each proxy appends to the end.
+pub(crate) fn hops(headers: &HeaderMap) -> Vec<Forwarded> {
+    if let Some(value) = headers.get_joined("forwarded") {
+	return Forwarded::parse_list(&value).unwrap_or_default();
+    }
+    let list = |name: &str| -> Vec<String> {
+	headers.get_joined(name).unwrap_or_default().split(',').map(str::trim).filter(|value| !value.is_empty()).map(str::to_string).collect()
+    };
+    let mut hops: Vec<Forwarded> = list("x-forwarded-for").iter().map(|node| {
+	let mut hop: Forwarded = Forwarded::new();
+	hop.set_for_node(node);
+	hop
+    }).collect();
+    let length: usize = hops.len();
+    for (i, proto) in list("x-forwarded-proto").iter().rev().enumerate().take(length) {
+	hops[length - 1 - i].set_proto(proto);
+    }
+    for (i, host) in list("x-forwarded-host").iter().rev().enumerate().take(length) {
+	hops[length - 1 - i].set_host(host);
+    }
+    hops
+}
+
+// The hop describing the original client under `trust`.
+pub(crate) fn trusted_hop(headers: &HeaderMap, trust: &TrustPolicy) -> Option<Forwarded> {
+    let mut hops: Vec<Forwarded> = hops(headers);
+    let index: usize = match trust {
+	TrustPolicy::Never => return None,
+	TrustPolicy::All => 0,
+	TrustPolicy::Hops(0) => return None,
+	TrustPolicy::Hops(count) => hops.len().saturating_sub(*count),
+	TrustPolicy::Proxies(proxies) => hops.iter()
+	    .rposition(|hop| hop.client_ip().is_none_or(|ip| !proxies.contains(&ip)))
+	    .unwrap_or(0),
+    };
+    if index < hops.len() { Some(hops.swap_remove(index)) } else { None }
+}
+
+#[cfg(test)]
+mod test_forwarded {
+    use std::net::IpAddr;
+
+    use super::{hops, trusted_hop, Forwarded, TrustPolicy};
+    use crate::{HeaderMap, ParseError};
+
+    #[test]
+    fn test_parse_list() {
+	let elements: Vec<Forwarded> = Forwarded::parse_list("for=\"_gazonk\", For=\"[2001:db8:cafe::17]:4711\";proto=https, for=192.0.2.60;proto=http;by=203.0.113.43;ext=1").unwrap();
+	assert_eq!(elements.len(), 3);
+	assert_eq!(elements[0].for_node(), Some("_gazonk"));
+	assert_eq!(elements[0].client_ip(), None);
+	assert_eq!(elements[1].client_ip(), Some("2001:db8:cafe::17".parse().unwrap()));
+	assert_eq!(elements[1].proto(), Some("https"));
+	assert_eq!(elements[2].by(), Some("203.0.113.43"));
+	assert_eq!(elements[1].to_string(), "for=\"[2001:db8:cafe::17]:4711\";proto=https");
+	assert_eq!(Forwarded::parse_list(&elements[2].to_string()).unwrap(), vec![elements[2].clone()]);
+
+	for value in ["for", "for=a b", "for=1;for=2", "for=\"open", "f@r=1"] {
+	    assert_eq!(Forwarded::parse_list(value), Err(ParseError::InvalidForwarded(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_legacy_headers() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append("X-Forwarded-For", "203.0.113.195, 2001:db8:85a3::8a2e:370:7334");
+	headers.append("X-Forwarded-For", "198.51.100.178:8080");
+	headers.append("X-Forwarded-Proto", "https");
+	let hops: Vec<Forwarded> = hops(&headers);
+	assert_eq!(hops.len(), 3);
+	assert_eq!(hops[1].client_ip(), Some("2001:db8:85a3::8a2e:370:7334".parse().unwrap()));
+	assert_eq!(hops[2].client_ip(), Some("198.51.100.178".parse().unwrap()));
+	assert_eq!(hops[2].proto(), Some("https"));
+	assert_eq!(hops[0].proto(), None);
+    }
+
+    #[test]
+    fn test_trust_policy() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append("Forwarded", "for=1.1.1.1, for=10.0.0.2;proto=https, for=10.0.0.1");
+	let ip = |trust: TrustPolicy| trusted_hop(&headers, &trust).and_then(|hop| hop.client_ip());
+	let address = |address: &str| address.parse::<IpAddr>().ok();
+	assert_eq!(ip(TrustPolicy::Never), None);
+	assert_eq!(ip(TrustPolicy::Hops(0)), None);
+	assert_eq!(ip(TrustPolicy::All), address("1.1.1.1"));
+	assert_eq!(ip(TrustPolicy::Hops(1)), address("10.0.0.1"));
+	assert_eq!(ip(TrustPolicy::Hops(2)), address("10.0.0.2"));
+	assert_eq!(ip(TrustPolicy::Hops(9)), address("1.1.1.1"));
+	let proxies: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+	assert_eq!(ip(TrustPolicy::Proxies(proxies.clone())), address("1.1.1.1"));
+	assert_eq!(ip(TrustPolicy::Proxies(proxies[..1].to_vec())), address("10.0.0.2"));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::prelude::*;
+use crate::{connection, HeaderMap, Method, ParseError, Request, Uri, Version};
+
+// The request as an HTTP/2 header list (RFC 9113 section 8.3): the
+// pseudo-headers first, then the fields with lowercase names. Host becomes
+// `:authority` and connection-specific fields are dropped. Origin-form
+// targets are given the `https` scheme.
+pub(crate) fn to_h2_headers(request: &Request) -> HeaderMap {
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.append(":method", request.method.as_str());
+    let host: Option<&str> = request.headers.get("host").map(|header| header.value().as_str());
+    if request.method == Method::CONNECT {
+	headers.append(":authority", &request.full_path);
+    } else {
+	let target: String = request.target();
+	let (scheme, authority, path) = match Uri::parse(&target) {
+	    Ok(uri) if uri.is_absolute() => {
+		let path: &str = if uri.path().is_empty() { "/" } else { uri.path() };
+		let path: String = match uri.query() {
+		    Some(query) => format!("{}?{}", path, query),
+		    None => path.to_string(),
+		};
+		(uri.scheme().unwrap_or("https").to_string(), uri.authority().or(host).map(str::to_string), path)
+	    }
+	    _ => ("https".to_string(), host.map(str::to_string), target),
+	};
+	headers.append(":scheme", &scheme);
+	if let Some(authority) = authority {
+	    headers.append(":authority", &authority);
+	}
+	headers.append(":path", &path);
+    }
+
+    let options: Vec<String> = connection::connection_options(&request.headers);
+    for header in &request.headers {
+	let name: String = header.name().to_ascii_lowercase();
+	// TE is allowed, but only to announce support for trailers.
+	let forbidden_te: bool = name == "te" && !header.value().trim().eq_ignore_ascii_case("trailers");
+	if name == "host" || forbidden_te || connection::is_connection_specific(&name, &options) {
+	    continue;
+	}
+	headers.append(&name, header.value());
+    }
+    headers
+}
+
+// Rebuilds an HTTP/1.1-style request from an HTTP/2 header list, checking
+// the rules a receiver must enforce: known pseudo-headers, each at most
+// once and before any field, lowercase names and no connection-specific
+// fields. `:authority` becomes the Host header and split Cookie fields are
+// joined again (RFC 9113 section 8.2.3). The body is not included.
+pub(crate) fn from_h2_headers(headers: &HeaderMap) -> Result<Request, ParseError> {
+    let mut method: Option<&str> = None;
+    let mut scheme: Option<&str> = None;
+    let mut authority: Option<&str> = None;
+    let mut path: Option<&str> = None;
+    let mut request: Request = Request::new();
+    let mut cookies: Vec<&str> = Vec::new();
+    for header in headers {
+	let name: &str = header.name();
+	if name.starts_with(':') {
+	    let invalid = || ParseError::InvalidPseudoHeader(name.to_string());
+	    let field: &mut Option<&str> = match name {
+		":method" => &mut method,
+		":scheme" => &mut scheme,
+		":authority" => &mut authority,
+		":path" => &mut path,
+		_ => return Err(invalid()),
+	    };
+	    if !request.headers.is_empty() || !cookies.is_empty() || field.replace(header.value()).is_some() {
+		return Err(invalid());
+	    }
+	    continue;
+	}
+	let forbidden_te: bool = name == "te" && !header.value().trim().eq_ignore_ascii_case("trailers");
+	if name.bytes().any(|byte| byte.is_ascii_uppercase()) || forbidden_te || connection::is_connection_specific(name, &[]) {
+	    return Err(ParseError::MalformedHeader(name.to_string()));
+	}
+	if name == "cookie" {
+	    cookies.push(header.value());
+	} else {
+	    request.headers.append(name, header.value());
+	}
+    }
+
+    request.version = Version::Http2;
+    request.method = method.ok_or_else(|| ParseError::InvalidPseudoHeader(":method".to_string()))?.parse()?;
+    if request.method == Method::CONNECT {
+	if scheme.is_some() || path.is_some() {
+	    return Err(ParseError::InvalidPseudoHeader(if scheme.is_some() { ":scheme" } else { ":path" }.to_string()));
+	}
+	request.full_path = authority.ok_or_else(|| ParseError::InvalidPseudoHeader(":authority".to_string()))?.to_string();
+    } else {
+	scheme.ok_or_else(|| ParseError::InvalidPseudoHeader(":scheme".to_string()))?;
+	let path: &str = path.filter(|path| !path.is_empty()).ok_or_else(|| ParseError::InvalidPseudoHeader(":path".to_string()))?;
+	request.full_path = path.to_string();
+	request.parse_target(path, false)?;
+    }
+    if let Some(authority) = authority {
+	if !request.headers.contains("host") {
+	    request.headers.append("host", authority);
+	}
+    }
+    if !cookies.is_empty() {
+	request.headers.append("cookie", &cookies.join("; "));
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod test_h2 {
+    use crate::{HeaderMap, Method, ParseError, Request, Version};
+
+    fn header_list(headers: &HeaderMap) -> Vec<(&str, &str)> {
+	headers.iter().map(|header| (header.name().as_str(), header.value().as_str())).collect()
+    }
+
+    #[test]
+    fn test_to_h2_headers() {
+	let request: Request = Request::parse("GET /search?q=1 HTTP/1.1\r\nHost: example.com\r\nConnection: keep-alive, X-Hop\r\nX-Hop: 1\r\nTE: gzip\r\nAccept: */*\r\n\r\n").unwrap();
+	assert_eq!(header_list(&request.to_h2_headers()), vec![
+	    (":method", "GET"),
+	    (":scheme", "https"),
+	    (":authority", "example.com"),
+	    (":path", "/search?q=1"),
+	    ("accept", "*/*"),
+	]);
+
+	let request: Request = Request::parse("GET http://example.com:8080 HTTP/1.1\r\nHost: example.com:8080\r\n\r\n").unwrap();
+	assert_eq!(header_list(&request.to_h2_headers())[..4], [(":method", "GET"), (":scheme", "http"), (":authority", "example.com:8080"), (":path", "/")]);
+	let request: Request = Request::parse("CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n").unwrap();
+	assert_eq!(header_list(&request.to_h2_headers()), vec![(":method", "CONNECT"), (":authority", "example.com:443")]);
+    }
+
+    #[test]
+    fn test_from_h2_headers() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append(":method", "POST");
+	headers.append(":scheme", "https");
+	headers.append(":authority", "example.com");
+	headers.append(":path", "/form?x=1");
+	headers.append("cookie", "a=1");
+	headers.append("content-type", "text/plain");
+	headers.append("cookie", "b=2");
+	let request: Request = Request::from_h2_headers(&headers).unwrap();
+	assert_eq!(*request.method(), Method::POST);
+	assert_eq!(*request.version(), Version::Http2);
+	assert_eq!(request.path(), "/form");
+	assert_eq!(request.find_query("x").unwrap().value(), "1");
+	assert_eq!(request.find_header("host").unwrap().value(), "example.com");
+	assert_eq!(request.find_header("cookie").unwrap().value(), "a=1; b=2");
+	assert_eq!(Request::from_h2_headers(&request.to_h2_headers()).unwrap().to_h2_headers().len(), request.to_h2_headers().len());
+    }
+
+    #[test]
+    fn test_malformed() {
+	let build = |headers: &[(&str, &str)]| {
+	    let mut map: HeaderMap = HeaderMap::new();
+	    for (name, value) in headers {
+		map.append(name, value);
+	    }
+	    Request::from_h2_headers(&map).err()
+	};
+	let pseudo = |name: &str| Some(ParseError::InvalidPseudoHeader(name.to_string()));
+	assert_eq!(build(&[(":scheme", "https"), (":path", "/")]), pseudo(":method"));
+	assert_eq!(build(&[(":method", "GET"), (":scheme", "https")]), pseudo(":path"));
+	assert_eq!(build(&[(":method", "GET"), (":method", "GET")]), pseudo(":method"));
+	assert_eq!(build(&[(":method", "GET"), (":status", "200")]), pseudo(":status"));
+	assert_eq!(build(&[(":method", "GET"), ("accept", "*/*"), (":path", "/")]), pseudo(":path"));
+	assert_eq!(build(&[(":method", "CONNECT"), (":authority", "a:443"), (":path", "/")]), pseudo(":path"));
+	assert_eq!(build(&[(":method", "GET"), ("Accept", "*/*")]), Some(ParseError::MalformedHeader("Accept".to_string())));
+	assert_eq!(build(&[(":method", "GET"), ("connection", "close")]), Some(ParseError::MalformedHeader("connection".to_string())));
+	assert_eq!(build(&[(":method", "CONNECT"), (":authority", "a:443")]), None);
+    }
+}
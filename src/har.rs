@@ -0,0 +1,132 @@
+use serde_json::{json, Value};
+
+use crate::{curl, date, HttpDate, Method, ParseError, Request, Uri, Version};
+
+// A HAR 1.2 entry for the request as recorded by browser developer tools.
+// No response was seen, which HAR records as status 0.
+pub(crate) fn to_har_entry(request: &Request) -> Value {
+    let pairs = |pairs: Vec<(&str, &str)>| -> Vec<Value> {
+	pairs.into_iter().map(|(name, value)| json!({ "name": name, "value": value })).collect()
+    };
+    let mut har_request: Value = json!({
+	"method": request.method.as_str(),
+	"url": curl::url(request),
+	"httpVersion": request.version.to_string(),
+	"cookies": pairs(request.cookies().iter().map(|cookie| (cookie.name().as_str(), cookie.value().as_str())).collect()),
+	"headers": pairs(request.headers.iter().map(|header| (header.name().as_str(), header.value().as_str())).collect()),
+	"queryString": pairs(request.query.iter().map(|query| (query.name().as_str(), query.value().as_str())).collect()),
+	"headersSize": -1,
+	"bodySize": request.body.len(),
+    });
+    if !request.body.is_empty() {
+	let mime_type: &str = request.headers.get("content-type").map_or("", |header| header.value().as_str());
+	har_request["postData"] = json!({
+	    "mimeType": mime_type,
+	    "text": String::from_utf8_lossy(&request.body),
+	});
+    }
+    json!({
+	"startedDateTime": date::iso8601(HttpDate::now()),
+	"time": 0,
+	"request": har_request,
+	"response": {
+	    "status": 0,
+	    "statusText": "",
+	    "httpVersion": "",
+	    "cookies": [],
+	    "headers": [],
+	    "content": { "size": 0, "mimeType": "" },
+	    "redirectURL": "",
+	    "headersSize": -1,
+	    "bodySize": -1,
+	},
+	"cache": {},
+	"timings": { "send": 0, "wait": 0, "receive": 0 },
+    })
+}
+
+// Accepts a whole entry or just its `request` object. HTTP/2 pseudo-headers
+// are dropped, and a Host header is added from the URL when missing.
+pub(crate) fn from_har_entry(entry: &Value) -> Result<Request, ParseError> {
+    let har_request: &Value = entry.get("request").unwrap_or(entry);
+    let text = |field: &str| har_request.get(field).and_then(Value::as_str).ok_or_else(|| ParseError::InvalidHar(field.to_string()));
+
+    let mut request: Request = Request::new();
+    request.method = text("method")?.parse::<Method>()?;
+    request.version = match text("httpVersion")?.to_ascii_uppercase().as_str() {
+	"H2" => Version::Http2,
+	"H3" => Version::Http3,
+	version => version.parse()?,
+    };
+    let uri: Uri = Uri::parse(text("url")?)?;
+    let path: &str = if uri.path().is_empty() { "/" } else { uri.path() };
+    request.full_path = match uri.query() {
+	Some(query) => format!("{}?{}", path, query),
+	None => path.to_string(),
+    };
+    let target: String = request.full_path.clone();
+    request.parse_target(&target, true)?;
+
+    let headers: &Vec<Value> = har_request.get("headers").and_then(Value::as_array).ok_or_else(|| ParseError::InvalidHar("headers".to_string()))?;
+    for header in headers {
+	let name: &str = header.get("name").and_then(Value::as_str).ok_or_else(|| ParseError::InvalidHar("headers".to_string()))?;
+	let value: &str = header.get("value").and_then(Value::as_str).ok_or_else(|| ParseError::InvalidHar("headers".to_string()))?;
+	if !name.starts_with(':') {
+	    request.headers.append(name, value);
+	}
+    }
+    if let Some(authority) = uri.authority() {
+	if !request.headers.contains("host") {
+	    request.headers.append("Host", authority.rsplit_once('@').map_or(authority, |(_, host)| host));
+	}
+    }
+    if let Some(body) = har_request.get("postData").and_then(|post_data| post_data.get("text")).and_then(Value::as_str) {
+	request.body = body.as_bytes().to_vec();
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod test_har {
+    use serde_json::{json, Value};
+
+    use crate::{Method, ParseError, Request, Version};
+
+    #[test]
+    fn test_round_trip() {
+	let request: Request = Request::parse("POST /login?next=%2Fhome HTTP/1.1\r\nHost: example.com\r\nCookie: id=1\r\nContent-Type: application/json\r\n\r\n{\"user\":\"a\"}").unwrap();
+	let entry: Value = request.to_har_entry();
+	assert_eq!(entry["request"]["url"], "http://example.com/login?next=%2Fhome");
+	assert_eq!(entry["request"]["cookies"], json!([{ "name": "id", "value": "1" }]));
+	assert_eq!(entry["request"]["queryString"], json!([{ "name": "next", "value": "/home" }]));
+	assert_eq!(entry["request"]["postData"]["mimeType"], "application/json");
+	assert!(entry["startedDateTime"].as_str().unwrap().ends_with('Z'));
+
+	let parsed: Request = Request::from_har_entry(&entry).unwrap();
+	assert_eq!(parsed.build(), request.build());
+    }
+
+    #[test]
+    fn test_from_browser() {
+	let entry: Value = json!({
+	    "request": {
+		"method": "GET",
+		"url": "https://user@example.com:8443?tab=1#top",
+		"httpVersion": "h2",
+		"headers": [
+		    { "name": ":authority", "value": "example.com:8443" },
+		    { "name": "accept", "value": "text/html" },
+		],
+	    }
+	});
+	let request: Request = Request::from_har_entry(&entry).unwrap();
+	assert_eq!(*request.method(), Method::GET);
+	assert_eq!(*request.version(), Version::Http2);
+	assert_eq!(request.full_path(), "/?tab=1");
+	assert_eq!(request.find_query("tab").unwrap().value(), "1");
+	assert_eq!(request.find_header("host").unwrap().value(), "example.com:8443");
+	assert_eq!(request.headers().len(), 2);
+
+	assert_eq!(Request::from_har_entry(&json!({ "request": { "method": "GET", "httpVersion": "HTTP/1.1" } })).err(), Some(ParseError::InvalidHar("url".to_string())));
+    }
+}
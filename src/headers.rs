@@ -0,0 +1,374 @@
+// Without std the name index is ordered instead of hashed.
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use core::fmt;
+use alloc::borrow::Cow;
+use alloc::vec;
+use core::ops::Index;
+use core::slice;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crate::prelude::*;
+use crate::{lint, Header, ParseError};
+
+// Ordered, case-insensitive header collection. Every field line is kept in
+// arrival order; a side index maps lowercased names to their positions so
+// lookups don't scan the whole list.
+#[derive(Clone, Default)]
+pub struct HeaderMap {
+    entries: Vec<Header>,
+    index: HashMap<String, Vec<usize>>,
+}
+impl HeaderMap {
+    pub fn new() -> HeaderMap {
+	HeaderMap {
+	    entries: Vec::new(),
+	    index: HashMap::new(),
+	}
+    }
+
+    pub fn len(&self) -> usize {
+	self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+	self.entries.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+	self.index.contains_key(&name.to_ascii_lowercase())
+    }
+
+    // The first header with this name.
+    pub fn get(&self, name: &str) -> Option<&Header> {
+	self.index.get(&name.to_ascii_lowercase()).map(|positions| &self.entries[positions[0]])
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&Header> {
+	match self.index.get(&name.to_ascii_lowercase()) {
+	    Some(positions) => positions.iter().map(|position| &self.entries[*position]).collect(),
+	    None => Vec::new(),
+	}
+    }
+
+    // Sets a single value for `name`, replacing every existing value. The
+    // header keeps the position and spelling of its first occurrence.
+    pub fn insert(&mut self, name: &str, value: &str) {
+	let key: String = name.to_ascii_lowercase();
+	match self.index.get(&key).cloned() {
+	    Some(positions) => {
+		self.entries[positions[0]].set_value(value.to_string());
+		if positions.len() > 1 {
+		    for position in positions[1..].iter().rev() {
+			self.entries.remove(*position);
+		    }
+		    self.reindex();
+		}
+	    }
+	    None => self.append(name, value),
+	}
+    }
+
+    // Adds another value for `name`, keeping the existing ones.
+    pub fn append(&mut self, name: &str, value: &str) {
+	self.index.entry(name.to_ascii_lowercase()).or_default().push(self.entries.len());
+	self.entries.push(Header::new(name.to_string(), value.to_string()));
+    }
+
+    // Removes every value for `name` and returns them in order.
+    pub fn remove(&mut self, name: &str) -> Vec<Header> {
+	if !self.contains(name) {
+	    return Vec::new();
+	}
+	let (removed, kept): (Vec<Header>, Vec<Header>) = self.entries.drain(..).partition(|header| header.name().eq_ignore_ascii_case(name));
+	self.entries = kept;
+	self.reindex();
+	removed
+    }
+
+    // Every value for `name` combined into one comma-separated list, as for
+    // list-based fields sent on several lines.
+    pub(crate) fn get_joined(&self, name: &str) -> Option<String> {
+	let positions: &Vec<usize> = self.index.get(&name.to_ascii_lowercase())?;
+	Some(positions.iter().map(|position| self.entries[*position].value().as_str()).collect::<Vec<&str>>().join(", "))
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Header> {
+	self.entries.iter()
+    }
+
+    fn reindex(&mut self) {
+	self.index.clear();
+	for (position, header) in self.entries.iter().enumerate() {
+	    self.index.entry(header.name().to_ascii_lowercase()).or_default().push(position);
+	}
+    }
+}
+impl fmt::Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	f.debug_map().entries(self.entries.iter().map(|header| (header.name(), header.value()))).finish()
+    }
+}
+impl Index<usize> for HeaderMap {
+    type Output = Header;
+
+    fn index(&self, position: usize) -> &Header {
+	&self.entries[position]
+    }
+}
+// The value of the first header with this name. Panics when there is none;
+// use `get` when the header may be missing.
+impl Index<&str> for HeaderMap {
+    type Output = str;
+
+    fn index(&self, name: &str) -> &str {
+	match self.get(name) {
+	    Some(header) => header.value(),
+	    None => panic!("no header named `{}`", name),
+	}
+    }
+}
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = &'a Header;
+    type IntoIter = slice::Iter<'a, Header>;
+
+    fn into_iter(self) -> slice::Iter<'a, Header> {
+	self.entries.iter()
+    }
+}
+impl IntoIterator for HeaderMap {
+    type Item = Header;
+    type IntoIter = vec::IntoIter<Header>;
+
+    fn into_iter(self) -> vec::IntoIter<Header> {
+	self.entries.into_iter()
+    }
+}
+// Appends every pair, keeping repeated names as separate values.
+impl<'a> FromIterator<(&'a str, &'a str)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (&'a str, &'a str)>>(headers: I) -> HeaderMap {
+	let mut map: HeaderMap = HeaderMap::new();
+	map.extend(headers);
+	map
+    }
+}
+impl<'a> Extend<(&'a str, &'a str)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (&'a str, &'a str)>>(&mut self, headers: I) {
+	for (name, value) in headers {
+	    self.append(name, value);
+	}
+    }
+}
+
+// Splits a `name: value` field line at the first colon and trims optional
+// whitespace around the value. Strict parsing rejects whitespace before the
+// colon; lenient parsing drops it.
+pub(crate) fn split_header_line(line: &str, strict: bool) -> Option<(&str, &str)> {
+    let colon: usize = memchr::memchr(b':', line.as_bytes())?;
+    let (name, value) = (&line[..colon], &line[colon + 1..]);
+    let name: &str = if strict { name } else { name.trim_end_matches([' ', '\t']) };
+    if name.is_empty() || !name.bytes().all(lint::is_token_char) {
+	return None;
+    }
+    let value: &str = value.trim_matches([' ', '\t']);
+    if value.bytes().any(|byte| byte == b'\r' || byte == b'\n' || byte == 0) {
+	return None;
+    }
+    Some((name, value))
+}
+
+// Fields that may appear only once. A repeat is dropped rather than guessed
+// at, since senders and intermediaries might disagree on which one counts.
+const SINGLETON_FIELDS: [&str; 21] = [
+    "age", "authorization", "content-length", "content-location", "content-range", "content-type", "date",
+    "etag", "expires", "from", "host", "if-modified-since", "if-range", "if-unmodified-since",
+    "last-modified", "location", "max-forwards", "proxy-authorization", "range", "referer", "retry-after",
+];
+
+// List-based fields (RFC 9110 section 5.3) that a repeat is folded into.
+const LIST_FIELDS: [&str; 22] = [
+    "accept", "accept-charset", "accept-encoding", "accept-language", "accept-ranges", "allow",
+    "cache-control", "connection", "content-encoding", "content-language", "expect", "forwarded",
+    "if-match", "if-none-match", "pragma", "te", "trailer", "transfer-encoding", "upgrade", "vary",
+    "via", "x-forwarded-for",
+];
+
+// Adds a parsed field line. A repeated list-based field is combined into the
+// first line's value, Cookie with `; ` (RFC 6265 section 5.4) and the others
+// with `, `; any other repeated field, such as Set-Cookie, is kept as its own
+// line. Returns false without adding anything for a repeated singleton field.
+pub(crate) fn append_field(headers: &mut HeaderMap, name: &str, value: &str) -> bool {
+    let first: &str = match headers.get(name) {
+	Some(header) => header.value(),
+	None => {
+	    headers.append(name, value);
+	    return true;
+	}
+    };
+    let key: String = name.to_ascii_lowercase();
+    let separator: &str = match key.as_str() {
+	key if SINGLETON_FIELDS.contains(&key) => return false,
+	"cookie" => "; ",
+	key if LIST_FIELDS.contains(&key) => ", ",
+	_ => {
+	    headers.append(name, value);
+	    return true;
+	}
+    };
+    // Empty list elements carry nothing and are skipped.
+    let combined: String = match (first.is_empty(), value.is_empty()) {
+	(_, true) => return true,
+	(true, false) => value.to_string(),
+	(false, false) => format!("{}{}{}", first, separator, value),
+    };
+    headers.insert(name, &combined);
+    true
+}
+
+// Joins an obs-fold continuation line onto the field line it continues,
+// replacing the fold with a single space (RFC 9112 section 5.2).
+pub(crate) fn unfold(line: &mut Cow<'_, str>, continuation: &str) {
+    let line: &mut String = line.to_mut();
+    line.push(' ');
+    line.push_str(continuation.trim_matches([' ', '\t']));
+}
+
+pub(crate) fn is_obs_fold(line: &str) -> bool {
+    line.starts_with([' ', '\t'])
+}
+
+// Content-Length is 1*DIGIT; signs, whitespace inside the number and
+// comma-separated lists are rejected.
+pub(crate) fn parse_content_length(value: &str) -> Result<u64, ParseError> {
+    let digits: &str = value.trim_matches([' ', '\t']);
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+	return Err(ParseError::InvalidContentLength(value.to_string()));
+    }
+    digits.parse().map_err(|_| ParseError::InvalidContentLength(value.to_string()))
+}
+
+// The body is chunked when `chunked` is the final transfer coding.
+pub(crate) fn is_chunked(headers: &HeaderMap) -> bool {
+    headers.get("transfer-encoding")
+	.is_some_and(|header| header.value().rsplit(',').next().unwrap_or("").trim().eq_ignore_ascii_case("chunked"))
+}
+
+// Appends the header lines of a message head. With `body_length` set, the
+// Content-Length header is rewritten to match it, or added for a non-empty
+// body. A message using Transfer-Encoding never gets one (RFC 9112 section
+// 6.2).
+pub(crate) fn write_header_lines(head: &mut String, headers: &HeaderMap, body_length: Option<usize>) {
+    let framed: bool = headers.contains("transfer-encoding");
+    let mut wrote_length: bool = false;
+    for header in headers {
+	if header.name().eq_ignore_ascii_case("content-length") {
+	    if framed {
+		continue;
+	    }
+	    if let Some(length) = body_length {
+		if !wrote_length {
+		    head.push_str(&format!("{}: {}\r\n", header.name(), length));
+		    wrote_length = true;
+		}
+		continue;
+	    }
+	}
+	head.push_str(&format!("{}: {}\r\n", header.name(), header.value()));
+    }
+    if let Some(length) = body_length.filter(|length| *length > 0 && !wrote_length && !framed) {
+	head.push_str(&format!("Content-Length: {}\r\n", length));
+    }
+}
+
+#[cfg(test)]
+mod test_header_map {
+    use super::{append_field, parse_content_length, split_header_line, HeaderMap};
+    use crate::ParseError;
+
+    #[test]
+    fn test_get_is_case_insensitive() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append("Content-Type", "text/plain");
+	assert!(headers.contains("content-type"));
+	assert_eq!(headers.get("CONTENT-TYPE").unwrap().value(), "text/plain");
+	assert!(headers.get("Accept").is_none());
+    }
+
+    #[test]
+    fn test_multiple_values() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append("Accept", "text/html");
+	headers.append("Host", "localhost");
+	headers.append("accept", "application/json");
+	assert_eq!(headers.len(), 3);
+	let values: Vec<&str> = headers.get_all("Accept").iter().map(|header| header.value().as_str()).collect();
+	assert_eq!(values, vec!["text/html", "application/json"]);
+
+	headers.insert("ACCEPT", "*/*");
+	assert_eq!(headers.len(), 2);
+	assert_eq!(headers[0].name(), "Accept");
+	assert_eq!(headers[0].value(), "*/*");
+	assert_eq!(headers.get("host").unwrap().value(), "localhost");
+    }
+
+    #[test]
+    fn test_split_header_line() {
+	assert_eq!(split_header_line("Host: localhost", true), Some(("Host", "localhost")));
+	assert_eq!(split_header_line("Host:localhost\t", true), Some(("Host", "localhost")));
+	assert_eq!(split_header_line("X-Time: 12:30", true), Some(("X-Time", "12:30")));
+	assert_eq!(split_header_line("Host : localhost", true), None);
+	assert_eq!(split_header_line("Host : localhost", false), Some(("Host", "localhost")));
+	assert_eq!(split_header_line(" Host: localhost", false), None);
+	assert_eq!(split_header_line("Host localhost", false), None);
+    }
+
+    #[test]
+    fn test_append_field() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	for (name, value) in [("Accept", "text/html"), ("accept", "*/*"), ("Cookie", "a=1"), ("Set-Cookie", "a=1"), ("Cookie", "b=2"), ("Set-Cookie", "b=2"), ("Vary", ""), ("vary", "Origin"), ("Accept", "")] {
+	    assert!(append_field(&mut headers, name, value));
+	}
+	assert!(append_field(&mut headers, "Host", "a"));
+	assert!(!append_field(&mut headers, "host", "b"));
+	assert_eq!(headers.get_all("host").len(), 1);
+	assert_eq!(headers.get("host").unwrap().value(), "a");
+	assert_eq!(&headers["accept"], "text/html, */*");
+	assert_eq!(&headers["cookie"], "a=1; b=2");
+	assert_eq!(&headers["vary"], "Origin");
+	assert_eq!(headers.get_all("set-cookie").len(), 2);
+    }
+
+    #[test]
+    fn test_parse_content_length() {
+	assert_eq!(parse_content_length("42"), Ok(42));
+	assert_eq!(parse_content_length(" 0 "), Ok(0));
+	for value in ["", "+5", "-1", "1 2", "4,4", "0x10", "99999999999999999999"] {
+	    assert_eq!(parse_content_length(value), Err(ParseError::InvalidContentLength(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_iterators() {
+	let mut headers: HeaderMap = [("Accept", "text/html"), ("Host", "localhost")].into_iter().collect();
+	headers.extend([("accept", "*/*")]);
+	assert_eq!(&headers["ACCEPT"], "text/html");
+	assert_eq!(headers.get_all("accept").len(), 2);
+	let names: Vec<String> = headers.into_iter().map(|header| header.name().clone()).collect();
+	assert_eq!(names, vec!["Accept", "Host", "accept"]);
+    }
+
+    #[test]
+    fn test_remove_keeps_order() {
+	let mut headers: HeaderMap = HeaderMap::new();
+	headers.append("A", "1");
+	headers.append("B", "2");
+	headers.append("a", "3");
+	headers.append("C", "4");
+	assert_eq!(headers.remove("A").len(), 2);
+	assert!(headers.remove("missing").is_empty());
+	let names: Vec<&str> = headers.iter().map(|header| header.name().as_str()).collect();
+	assert_eq!(names, vec!["B", "C"]);
+	assert_eq!(headers.get("c").unwrap().value(), "4");
+    }
+}
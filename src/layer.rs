@@ -0,0 +1,322 @@
+use alloc::boxed::Box;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::prelude::*;
+use crate::{HeaderMap, Request, Response, StatusCode};
+
+// Why a layer refused a request, and the status to answer it with.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Rejection {
+    status: StatusCode,
+    reason: String,
+}
+impl Rejection {
+    pub fn new(status: StatusCode, reason: &str) -> Rejection {
+	Rejection {
+	    status,
+	    reason: reason.to_string(),
+	}
+    }
+
+    pub fn status(&self) -> StatusCode {
+	self.status
+    }
+
+    pub fn reason(&self) -> &str {
+	&self.reason
+    }
+
+    // The status with the reason as a plain-text body. The connection is
+    // closed, since the rest of the request may not have been read.
+    pub fn to_response(&self) -> Response {
+	let mut response: Response = Response::new();
+	response.set_status(self.status);
+	response.set_header("Content-Type", "text/plain; charset=utf-8");
+	response.set_body(&self.reason);
+	response.set_keep_alive(false);
+	response
+    }
+}
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "Request rejected with {}: `{}`", self.status, self.reason)
+    }
+}
+impl core::error::Error for Rejection {}
+
+// A step run on each request before it reaches the handler, e.g. to enforce
+// limits or add headers. Closures taking `&mut Request` are layers too.
+//
+// `on_request` sees the head before the body is read, so a rejection spares
+// reading an unwanted upload; `on_body` runs again once the body is in.
+pub trait Layer {
+    fn on_request(&self, request: &mut Request) -> Result<(), Rejection>;
+
+    fn on_body(&self, _request: &mut Request) -> Result<(), Rejection> {
+	Ok(())
+    }
+}
+impl<F: Fn(&mut Request) -> Result<(), Rejection>> Layer for F {
+    fn on_request(&self, request: &mut Request) -> Result<(), Rejection> {
+	self(request)
+    }
+}
+
+// Layers run in the order they were pushed, and the first rejection stops
+// the chain. A chain is itself a layer, so chains can be nested.
+#[derive(Default)]
+pub struct Chain {
+    layers: Vec<Box<dyn Layer + Send + Sync>>,
+}
+impl Chain {
+    pub fn new() -> Chain {
+	Chain { layers: Vec::new() }
+    }
+
+    pub fn push<L: Layer + Send + Sync + 'static>(&mut self, layer: L) {
+	self.layers.push(Box::new(layer));
+    }
+
+    pub fn len(&self) -> usize {
+	self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+	self.layers.is_empty()
+    }
+}
+impl Layer for Chain {
+    fn on_request(&self, request: &mut Request) -> Result<(), Rejection> {
+	self.layers.iter().try_for_each(|layer| layer.on_request(request))
+    }
+
+    fn on_body(&self, request: &mut Request) -> Result<(), Rejection> {
+	self.layers.iter().try_for_each(|layer| layer.on_body(request))
+    }
+}
+
+// Rejects requests larger than the limits. The declared Content-Length is
+// checked on the head, and the body itself once read, which also covers
+// chunked uploads.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct SizeLimit {
+    max_body_size: usize,
+    max_header_bytes: usize,
+}
+impl SizeLimit {
+    pub fn new(max_body_size: usize, max_header_bytes: usize) -> SizeLimit {
+	SizeLimit { max_body_size, max_header_bytes }
+    }
+
+    pub fn max_body_size(&self) -> usize {
+	self.max_body_size
+    }
+
+    // The combined size of the header field lines, as sent on the wire.
+    pub fn max_header_bytes(&self) -> usize {
+	self.max_header_bytes
+    }
+}
+impl Layer for SizeLimit {
+    fn on_request(&self, request: &mut Request) -> Result<(), Rejection> {
+	let header_bytes: usize = request.headers.iter().map(|header| header.name().len() + header.value().len() + 4).sum();
+	if header_bytes > self.max_header_bytes {
+	    return Err(Rejection::new(StatusCode::RequestHeaderFieldsTooLarge, "header fields too large"));
+	}
+	let declared: u64 = request.content_length().ok().flatten().unwrap_or(0);
+	if request.body.len() > self.max_body_size || declared > self.max_body_size as u64 {
+	    return Err(Rejection::new(StatusCode::ContentTooLarge, "body too large"));
+	}
+	Ok(())
+    }
+
+    fn on_body(&self, request: &mut Request) -> Result<(), Rejection> {
+	if request.body.len() > self.max_body_size {
+	    return Err(Rejection::new(StatusCode::ContentTooLarge, "body too large"));
+	}
+	Ok(())
+    }
+}
+
+// Rewrites header names to their conventional capitalization, e.g.
+// `content-type` to `Content-Type`, and trims whitespace around values, so
+// handlers and logs see one spelling whatever the client sent.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct NormalizeHeaders;
+impl NormalizeHeaders {
+    pub fn new() -> NormalizeHeaders {
+	NormalizeHeaders
+    }
+}
+impl Layer for NormalizeHeaders {
+    fn on_request(&self, request: &mut Request) -> Result<(), Rejection> {
+	request.headers = normalize(&request.headers);
+	request.trailers = normalize(&request.trailers);
+	Ok(())
+    }
+}
+
+fn normalize(headers: &HeaderMap) -> HeaderMap {
+    let mut normalized: HeaderMap = HeaderMap::new();
+    for header in headers {
+	let mut name: String = String::with_capacity(header.name().len());
+	for (i, part) in header.name().split('-').enumerate() {
+	    if i > 0 {
+		name.push('-');
+	    }
+	    let mut chars = part.chars();
+	    if let Some(first) = chars.next() {
+		name.push(first.to_ascii_uppercase());
+		name.extend(chars.map(|c| c.to_ascii_lowercase()));
+	    }
+	}
+	normalized.append(&name, header.value().trim_matches([' ', '\t']));
+    }
+    normalized
+}
+
+// Tags each request with an ID in `X-Request-Id` (or another header) for
+// correlating logs. A well-formed ID sent by the client is kept unless
+// `set_trust_incoming(false)`; otherwise IDs are generated from a counter,
+// prefixed with the layer's creation time when `std` is available so they
+// stay unique across restarts.
+#[derive(Debug)]
+pub struct RequestId {
+    header: String,
+    prefix: String,
+    trust_incoming: bool,
+    counter: AtomicUsize,
+}
+impl Default for RequestId {
+    fn default() -> RequestId {
+	RequestId::new()
+    }
+}
+impl RequestId {
+    pub fn new() -> RequestId {
+	#[cfg(feature = "std")]
+	let prefix: String = std::time::SystemTime::now()
+	    .duration_since(std::time::UNIX_EPOCH)
+	    .map_or(String::new(), |elapsed| format!("{:x}", elapsed.as_micros()));
+	#[cfg(not(feature = "std"))]
+	let prefix: String = String::new();
+	RequestId {
+	    header: "X-Request-Id".to_string(),
+	    prefix,
+	    trust_incoming: true,
+	    counter: AtomicUsize::new(0),
+	}
+    }
+
+    pub fn header(&self) -> &str {
+	&self.header
+    }
+
+    pub fn set_header(&mut self, header: &str) {
+	self.header = header.to_string();
+    }
+
+    pub fn prefix(&self) -> &str {
+	&self.prefix
+    }
+
+    // Prepended to every generated ID, e.g. a host or instance name.
+    pub fn set_prefix(&mut self, prefix: &str) {
+	self.prefix = prefix.to_string();
+    }
+
+    pub fn trust_incoming(&self) -> bool {
+	self.trust_incoming
+    }
+
+    pub fn set_trust_incoming(&mut self, trust_incoming: bool) {
+	self.trust_incoming = trust_incoming;
+    }
+
+    fn next_id(&self) -> String {
+	let count: usize = self.counter.fetch_add(1, Ordering::Relaxed) + 1;
+	if self.prefix.is_empty() { format!("{:08x}", count) } else { format!("{}-{:08x}", self.prefix, count) }
+    }
+}
+impl Layer for RequestId {
+    fn on_request(&self, request: &mut Request) -> Result<(), Rejection> {
+	// Anything else could smuggle control characters or huge values into
+	// log lines.
+	let valid = |id: &str| !id.is_empty() && id.len() <= 128 && id.bytes().all(|byte| byte.is_ascii_graphic());
+	let incoming: bool = request.headers.get_all(&self.header).len() == 1 && request.headers.get(&self.header).is_some_and(|header| valid(header.value()));
+	if !(self.trust_incoming && incoming) {
+	    request.headers.insert(&self.header, &self.next_id());
+	}
+	Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_layer {
+    use super::{Chain, Layer, NormalizeHeaders, Rejection, RequestId, SizeLimit};
+    use crate::prelude::*;
+    use crate::{Request, StatusCode};
+
+    #[test]
+    fn test_chain() {
+	let mut chain: Chain = Chain::new();
+	chain.push(NormalizeHeaders::new());
+	chain.push(|request: &mut Request| {
+	    if request.path().starts_with("/admin") {
+		return Err(Rejection::new(StatusCode::Forbidden, "admin only"));
+	    }
+	    request.set_header("X-Seen", "1");
+	    Ok(())
+	});
+	let mut outer: Chain = Chain::new();
+	outer.push(chain);
+	outer.push(SizeLimit::new(4, 1024));
+	assert_eq!(outer.len(), 2);
+
+	let mut request: Request = Request::parse("POST /a HTTP/1.1\r\nhost:  a \r\ncontent-LENGTH: 2\r\n\r\nhi").unwrap();
+	assert_eq!(outer.on_request(&mut request), Ok(()));
+	let names: Vec<&str> = request.headers().iter().map(|header| header.name().as_str()).collect();
+	assert_eq!(names, vec!["Host", "Content-Length", "X-Seen"]);
+	assert_eq!(request.find_header("host").unwrap().value(), "a");
+
+	let mut request: Request = Request::parse("GET /admin HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	let rejection: Rejection = outer.on_request(&mut request).unwrap_err();
+	assert_eq!(rejection.status(), StatusCode::Forbidden);
+	assert!(request.find_header("x-seen").is_none());
+	assert_eq!(rejection.to_response().build(), "HTTP/1.1 403 Forbidden\r\nContent-Type: text/plain; charset=utf-8\r\nConnection: close\r\nContent-Length: 10\r\n\r\nadmin only");
+    }
+
+    #[test]
+    fn test_size_limit() {
+	let limit: SizeLimit = SizeLimit::new(4, 64);
+	let mut request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+	assert_eq!(limit.on_request(&mut request).map_err(|rejection| rejection.status()), Err(StatusCode::ContentTooLarge));
+	let mut head: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 500\r\n\r\n").unwrap();
+	assert_eq!(limit.on_request(&mut head).map_err(|rejection| rejection.status()), Err(StatusCode::ContentTooLarge));
+	let mut request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n").unwrap();
+	assert_eq!(Chain::new().on_body(&mut request), Ok(()));
+	assert_eq!(limit.on_body(&mut request).map_err(|rejection| rejection.status()), Err(StatusCode::ContentTooLarge));
+	let mut request: Request = Request::parse(&format!("GET / HTTP/1.1\r\nHost: a\r\nCookie: {}\r\n\r\n", "x".repeat(64))).unwrap();
+	assert_eq!(limit.on_request(&mut request).map_err(|rejection| rejection.status()), Err(StatusCode::RequestHeaderFieldsTooLarge));
+    }
+
+    #[test]
+    fn test_request_id() {
+	let mut layer: RequestId = RequestId::new();
+	layer.set_prefix("web1");
+	let mut request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	layer.on_request(&mut request).unwrap();
+	assert_eq!(request.find_header("x-request-id").unwrap().value(), "web1-00000001");
+	layer.on_request(&mut request).unwrap();
+	assert_eq!(request.find_header("x-request-id").unwrap().value(), "web1-00000001");
+
+	let mut request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\nX-Request-Id: bad id\r\n\r\n").unwrap();
+	layer.on_request(&mut request).unwrap();
+	assert_eq!(request.find_header("x-request-id").unwrap().value(), "web1-00000002");
+	layer.set_trust_incoming(false);
+	layer.on_request(&mut request).unwrap();
+	assert_eq!(request.find_header("x-request-id").unwrap().value(), "web1-00000003");
+	assert_eq!(request.find_headers("x-request-id").len(), 1);
+    }
+}
@@ -1,7 +1,98 @@
-use log::{warn, error};
-use std::fmt;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-#[derive(Clone)]
+// Without the default `std` feature the crate needs only `core` and `alloc`;
+// reader/writer integration and the system clock are left out.
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Index;
+use core::net::IpAddr;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::path::{Component, Path, PathBuf};
+
+use prelude::*;
+
+#[cfg(feature = "tokio")]
+mod async_io;
+mod authorization;
+mod base64;
+mod borrowed;
+mod builder;
+mod cache;
+mod chunked;
+#[cfg(feature = "std")]
+pub mod client;
+mod config;
+mod connection;
+mod cookie;
+mod curl;
+mod date;
+mod display;
+mod encoding;
+mod error;
+mod forwarded;
+mod h2;
+#[cfg(feature = "serde")]
+mod har;
+mod headers;
+pub mod layer;
+pub mod lint;
+mod media_type;
+mod parser;
+mod prelude;
+pub mod proxy;
+#[cfg(feature = "serde")]
+mod query_params;
+mod range;
+#[cfg(feature = "std")]
+mod reader;
+mod response;
+pub mod router;
+mod scan;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "std")]
+pub mod server;
+mod sha1;
+mod status;
+mod uri;
+mod url;
+mod validate;
+mod version;
+pub mod websocket;
+
+pub use authorization::Authorization;
+pub use borrowed::{HeaderRef, RequestRef};
+pub use builder::RequestBuilder;
+pub use cache::{CacheControl, ETag, ETagMatch};
+pub use config::{ParseMode, ParserConfig};
+pub use connection::KeepAlive;
+pub use cookie::{Cookie, CookieJar, SameSite};
+pub use date::HttpDate;
+pub use display::MessageDisplay;
+pub use encoding::Encoding;
+pub use error::{BodyError, BuildError, ParseError, ParseWarning, QueryError};
+pub use forwarded::{Forwarded, TrustPolicy};
+pub use headers::HeaderMap;
+pub use media_type::MediaType;
+pub use parser::{ParseStatus, RequestParser};
+pub use range::{ByteRange, Range};
+pub use response::Response;
+pub use status::StatusCode;
+pub use uri::Uri;
+pub use url::{url_decode, url_encode};
+pub use validate::ValidationError;
+pub use version::Version;
+
+const FAST_PATH_MAX_LENGTH: usize = 1024;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header {
     name: String,
     value: String,
@@ -9,8 +100,8 @@ pub struct Header {
 impl Header {
     pub fn new(name: String, value: String) -> Header {
 	Header {
-	    name: name,
-	    value: value,
+	    name,
+	    value,
 	}
     }
 
@@ -31,7 +122,8 @@ impl Header {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query {
     name: String,
     value: String,
@@ -39,8 +131,8 @@ pub struct Query {
 impl Query {
     pub fn new(name: String, value: String) -> Query {
 	Query {
-	    name: name,
-	    value: value,
+	    name,
+	    value,
 	}
     }
     
@@ -61,7 +153,10 @@ impl Query {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+// Any method token outside the standard nine parses as `Extension`, e.g.
+// WebDAV's `PROPFIND`. Methods compare by name, so `Extension("GET")` equals
+// `GET`.
+#[derive(Clone, Debug)]
 pub enum Method {
     GET,
     POST,
@@ -72,428 +167,1350 @@ pub enum Method {
     CONNECT,
     TRACE,
     PATCH,
+    Extension(String),
+}
+impl Method {
+    pub fn as_str(&self) -> &str {
+	match self {
+	    Method::GET => "GET",
+	    Method::POST => "POST",
+	    Method::PUT => "PUT",
+	    Method::DELETE => "DELETE",
+	    Method::HEAD => "HEAD",
+	    Method::OPTIONS => "OPTIONS",
+	    Method::CONNECT => "CONNECT",
+	    Method::TRACE => "TRACE",
+	    Method::PATCH => "PATCH",
+	    Method::Extension(method) => method,
+	}
+    }
+
+    // Whether repeating the request has the same effect as sending it once
+    // (RFC 9110 section 9.2.2), so it may be retried after a failure.
+    pub fn is_idempotent(&self) -> bool {
+	matches!(self, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE)
+    }
+}
+impl PartialEq for Method {
+    fn eq(&self, other: &Method) -> bool {
+	self.as_str() == other.as_str()
+    }
+}
+impl Eq for Method {}
+impl Hash for Method {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+	self.as_str().hash(state);
+    }
 }
 impl fmt::Display for Method {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-	match self {
-	    Method::GET => write!(f, "GET"),
-	    Method::POST => write!(f, "POST"),
-	    Method::PUT => write!(f, "PUT"),
-	    Method::DELETE => write!(f, "DELETE"),
-	    Method::HEAD => write!(f, "HEAD"),
-	    Method::OPTIONS => write!(f, "OPTIONS"),
-	    Method::CONNECT => write!(f, "CONNECT"),
-	    Method::TRACE => write!(f, "TRACE"),
-	    Method::PATCH => write!(f, "PATCH"),
+	write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for Method {
+    type Err = ParseError;
+
+    fn from_str(method: &str) -> Result<Method, ParseError> {
+	match method {
+	    "GET" => Ok(Method::GET),
+	    "POST" => Ok(Method::POST),
+	    "PUT" => Ok(Method::PUT),
+	    "DELETE" => Ok(Method::DELETE),
+	    "HEAD" => Ok(Method::HEAD),
+	    "OPTIONS" => Ok(Method::OPTIONS),
+	    "CONNECT" => Ok(Method::CONNECT),
+	    "TRACE" => Ok(Method::TRACE),
+	    "PATCH" => Ok(Method::PATCH),
+	    _ if !method.is_empty() && method.bytes().all(lint::is_token_char) => Ok(Method::Extension(method.to_string())),
+	    _ => Err(ParseError::UnsupportedMethod(method.to_string())),
 	}
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Request {
-    headers: Vec<Header>,
+    headers: HeaderMap,
     query: Vec<Query>,
-    body: String,
+    body: Vec<u8>,
     method: Method,
     full_path: String,
     path: String,
-    initialized: bool,
-    version: String,
+    version: Version,
+    warnings: Vec<ParseWarning>,
+    percent_encoding: bool,
+    auto_content_length: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    trailers: HeaderMap,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    anomalies: Vec<ValidationError>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    config: ParserConfig,
 }
 impl fmt::Display for Request {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-	if !self.initialized {
-	    return write!(f, "Request read not initialized");
-	}
-	
-	let mut headers: String = String::new();
-	if self.headers.len() > 0 {
-	    headers.push_str(&format!("\x1B[1mHeaders:\n\x1B[0m"));
-	    for header in &self.headers {
-		headers.push_str(&format!("  \"{}\": \"{}\"\r\n", header.name(), header.value()));
-	    }
-	}
-
-	let mut query_str: String = String::new();
- 	if self.query.len() > 0 {
-	    query_str.push_str(&format!("\x1B[1mQueries:\n\x1B[0m"));
-	    for query in &self.query {
-		query_str.push_str(&format!("  \"{}\" = \"{}\"\n", query.name(), query.value()));
-	    }
-	}
+	MessageDisplay::request(self).fmt(f)
+    }
+}
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	f.debug_struct("Request")
+	    .field("method", &self.method)
+	    .field("target", &self.target())
+	    .field("version", &self.version)
+	    .field("headers", &self.headers)
+	    .field("trailers", &self.trailers)
+	    .field("body", &String::from_utf8_lossy(&self.body))
+	    .field("warnings", &self.warnings)
+	    .finish_non_exhaustive()
+    }
+}
+// The value of the first header with this name, e.g. `request["host"]`.
+// Panics when there is none; use `find_header` when it may be missing.
+impl Index<&str> for Request {
+    type Output = str;
 
-	let mut body_str: String = String::new();
-	if self.body.len() > 0 {
-	    body_str.push_str(&format!("\x1B[1mBody:\n\x1B[0m  \""));
-	    body_str.push_str(&self.body.clone());
-	    body_str.push_str(&format!("\""));
-	}
-	
-        write!(f, "\x1B[1mRequest:\x1B[0m\n  {} {} {}\n{}{}{}", self.method, self.path, self.version, headers, query_str, body_str)
+    fn index(&self, name: &str) -> &str {
+	&self.headers[name]
+    }
+}
+impl Default for Request {
+    fn default() -> Request {
+	Request::new()
     }
 }
 impl Request {
     pub fn new() -> Request {
 	Request {
-	    headers: Vec::new(),
+	    headers: HeaderMap::new(),
 	    query: Vec::new(),
-	    body: String::new(),
+	    body: Vec::new(),
 	    method: Method::GET,
 	    path: String::new(),
 	    full_path: String::new(),
-	    version: "HTTP/1.1".to_string(),
-	    initialized: false,	    
+	    version: Version::Http11,
+	    warnings: Vec::new(),
+	    percent_encoding: true,
+	    auto_content_length: true,
+	    trailers: HeaderMap::new(),
+	    anomalies: Vec::new(),
+	    config: ParserConfig::new(),
 	}
     }
 
-    pub fn headers(&self) -> &Vec<Header> {
-	if !self.initialized {
-	    warn!("Request headers read not initialized");
-	}
+    pub fn headers(&self) -> &HeaderMap {
 	&self.headers
     }
 
     pub fn query(&self) -> &Vec<Query> {
-	if !self.initialized {
-	    warn!("Request queries read not initialized");
-	}
 	&self.query
     }
 
-    pub fn body(&self) -> &String {
-	if !self.initialized {
-	    warn!("Request body read not initialized");
-	}
-	&self.body
+    // The body as text; bytes that are not valid UTF-8 are replaced with U+FFFD.
+    // Use `body_bytes` for binary payloads.
+    pub fn body(&self) -> Cow<'_, str> {
+	String::from_utf8_lossy(&self.body)
     }
 
     pub fn set_body(&mut self, body: &str) {
-	self.initialized = true;
-	self.body = body.to_string();
+	self.set_body_bytes(body.as_bytes());
+    }
+
+    pub fn body_bytes(&self) -> &[u8] {
+	&self.body
+    }
+
+    pub fn set_body_bytes(&mut self, body: &[u8]) {
+	self.body = body.to_vec();
+    }
+
+    // The body with every `Content-Encoding` coding removed, limited to the
+    // configured `max_body_size`.
+    pub fn decoded_body(&self) -> Result<Cow<'_, [u8]>, ParseError> {
+	let content_encoding: Option<String> = self.headers.get_joined("content-encoding");
+	encoding::decode_body(&self.body, content_encoding.as_deref(), self.config.max_body_size())
+    }
+
+    // Deserializes a JSON body. The Content-Type must be JSON
+    // (`application/json` or a `+json` type) and any Content-Encoding is
+    // removed first.
+    #[cfg(feature = "serde")]
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, BodyError> {
+	match self.content_type()? {
+	    Some(media_type) if media_type.is_json() => {}
+	    Some(media_type) => return Err(BodyError::UnexpectedContentType(media_type.essence())),
+	    None => return Err(BodyError::MissingContentType),
+	}
+	serde_json::from_slice(&self.decoded_body()?).map_err(|err| BodyError::Json(err.to_string()))
     }
 
-    pub fn version(&self) -> &String {
-	if !self.initialized {
-	    warn!("Request version read not initialized");
+    #[cfg(feature = "serde")]
+    pub fn set_json<T: serde::Serialize>(&mut self, value: &T) -> Result<(), BodyError> {
+	let body: Vec<u8> = serde_json::to_vec(value).map_err(|err| BodyError::Json(err.to_string()))?;
+	self.set_header("Content-Type", "application/json");
+	self.set_header("Content-Length", &body.len().to_string());
+	self.remove_header("Content-Encoding");
+	self.set_body_bytes(&body);
+	Ok(())
+    }
+
+    // Sets the body compressed with `encoding` and a matching
+    // `Content-Encoding` header.
+    pub fn set_body_compressed(&mut self, body: &[u8], encoding: Encoding) {
+	self.set_body_bytes(&encoding.encode(body));
+	if encoding == Encoding::Identity {
+	    self.remove_header("Content-Encoding");
+	} else {
+	    self.set_header("Content-Encoding", encoding.as_str());
 	}
+    }
+
+    pub fn version(&self) -> &Version {
 	&self.version
     }
 
-    pub fn set_version(&mut self, version: &str) {
-	self.initialized = true;
-	self.version = version.to_string();
+    pub fn set_version(&mut self, version: Version) {
+	self.version = version;
     }
 
     pub fn method(&self) -> &Method {
-	if !self.initialized {
-	    warn!("Request method read not initialized");
-	}
 	&self.method
     }
 
     pub fn set_method(&mut self, method: Method) {
-	self.initialized = true;
 	self.method = method;
     }
 
     pub fn full_path(&self) -> &String {
-	if !self.initialized {
-	    warn!("Request full path read not initialized");
-	}
 	&self.full_path
     }
 
     pub fn set_full_path(&mut self, full_path: String) {
-	self.initialized = true;
 	self.full_path = full_path;
     }
 
     pub fn path(&self) -> &String {
-	if !self.initialized {
-	    warn!("Request path read not initialized");
-	}
 	&self.path
     }
 
     pub fn set_path(&mut self, path: &str) {
-	self.initialized = true;
 	self.path = path.to_string();
     }
 
+    // The request target, reflecting any changes made to the path and query.
+    pub fn uri(&self) -> Option<Uri> {
+	Uri::parse(&self.target()).ok()
+    }
+
+    pub fn percent_encoding(&self) -> bool {
+	self.percent_encoding
+    }
+
+    // When enabled (the default), the path and query are percent-decoded while
+    // parsing and percent-encoded by `build`. Disable it before parsing to keep
+    // the raw values.
+    pub fn set_percent_encoding(&mut self, enabled: bool) {
+	self.percent_encoding = enabled;
+    }
+
+    pub fn auto_content_length(&self) -> bool {
+	self.auto_content_length
+    }
+
+    // When enabled (the default), `build`, `to_bytes` and `write_to` set the
+    // Content-Length header from the actual body size.
+    pub fn set_auto_content_length(&mut self, enabled: bool) {
+	self.auto_content_length = enabled;
+    }
+
+    pub fn config(&self) -> &ParserConfig {
+	&self.config
+    }
+
+    // The limits enforced by `parse_from_str`; set them before parsing.
+    pub fn set_config(&mut self, config: ParserConfig) {
+	self.config = config;
+    }
+
     pub fn find_header(&self, name: &str) -> Option<&Header> {
-	if !self.initialized {
-	    warn!("Request headers read not initialized");
-	}
-	self.headers.iter().find(|header| header.name().to_lowercase() == name.to_lowercase())
+	self.headers.get(name)
+    }
+
+    pub fn find_headers(&self, name: &str) -> Vec<&Header> {
+	self.headers.get_all(name)
     }
 
     pub fn set_header(&mut self, header_name: &str, header_value: &str) {
-	self.initialized = true;
-	if self.headers.iter().any(|header| header.name().to_lowercase() == header_name.to_lowercase()) {
-	    let header: &mut Header = self.headers.iter_mut().find(|header| header.name().to_lowercase() == header_name.to_lowercase()).unwrap();
-	    header.set_value(header_value.to_string());
-	} else {
-	    self.headers.push(Header::new(header_name.to_string(), header_value.to_string()));
-	}
+	self.headers.insert(header_name, header_value);
     }
 
     pub fn add_header(&mut self, header_name: &str, header_value: &str) {
-	self.initialized = true;
-	if self.headers.iter().any(|h| h.name().to_lowercase() == header_name.to_lowercase()) {
-	    self.set_header(header_name, header_value);
-	    return;
-	}
-	self.headers.push(Header::new(header_name.to_string(), header_value.to_string()));
+	self.set_header(header_name, header_value);
+    }
+
+    // Adds another value for the header, keeping any existing ones.
+    pub fn append_header(&mut self, header_name: &str, header_value: &str) {
+	self.headers.append(header_name, header_value);
+    }
+
+    pub fn remove_header(&mut self, header_name: &str) -> Vec<Header> {
+	self.headers.remove(header_name)
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+	&mut self.headers
+    }
+
+    // Appends every name/value pair, keeping existing values.
+    pub fn extend_headers<'a, I: IntoIterator<Item = (&'a str, &'a str)>>(&mut self, headers: I) {
+	self.headers.extend(headers);
+    }
+
+    // Fields sent after the last chunk of a chunked body. Filled in when a
+    // chunked body is read and written by `to_chunked_bytes`.
+    pub fn trailers(&self) -> &HeaderMap {
+	&self.trailers
+    }
+
+    pub fn set_trailer(&mut self, name: &str, value: &str) {
+	self.trailers.insert(name, value);
     }
 
     pub fn find_query(&self, name: &str) -> Option<&Query> {
-	if !self.initialized {
-	    warn!("Request queries read not initialized");
-	}
 	self.query.iter().find(|query| query.name() == name)
     }
 
+    // The first value of the parameter converted with `FromStr`, e.g.
+    // `request.query_as::<u32>("page")`. None when the parameter is absent.
+    pub fn query_as<T: FromStr>(&self, name: &str) -> Result<Option<T>, QueryError> {
+	match self.find_query(name) {
+	    Some(query) => query.value().parse().map(Some).map_err(|_| QueryError::InvalidValue(name.to_string(), query.value().clone())),
+	    None => Ok(None),
+	}
+    }
+
+    // Deserializes the whole query string into `T`, typically a struct of
+    // pagination or filter parameters.
+    #[cfg(feature = "serde")]
+    pub fn query_params<T: serde::de::DeserializeOwned>(&self) -> Result<T, QueryError> {
+	query_params::from_query(&self.query)
+    }
+
+    // Every value of a repeated parameter, in order. Array-style keys are
+    // treated as the same parameter, so `tag` also matches `tag[]=a&tag[]=b`.
+    pub fn query_all(&self, name: &str) -> Vec<&str> {
+	let name: &str = name.strip_suffix("[]").unwrap_or(name);
+	self.query.iter()
+	    .filter(|query| query.name().strip_suffix("[]").unwrap_or(query.name()) == name)
+	    .map(|query| query.value().as_str())
+	    .collect()
+    }
+
+    // Sets a single value, replacing every existing value of the parameter.
+    // The parameter keeps the position of its first occurrence.
     pub fn set_query(&mut self, query_name: &str, query_value: &str) {
-	self.initialized = true;
-	if self.query.iter().any(|query| query.name() == query_name) {
-	    let query: &mut Query = self.query.iter_mut().find(|query| query.name() == query_name).unwrap();
-	    query.set_value(query_value.to_string());
-	} else {
-	    self.query.push(Query::new(query_name.to_string(), query_value.to_string()));
+	match self.query.iter().position(|query| query.name() == query_name) {
+	    Some(position) => {
+		self.query[position].set_value(query_value.to_string());
+		let mut index: usize = 0;
+		self.query.retain(|query| {
+		    index += 1;
+		    index - 1 == position || query.name() != query_name
+		});
+	    }
+	    None => self.query.push(Query::new(query_name.to_string(), query_value.to_string())),
 	}
     }
 
+    // Adds another value, keeping the existing ones.
     pub fn add_query(&mut self, query_name: &str, query_value: &str) {
-	self.initialized = true;
-	if self.query.iter().any(|q| q.name() == query_name) {
-	    self.set_query(query_name, query_value);
-	    return;
-	}
 	self.query.push(Query::new(query_name.to_string(), query_value.to_string()));
     }
 
-    pub fn content_type(&self) -> Option<String> {
-	if !self.initialized {
-	    warn!("Request content type read not initialized");
-	}
-	let header: Option<&Header> = self.headers.iter().find(|header| header.name() == "content-type");
-	match header {
-	    Some(header) => Some(header.value().clone()),
-	    None => None,
-	}
+    // Removes every parameter with this name and returns them in order.
+    pub fn remove_query(&mut self, query_name: &str) -> Vec<Query> {
+	let (removed, kept): (Vec<Query>, Vec<Query>) = self.query.drain(..).partition(|query| query.name() == query_name);
+	self.query = kept;
+	removed
     }
 
-    pub fn content_length(&self) -> Option<String> {
-	if !self.initialized {
-	    warn!("Request content length read not initialized");
-	}
-	let header: Option<&Header> = self.headers.iter().find(|header| header.name() == "content-length");
-	match header {
-	    Some(header) => Some(header.value().clone()),
-	    None => None,
-	}
+    // Decodes an application/x-www-form-urlencoded body into name/value pairs.
+    pub fn form(&self) -> Vec<Query> {
+	self.body().split('&')
+	    .filter(|pair| !pair.is_empty())
+	    .map(|pair| match pair.split_once('=') {
+		Some((name, value)) => Query::new(url::decode_query(name), url::decode_query(value)),
+		None => Query::new(url::decode_query(pair), String::new()),
+	    })
+	    .collect()
     }
 
-    pub fn parse_from_str(&mut self, request: &str) {
-	self.parse_request(request.to_string());
+    pub fn set_form(&mut self, fields: &[(&str, &str)]) {
+	let body: String = fields.iter()
+	    .map(|(name, value)| format!("{}={}", url::encode_form(name), url::encode_form(value)))
+	    .collect::<Vec<String>>()
+	    .join("&");
+	self.set_body(&body);
+	self.set_header("Content-Type", "application/x-www-form-urlencoded");
+	self.set_header("Content-Length", &body.len().to_string());
     }
 
-    pub fn build(&self) -> String {
-	let mut lines: Vec<String> = Vec::new();
-	
-	let mut new_path: String = self.path.clone();
-	for query in &self.query {
-	    new_path.push_str(&format!("{}{}={}", if new_path.contains("?") { "&" } else { "?" }, query.name(), query.value()));
+    pub fn cookies(&self) -> Vec<Cookie> {
+	match self.find_header("cookie") {
+	    Some(header) => cookie::parse_cookie_header(header.value()),
+	    None => Vec::new(),
 	}
-	
-	lines.push(format!("{} {} {}", self.method, new_path, self.version));
-	for header in &self.headers {
-	    lines.push(format!("{}: {}", header.name(), header.value()));
+    }
+
+    pub fn find_cookie(&self, name: &str) -> Option<Cookie> {
+	self.cookies().into_iter().find(|cookie| cookie.name() == name)
+    }
+
+    pub fn set_cookie(&mut self, cookie_name: &str, cookie_value: &str) {
+	let mut cookies: Vec<Cookie> = self.cookies();
+	match cookies.iter_mut().find(|cookie| cookie.name() == cookie_name) {
+	    Some(cookie) => cookie.set_value(cookie_value),
+	    None => cookies.push(Cookie::new(cookie_name, cookie_value)),
 	}
+	self.set_header("Cookie", &cookie::build_cookie_header(&cookies));
+    }
 
-	return format!("{}\r\n\r\n{}", lines.join("\r\n"), self.body);
+    pub fn content_type(&self) -> Result<Option<MediaType>, ParseError> {
+	self.headers.get("content-type").map(|header| MediaType::parse(header.value())).transpose()
     }
-    
-    fn parse_request(&mut self, request: String) {
-	let mut body_lines: Vec<&str> = Vec::new();
-	let mut read_body: bool = false;
-	for (i, line) in request.lines().enumerate() {
-	    if i == 0 {
-		self.parse_method_line(line);
-		continue;
-	    } else if line.is_empty() {
-		if self.method == Method::POST || self.method == Method::PUT {
-		    read_body = true;
-		}
-		continue;
-	    }
 
-	    if read_body {
-		body_lines.push(line);
-		continue;
-	    } else {
-		if line.contains(": ") {
-		    self.parse_header_line(line);
-		}
+    // None when the header is absent; an error when its value is not a number.
+    pub fn content_length(&self) -> Result<Option<u64>, ParseError> {
+	self.headers.get("content-length").map(|header| headers::parse_content_length(header.value())).transpose()
+    }
+
+    // Reports constructs that let a proxy and a server disagree on where the
+    // request ends: Content-Length alongside Transfer-Encoding, conflicting
+    // Content-Length values, CR, LF or NUL in a header value and whitespace
+    // before a header colon. Includes duplicates and whitespace the lenient
+    // parser dropped, so servers can reject the message instead.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+	validate::validate(self)
+    }
+
+    pub fn authorization(&self) -> Result<Option<Authorization>, ParseError> {
+	self.headers.get("authorization").map(|header| Authorization::parse(header.value())).transpose()
+    }
+
+    pub fn set_basic_auth(&mut self, user: &str, password: &str) {
+	self.set_header("Authorization", &Authorization::basic(user, password).to_string());
+    }
+
+    pub fn set_bearer_auth(&mut self, token: &str) {
+	self.set_header("Authorization", &Authorization::bearer(token).to_string());
+    }
+
+    pub fn date(&self) -> Result<Option<HttpDate>, ParseError> {
+	self.headers.get("date").map(|header| HttpDate::parse(header.value())).transpose()
+    }
+
+    pub fn set_date(&mut self, date: HttpDate) {
+	self.set_header("Date", &date.to_string());
+    }
+
+    #[cfg(feature = "std")]
+    pub fn set_date_now(&mut self) {
+	self.set_date(HttpDate::now());
+    }
+
+    // The parsed `Range` header; see `Range::spans` for serving 206 responses.
+    pub fn range(&self) -> Result<Option<Range>, ParseError> {
+	self.headers.get("range").map(|header| Range::parse(header.value())).transpose()
+    }
+
+    pub fn cache_control(&self) -> Result<Option<CacheControl>, ParseError> {
+	self.headers.get_joined("cache-control").map(|value| CacheControl::parse(&value)).transpose()
+    }
+
+    pub fn if_match(&self) -> Result<Option<ETagMatch>, ParseError> {
+	self.headers.get_joined("if-match").map(|value| ETagMatch::parse(&value)).transpose()
+    }
+
+    pub fn if_none_match(&self) -> Result<Option<ETagMatch>, ParseError> {
+	self.headers.get_joined("if-none-match").map(|value| ETagMatch::parse(&value)).transpose()
+    }
+
+    pub fn if_modified_since(&self) -> Result<Option<HttpDate>, ParseError> {
+	self.headers.get("if-modified-since").map(|header| HttpDate::parse(header.value())).transpose()
+    }
+
+    pub fn if_unmodified_since(&self) -> Result<Option<HttpDate>, ParseError> {
+	self.headers.get("if-unmodified-since").map(|header| HttpDate::parse(header.value())).transpose()
+    }
+
+    // Whether the connection should stay open after this request, following
+    // the `Connection` header and the version's default.
+    pub fn keep_alive(&self) -> bool {
+	connection::is_keep_alive(&self.headers, self.version)
+    }
+
+    pub fn set_keep_alive(&mut self, keep_alive: bool) {
+	connection::set_keep_alive(&mut self.headers, self.version, keep_alive);
+    }
+
+    // The lowercased options of the `Connection` header, e.g. `upgrade`.
+    pub fn connection_options(&self) -> Vec<String> {
+	connection::connection_options(&self.headers)
+    }
+
+    pub fn keep_alive_header(&self) -> Option<KeepAlive> {
+	self.headers.get("keep-alive").map(|header| KeepAlive::parse(header.value()))
+    }
+
+    // Whether the client waits for `100 Continue` (see
+    // `Response::continue_response`) before sending the body. The
+    // expectation is ignored for HTTP/1.0 requests.
+    pub fn expects_continue(&self) -> bool {
+	self.version != Version::Http10 && self.version != Version::Http09
+	    && self.headers.get("expect").is_some_and(|header| header.value().trim().eq_ignore_ascii_case("100-continue"))
+    }
+
+    // The host the request is for. The authority of an absolute- or
+    // authority-form target takes precedence over the Host header. IPv6
+    // literals keep their brackets, e.g. `[::1]`.
+    pub fn host(&self) -> Result<Option<String>, ParseError> {
+	Ok(self.authority()?.map(|(host, _)| host))
+    }
+
+    // The port given alongside `host`, if any; default ports are not filled
+    // in.
+    pub fn port(&self) -> Result<Option<u16>, ParseError> {
+	Ok(self.authority()?.and_then(|(_, port)| port))
+    }
+
+    fn authority(&self) -> Result<Option<(String, Option<u16>)>, ParseError> {
+	if let Ok(uri) = Uri::parse(&self.full_path) {
+	    if let Some(host) = uri.host() {
+		return Ok(Some((host.to_string(), uri.port())));
 	    }
 	}
-	self.body = body_lines.join("\r\n");
-	self.initialized = true;
-    }
-
-    fn parse_method_line(&mut self, line: &str) {
-	let parts: Vec<&str> = line.split(" ").collect();
-	if parts.len() != 3 {
-	    error!("Invalid request line: `{}`", line);
-	    return;
-	}
-	self.method = match parts[0] {
-	    "GET" => Method::GET,
-	    "POST" => Method::POST,
-	    "PUT" => Method::PUT,
-	    "DELETE" => Method::DELETE,
-	    "HEAD" => Method::HEAD,
-	    "OPTIONS" => Method::OPTIONS,
-	    "CONNECT" => Method::CONNECT,
-	    "TRACE" => Method::TRACE,
-	    "PATCH" => Method::PATCH,
-	    _ => {
-		error!("Unsupported method: `{}`", parts[0]);
-		return;
-	    }
+	let value: &str = match self.headers.get("host") {
+	    Some(header) if !header.value().trim().is_empty() => header.value().trim(),
+	    _ => return Ok(None),
 	};
-	self.full_path = parts[1].to_string();
-	self.parse_query_string(parts[1]);
+	match uri::split_host(value) {
+	    Some((host, port)) => Ok(Some((host.to_string(), port))),
+	    None => Err(ParseError::InvalidHost(value.to_string())),
+	}
     }
 
-    fn parse_query_string(&mut self, string: &str) {
-	let parts: Vec<&str> = string.split("?").collect();
-	let path: &str = parts[0];
-	self.path = path.to_string();
-	if parts.len() == 2 {
-	    let query_string: &str = parts[1];
-	    let queries: Vec<&str> = query_string.split("&").collect();
-	    for query in queries {
-		let query_parts: Vec<&str> = query.split("=").collect();
-		if query_parts.len() != 2 {
-		    error!("Invalid query: `{}`", query);
-		    continue;
-		}
-		let query: Query = Query::new(query_parts[0].to_string(), query_parts[1].to_string());
-		self.query.push(query);
-	    }
-	};
+    // The path with dot segments resolved and repeated slashes collapsed.
+    // Percent-decoding happens exactly once, before normalization, so encoded
+    // dot segments are resolved too and `%252e` stays a literal `%2e`.
+    pub fn normalized_path(&self) -> String {
+	let path: String = if self.percent_encoding { self.path.clone() } else { url_decode(&self.path) };
+	uri::remove_dot_segments(&path)
     }
 
-    fn parse_header_line(&mut self, line: &str) {
-	let parts: Vec<&str> = line.split(": ").collect();
-	if parts.len() != 2 {
-	    error!("Invalid header line: `{}`", line);
-	    return
+    // Maps the path to a file below `root`. None when a segment could still
+    // change directory once decoded, e.g. one containing a backslash, a NUL
+    // or a drive prefix, so the result never escapes `root`. Symbolic links
+    // inside `root` are not checked.
+    #[cfg(feature = "std")]
+    pub fn resolve_under(&self, root: &Path) -> Option<PathBuf> {
+	let mut resolved: PathBuf = root.to_path_buf();
+	for segment in self.normalized_path().split('/').filter(|segment| !segment.is_empty()) {
+	    let mut components = Path::new(segment).components();
+	    let safe: bool = !segment.contains(['\\', '\0']) && matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+	    if !safe {
+		return None;
+	    }
+	    resolved.push(segment);
 	}
+	Some(resolved)
+    }
 
-	if self.headers.iter().any(|header| header.name().to_lowercase() == parts[0].to_lowercase()) {
-	    return;
-	}
-	let header: Header = Header::new(parts[0].to_string(), parts[1].to_string());
-	self.headers.push(header);
+    // An equivalent `curl` command line for reproducing the request. The URL
+    // assumes `http` unless the target is in absolute form.
+    pub fn to_curl(&self) -> String {
+	curl::to_curl(self)
     }
-}
 
-#[cfg(test)]
-mod test_header {
-    use super::Header;
+    // The request as a HAR 1.2 entry, e.g. to add to a `log.entries` array.
+    #[cfg(feature = "serde")]
+    pub fn to_har_entry(&self) -> serde_json::Value {
+	har::to_har_entry(self)
+    }
 
-    #[test]
-    fn test_new() {
-	let header: Header = Header::new("name".to_string(), "value".to_string());
-	assert_eq!(header.name(), "name");
-	assert_eq!(header.value(), "value");
+    // Reads the request from a HAR entry exported by browser developer tools.
+    #[cfg(feature = "serde")]
+    pub fn from_har_entry(entry: &serde_json::Value) -> Result<Request, ParseError> {
+	har::from_har_entry(entry)
     }
-}
 
-#[cfg(test)]
-mod test_query {
-    use super::Query;
-    
-    #[test]
-    fn test_new() {
-	let query: Query = Query::new("name".to_string(), "value".to_string());
-	assert_eq!(query.name(), "name");
-	assert_eq!(query.value(), "value");
+    // The head as an HTTP/2 header list, pseudo-headers first, for handing to
+    // an HTTP/2 framing library.
+    pub fn to_h2_headers(&self) -> HeaderMap {
+	h2::to_h2_headers(self)
     }
-}
 
-#[cfg(test)]
-mod test_method {
-    use super::Method;
-	
-	#[test]
-    fn test_display() {
-	assert_eq!(format!("{}", Method::GET), "GET");
-	assert_eq!(format!("{}", Method::POST), "POST");
-	assert_eq!(format!("{}", Method::PUT), "PUT");
-	assert_eq!(format!("{}", Method::DELETE), "DELETE");
-	assert_eq!(format!("{}", Method::HEAD), "HEAD");
-	assert_eq!(format!("{}", Method::OPTIONS), "OPTIONS");
-	assert_eq!(format!("{}", Method::CONNECT), "CONNECT");
-	assert_eq!(format!("{}", Method::TRACE), "TRACE");
-	assert_eq!(format!("{}", Method::PATCH), "PATCH");
+    // The request described by an HTTP/2 header list, as an HTTP/1.1-style
+    // request with a Host header. Set the body separately.
+    pub fn from_h2_headers(headers: &HeaderMap) -> Result<Request, ParseError> {
+	h2::from_h2_headers(headers)
     }
-}
 
-#[cfg(test)]
-mod test_request {
-    use super::{Request, Method};
-    
-    #[test]
-    fn test_new() {
-	let request: Request = Request::new();
-	assert_eq!(request.headers().len(), 0);
-	assert_eq!(request.query().len(), 0);
-	assert_eq!(request.body(), "");
-	assert_eq!(*request.method(), Method::GET);
-	assert_eq!(request.path(), "");
+    // A plain-text view with credentials and cookies masked, for logs.
+    pub fn redacted(&self) -> MessageDisplay<'_> {
+	MessageDisplay::request(self).redacted()
     }
 
-    #[test]
-    fn test_parse_from_str() {
-	let mut request: Request = Request::new();
-	request.parse_from_str("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
-	assert_eq!(request.headers().len(), 1);
-	assert_eq!(request.headers[0].name, "Host");
-	assert_eq!(request.headers[0].value, "localhost");
-	assert_eq!(request.query().len(), 0);
-	assert_eq!(request.body(), "");
-	assert_eq!(*request.method(), Method::GET);
-	assert_eq!(request.path(), "/");
+    // The `Display` output with ANSI-highlighted section titles.
+    pub fn colored(&self) -> MessageDisplay<'_> {
+	MessageDisplay::request(self).colored()
     }
 
-    #[test]
-    fn test_parse_from_str_with_query() {
-	let mut request: Request = Request::new();
-	request.parse_from_str("GET /?name=value&test=test2 HTTP/1.1\r\nHost: localhost\r\n\r\n");
-	assert_eq!(request.headers().len(), 1);
-	assert_eq!(request.headers[0].name, "Host");
-	assert_eq!(request.headers[0].value, "localhost");
-	assert_eq!(request.query().len(), 2);
-	assert_eq!(request.query[0].name, "name");
-	assert_eq!(request.query[0].value, "value");
-	assert_eq!(request.query[1].name, "test");
-	assert_eq!(request.query[1].value, "test2");
-	assert_eq!(request.body(), "");
-	assert_eq!(*request.method(), Method::GET);
-	assert_eq!(request.path(), "/");
+    pub fn is_websocket_upgrade(&self) -> bool {
+	websocket::is_upgrade(self)
     }
 
-    #[test]
-    fn test_parse_from_str_with_query_and_body() {
-	let mut request: Request = Request::new();
-	request.parse_from_str("POST /?name=value HTTP/1.1\r\nHost: localhost\r\nContent-Type: plain\r\n\r\nbody");
-	assert_eq!(request.headers().len(), 2);
-	assert_eq!(request.headers[0].name, "Host");
-	assert_eq!(request.headers[0].value, "localhost");
-	assert_eq!(request.headers[1].name, "Content-Type");
+    // The elements of the `Forwarded` header, client first.
+    pub fn forwarded(&self) -> Result<Vec<Forwarded>, ParseError> {
+	self.headers.get_joined("forwarded").map(|value| Forwarded::parse_list(&value)).transpose().map(Option::unwrap_or_default)
+    }
+
+    // The original client's address according to `Forwarded`, or
+    // `X-Forwarded-For` when there is no `Forwarded` header, believing only
+    // the proxies allowed by `trust`.
+    pub fn client_ip(&self, trust: &TrustPolicy) -> Option<IpAddr> {
+	forwarded::trusted_hop(&self.headers, trust)?.client_ip()
+    }
+
+    // The scheme the client used, from `proto` or `X-Forwarded-Proto`.
+    pub fn original_scheme(&self, trust: &TrustPolicy) -> Option<String> {
+	forwarded::trusted_hop(&self.headers, trust)?.proto().map(str::to_string)
+    }
+
+    // The host the client asked for, from `host` or `X-Forwarded-Host`.
+    pub fn original_host(&self, trust: &TrustPolicy) -> Option<String> {
+	forwarded::trusted_hop(&self.headers, trust)?.host().map(str::to_string)
+    }
+
+    // Adds a hop to the `Forwarded` header, as a proxy does before passing
+    // the request on.
+    pub fn append_forwarded(&mut self, forwarded: &Forwarded) {
+	self.append_list("Forwarded", &forwarded.to_string());
+    }
+
+    // Adds a hop to the legacy `X-Forwarded-For`, `X-Forwarded-Proto` and
+    // `X-Forwarded-Host` headers.
+    pub fn append_x_forwarded(&mut self, client: IpAddr, proto: &str, host: &str) {
+	self.append_list("X-Forwarded-For", &client.to_string());
+	self.append_list("X-Forwarded-Proto", proto);
+	self.append_list("X-Forwarded-Host", host);
+    }
+
+    // The entries of the `Via` header, e.g. `1.1 proxy.example`.
+    pub fn via(&self) -> Vec<String> {
+	self.headers.get_joined("via").unwrap_or_default().split(',').map(str::trim).filter(|entry| !entry.is_empty()).map(str::to_string).collect()
+    }
+
+    // Adds this proxy to `Via` under the request's protocol version.
+    pub fn append_via(&mut self, received_by: &str) {
+	let version: String = self.version.to_string();
+	self.append_list("Via", &format!("{} {}", version.trim_start_matches("HTTP/"), received_by));
+    }
+
+    fn append_list(&mut self, name: &str, value: &str) {
+	let value: String = match self.headers.get_joined(name) {
+	    Some(existing) => format!("{}, {}", existing, value),
+	    None => value.to_string(),
+	};
+	self.set_header(name, &value);
+    }
+
+    // Whether a GET or HEAD can be answered with 304 Not Modified, given the
+    // current validators of the selected representation (RFC 9110 section
+    // 13.2.2). If-Modified-Since is only consulted without If-None-Match,
+    // and invalid condition headers are ignored.
+    pub fn is_not_modified(&self, etag: Option<&ETag>, last_modified: Option<HttpDate>) -> bool {
+	if self.method != Method::GET && self.method != Method::HEAD {
+	    return false;
+	}
+	if let Ok(Some(if_none_match)) = self.if_none_match() {
+	    return etag.is_some_and(|etag| if_none_match.matches_weak(etag)) || if_none_match == ETagMatch::Any;
+	}
+	match (self.if_modified_since(), last_modified) {
+	    (Ok(Some(since)), Some(last_modified)) => last_modified <= since,
+	    _ => false,
+	}
+    }
+
+    // Whether the request should be answered with 412 Precondition Failed:
+    // If-Match (or, without it, If-Unmodified-Since) does not hold, or
+    // If-None-Match matches on a method other than GET and HEAD.
+    pub fn is_precondition_failed(&self, etag: Option<&ETag>, last_modified: Option<HttpDate>) -> bool {
+	if let Ok(Some(if_match)) = self.if_match() {
+	    if !(if_match == ETagMatch::Any || etag.is_some_and(|etag| if_match.matches_strong(etag))) {
+		return true;
+	    }
+	} else if let (Ok(Some(since)), Some(last_modified)) = (self.if_unmodified_since(), last_modified) {
+	    if last_modified > since {
+		return true;
+	    }
+	}
+	if self.method == Method::GET || self.method == Method::HEAD {
+	    return false;
+	}
+	match self.if_none_match() {
+	    Ok(Some(if_none_match)) => if_none_match == ETagMatch::Any || etag.is_some_and(|etag| if_none_match.matches_weak(etag)),
+	    _ => false,
+	}
+    }
+
+    pub fn parse(request: &str) -> Result<Request, ParseError> {
+	let mut parsed: Request = Request::new();
+	parsed.parse_from_str(request)?;
+	Ok(parsed)
+    }
+
+    pub fn parse_with_config(request: &str, config: &ParserConfig) -> Result<Request, ParseError> {
+	let mut parsed: Request = Request::new();
+	parsed.set_config(*config);
+	parsed.parse_from_str(request)?;
+	Ok(parsed)
+    }
+
+    // Parses raw bytes, e.g. straight from a socket buffer, without requiring
+    // UTF-8. Head lines that are not UTF-8 are read as Latin-1 so obs-text in
+    // field values survives, and everything after the blank line is kept as
+    // the body byte for byte. The method and field names must be tokens and
+    // field values may not contain control characters other than tab.
+    pub fn parse_from_bytes(request: &[u8]) -> Result<Request, ParseError> {
+	Request::parse_from_bytes_with_config(request, &ParserConfig::default())
+    }
+
+    pub fn parse_from_bytes_with_config(request: &[u8], config: &ParserConfig) -> Result<Request, ParseError> {
+	let mut head_end: Option<(usize, usize)> = scan::find_head_end(request).map(|position| (position, 4));
+	if config.mode() == ParseMode::Lenient {
+	    if let Some(position) = memchr::memmem::find(request, b"\n\n") {
+		if head_end.is_none_or(|(end, _)| position < end) {
+		    head_end = Some((position, 2));
+		}
+	    }
+	}
+	let (head, body): (&[u8], &[u8]) = match head_end {
+	    Some((position, length)) => (&request[..position + length], &request[position + length..]),
+	    None => (request, &[]),
+	};
+	config.check_head_size(head.len())?;
+
+	let mut decoded: String = String::new();
+	for (i, line) in head.split(|byte| *byte == b'\n').enumerate() {
+	    if i > 0 {
+		decoded.push('\n');
+	    }
+	    match core::str::from_utf8(line) {
+		Ok(line) => decoded.push_str(line),
+		Err(_) => decoded.extend(line.iter().map(|byte| char::from(*byte))),
+	    }
+	}
+
+	let mut parsed: Request = Request::new();
+	parsed.set_config(*config);
+	parsed.parse_from_str(&decoded)?;
+	for header in &parsed.headers {
+	    if header.value().chars().any(|c| (c < ' ' && c != '\t') || c == '\x7f') {
+		return Err(ParseError::MalformedHeader(format!("{}: {}", header.name(), header.value())));
+	    }
+	}
+	parsed.frame_body(body, false)?;
+	Ok(parsed)
+    }
+
+    // Like `parse`, but never fails: anything that could not be understood,
+    // including the request line, is skipped and recorded in `warnings()`.
+    pub fn parse_lenient(request: &str) -> Request {
+	let mut parsed: Request = Request::new();
+	let _ = match parsed.parse_fast(request, true) {
+	    Some(result) => result,
+	    None => parsed.parse_request(request, true),
+	};
+	parsed
+    }
+
+    // Reads one request from `reader` using the default `ParserConfig`.
+    #[cfg(feature = "std")]
+    pub fn parse_from_reader<R: Read>(reader: &mut R) -> Result<Request, ParseError> {
+	reader::read_request(reader, &ParserConfig::default())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn parse_from_reader_with_config<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+	reader::read_request(reader, config)
+    }
+
+    // Reads only the request line and headers, leaving the body in `reader`
+    // for `read_body_from_reader`. Used to answer `Expect: 100-continue`
+    // before the client sends the body.
+    #[cfg(feature = "std")]
+    pub fn parse_head_from_reader<R: Read>(reader: &mut R) -> Result<Request, ParseError> {
+	reader::read_head(reader, &ParserConfig::default())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn parse_head_from_reader_with_config<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+	reader::read_head(reader, config)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_body_from_reader<R: Read>(&mut self, reader: &mut R) -> Result<(), ParseError> {
+	reader::read_body(reader, self)
+    }
+
+    // Parses the first request in `bytes` and returns it with the number of
+    // bytes it spans, leaving any pipelined requests after it untouched.
+    // Returns None when `bytes` holds only part of a request. Unlike `parse`,
+    // the body is framed by Content-Length or chunked encoding.
+    #[cfg(feature = "std")]
+    pub fn parse_prefix(bytes: &[u8]) -> Result<Option<(Request, usize)>, ParseError> {
+	reader::parse_prefix(bytes, &ParserConfig::default())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn parse_prefix_with_config(bytes: &[u8], config: &ParserConfig) -> Result<Option<(Request, usize)>, ParseError> {
+	reader::parse_prefix(bytes, config)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn parse_from_async_reader<R: tokio::io::AsyncRead + Unpin>(reader: &mut R) -> Result<Request, ParseError> {
+	async_io::read_request(reader, &ParserConfig::default()).await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn parse_from_async_reader_with_config<R: tokio::io::AsyncRead + Unpin>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+	async_io::read_request(reader, config).await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn parse_head_from_async_reader<R: tokio::io::AsyncRead + Unpin>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+	async_io::read_head(reader, config).await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn read_body_from_async_reader<R: tokio::io::AsyncRead + Unpin>(&mut self, reader: &mut R) -> Result<(), ParseError> {
+	async_io::read_body(reader, self).await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn write_to_async<W: tokio::io::AsyncWrite + Unpin>(&self, writer: &mut W) -> std::io::Result<usize> {
+	async_io::write_request(self, writer).await
+    }
+
+    pub fn builder() -> RequestBuilder {
+	RequestBuilder::new()
+    }
+
+    pub fn parser() -> RequestParser {
+	RequestParser::new()
+    }
+
+    pub fn warnings(&self) -> &Vec<ParseWarning> {
+	&self.warnings
+    }
+
+    pub fn parse_from_str(&mut self, request: &str) -> Result<(), ParseError> {
+	match self.parse_fast(request, false) {
+	    Some(result) => result?,
+	    None => self.parse_request(request, false)?,
+	}
+	if self.config.mode() == ParseMode::Strict {
+	    self.check_host()?;
+	}
+	Ok(())
+    }
+
+    // The body is chunk-encoded, followed by the trailers, when chunked is
+    // the final transfer coding.
+    pub fn build(&self) -> String {
+	format!("{}{}", self.build_head(), String::from_utf8_lossy(&self.encoded_body()))
+    }
+
+    // Like `build`, but keeps a binary body intact.
+    pub fn to_bytes(&self) -> Vec<u8> {
+	let mut bytes: Vec<u8> = self.build_head().into_bytes();
+	bytes.extend_from_slice(&self.encoded_body());
+	bytes
+    }
+
+    // Writes the request line, headers and body without building the whole
+    // request in memory first. Returns the number of bytes written.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+	let head: String = self.build_head();
+	let body: Cow<'_, [u8]> = self.encoded_body();
+	writer.write_all(head.as_bytes())?;
+	writer.write_all(&body)?;
+	Ok(head.len() + body.len())
+    }
+
+    // Like `to_bytes`, but sends the body with chunked transfer coding in
+    // chunks of at most `chunk_size` bytes, followed by the trailers. Any
+    // Content-Length is dropped and the trailer names are announced in a
+    // `Trailer` header.
+    pub fn to_chunked_bytes(&self, chunk_size: usize) -> Vec<u8> {
+	let mut headers: HeaderMap = self.headers.clone();
+	headers.remove("content-length");
+	if !headers::is_chunked(&self.headers) {
+	    let codings: String = match headers.get_joined("transfer-encoding") {
+		Some(codings) => format!("{}, chunked", codings),
+		None => "chunked".to_string(),
+	    };
+	    headers.insert("Transfer-Encoding", &codings);
+	}
+	if !self.trailers.is_empty() {
+	    let mut names: Vec<&str> = Vec::new();
+	    for trailer in &self.trailers {
+		if !names.iter().any(|name| name.eq_ignore_ascii_case(trailer.name())) {
+		    names.push(trailer.name());
+		}
+	    }
+	    headers.insert("Trailer", &names.join(", "));
+	}
+
+	let mut head: String = format!("{} {} {}\r\n", self.method, self.target(), self.version);
+	headers::write_header_lines(&mut head, &headers, None);
+	head.push_str("\r\n");
+	let mut bytes: Vec<u8> = head.into_bytes();
+	bytes.extend_from_slice(&chunked::encode(&self.body, chunk_size, &self.trailers));
+	bytes
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_chunked_to<W: Write>(&self, writer: &mut W, chunk_size: usize) -> io::Result<usize> {
+	let bytes: Vec<u8> = self.to_chunked_bytes(chunk_size);
+	writer.write_all(&bytes)?;
+	Ok(bytes.len())
+    }
+
+    fn build_head(&self) -> String {
+	let mut head: String = format!("{} {} {}\r\n", self.method, self.target(), self.version);
+	headers::write_header_lines(&mut head, &self.headers, Some(self.body.len()).filter(|_| self.auto_content_length));
+	head.push_str("\r\n");
+	head
+    }
+
+    fn encoded_body(&self) -> Cow<'_, [u8]> {
+	if headers::is_chunked(&self.headers) {
+	    Cow::Owned(chunked::encode(&self.body, self.body.len(), &self.trailers))
+	} else {
+	    Cow::Borrowed(&self.body)
+	}
+    }
+
+    // The request target as sent: origin-form built from `path` and `query`,
+    // keeping the scheme and authority of an absolute-form `full_path`.
+    // Authority- and asterisk-form targets are sent unchanged.
+    fn target(&self) -> String {
+	let mut target: String = String::new();
+	if !self.full_path.is_empty() && !self.full_path.starts_with('/') {
+	    if let Ok(uri) = Uri::parse(&self.full_path) {
+		match (uri.scheme(), uri.authority()) {
+		    (Some(scheme), Some(authority)) => target = format!("{}://{}", scheme, authority),
+		    _ => return uri.to_string(),
+		}
+	    }
+	}
+	target.push_str(&if self.percent_encoding { url::encode_path(&self.path) } else { self.path.clone() });
+	for (i, query) in self.query.iter().enumerate() {
+	    let separator: &str = if i == 0 && !target.contains("?") { "?" } else { "&" };
+	    if self.percent_encoding {
+		target.push_str(&format!("{}{}={}", separator, url_encode(query.name()), url_encode(query.value())));
+	    } else {
+		target.push_str(&format!("{}{}={}", separator, query.name(), query.value()));
+	    }
+	}
+	target
+    }
+
+    fn parse_request(&mut self, request: &str, lenient: bool) -> Result<(), ParseError> {
+	if request.is_empty() {
+	    self.recover(ParseError::InvalidRequestLine(String::new()), lenient)?;
+	}
+	let strict: bool = self.config.mode() == ParseMode::Strict;
+	// Each header is held back until the next one starts, so continuation
+	// lines can still be folded into it.
+	let mut pending: Option<Cow<'_, str>> = None;
+	let mut count: usize = 0;
+	let mut lines: scan::Lines<'_> = scan::lines(request, strict);
+	for (i, line) in lines.by_ref().enumerate() {
+	    if i == 0 {
+		if strict && line.contains('\n') {
+		    self.recover(ParseError::InvalidRequestLine(line.to_string()), lenient)?;
+		    continue;
+		}
+		self.config.check_uri(line.split(' ').nth(1).unwrap_or(""))?;
+		if let Err(err) = self.parse_method_line(line, lenient) {
+		    self.recover(err, lenient)?;
+		}
+		continue;
+	    } else if line.is_empty() {
+		// The head ends at the first empty line, whatever the method.
+		break;
+	    }
+	    // Obsolete line folding continues the previous field value. Strict
+	    // parsing rejects it, as does the borrowed parser.
+	    if headers::is_obs_fold(line) {
+		if strict {
+		    self.recover(ParseError::ObsoleteLineFolding(line.to_string()), lenient)?;
+		    continue;
+		}
+		if let Some(previous) = pending.as_mut() {
+		    headers::unfold(previous, line);
+		    continue;
+		}
+	    }
+	    if let Some(previous) = pending.replace(Cow::Borrowed(line)) {
+		self.parse_pending_header(count, &previous, strict, lenient)?;
+		count += 1;
+	    }
+	}
+	if let Some(previous) = pending {
+	    self.parse_pending_header(count, &previous, strict, lenient)?;
+	}
+	if let Err(err) = self.frame_body(lines.rest().as_bytes(), lenient) {
+	    self.recover(err, lenient)?;
+	}
+	Ok(())
+    }
+
+    // Takes the body from the bytes after the head: chunked transfer coding
+    // is decoded, Content-Length caps it, and otherwise it runs to the end.
+    // Input that ends with the head, as from the readers, leaves it empty.
+    // An invalid Content-Length, or a body shorter than it declares, fails
+    // the parse as `RequestParser` does; lenient parsing records a warning
+    // and keeps the bytes that are there.
+    fn frame_body(&mut self, rest: &[u8], lenient: bool) -> Result<(), ParseError> {
+	if rest.is_empty() {
+	    self.body = Vec::new();
+	    return Ok(());
+	}
+	if headers::is_chunked(&self.headers) {
+	    (self.body, self.trailers) = chunked::decode(rest, &self.config)?;
+	    return Ok(());
+	}
+	let declared: Option<u64> = match self.content_length() {
+	    _ if self.headers.contains("transfer-encoding") => None,
+	    Ok(length) => length,
+	    Err(err) => {
+		self.recover(err, lenient)?;
+		None
+	    }
+	};
+	let length: usize = match declared.map(|length| usize::try_from(length).unwrap_or(usize::MAX)) {
+	    Some(length) if length > rest.len() => {
+		self.config.check_body_size(length)?;
+		self.recover(ParseError::IncompleteBody(format!("{} of {} bytes", rest.len(), length)), lenient)?;
+		rest.len()
+	    }
+	    Some(length) => length,
+	    None => rest.len(),
+	};
+	self.config.check_body_size(length)?;
+	self.body = rest[..length].to_vec();
+	Ok(())
+    }
+
+    fn parse_pending_header(&mut self, count: usize, line: &str, strict: bool, lenient: bool) -> Result<(), ParseError> {
+	self.config.check_header_line(count, line)?;
+	if let Err(err) = self.parse_header_line(line, strict) {
+	    self.recover(err, lenient)?;
+	}
+	Ok(())
+    }
+
+    // An HTTP/1.1 request needs exactly one Host header (RFC 9112 section
+    // 3.2), and it must be a valid authority unless it is empty.
+    fn check_host(&self) -> Result<(), ParseError> {
+	for warning in &self.warnings {
+	    if let ParseError::DuplicateHeader(name) = warning.error() {
+		if name.eq_ignore_ascii_case("host") {
+		    return Err(ParseError::DuplicateHeader(name.clone()));
+		}
+	    }
+	}
+	match self.headers.get("host") {
+	    Some(header) if !header.value().trim().is_empty() && uri::split_host(header.value().trim()).is_none() => {
+		Err(ParseError::InvalidHost(header.value().clone()))
+	    }
+	    None if self.version == Version::Http11 => Err(ParseError::MissingHost),
+	    _ => Ok(()),
+	}
+    }
+
+    // In lenient mode a recoverable error is downgraded to a warning and
+    // parsing carries on; otherwise it aborts the parse.
+    fn recover(&mut self, err: ParseError, lenient: bool) -> Result<(), ParseError> {
+	if !lenient {
+	    return Err(err);
+	}
+	self.warnings.push(ParseWarning::from(err));
+	Ok(())
+    }
+
+    // Handles the common bodyless GET/HEAD case without going through the
+    // line-by-line parser. Returns None without touching `self` whenever the
+    // input needs the generic path, or when `self` already holds headers, a
+    // body or warnings that the generic path would add to.
+    fn parse_fast(&mut self, request: &str, lenient: bool) -> Option<Result<(), ParseError>> {
+	if !self.headers.is_empty() || !self.body.is_empty() || !self.warnings.is_empty() || request.len() > FAST_PATH_MAX_LENGTH || !request.ends_with("\r\n\r\n") {
+	    return None;
+	}
+	let head: &str = &request[..request.len() - 4];
+	let (request_line, header_block) = match scan::find_crlf(head.as_bytes()) {
+	    Some(end) => (&head[..end], &head[end + 2..]),
+	    None => (head, ""),
+	};
+
+	let mut parts = request_line.split(' ');
+	let method: Method = match parts.next() {
+	    Some("GET") => Method::GET,
+	    Some("HEAD") => Method::HEAD,
+	    _ => return None,
+	};
+	let (full_path, version) = match (parts.next(), parts.next().map(str::parse::<Version>), parts.next()) {
+	    (Some(full_path), Some(Ok(version)), None) => (full_path, version),
+	    _ => return None,
+	};
+	if let Err(err) = self.config.check_uri(full_path) {
+	    return Some(Err(err));
+	}
+
+	let mut headers: HeaderMap = HeaderMap::new();
+	let mut warnings: Vec<ParseWarning> = Vec::new();
+	let mut anomalies: Vec<ValidationError> = Vec::new();
+	if !header_block.is_empty() {
+	    for (count, line) in scan::lines(header_block, true).enumerate() {
+		if let Err(err) = self.config.check_header_line(count, line) {
+		    return Some(Err(err));
+		}
+		let (name, value) = headers::split_header_line(line, true)?;
+		if !headers::append_field(&mut headers, name, value) {
+		    anomalies.extend(validate::dropped_duplicate(&headers, name, value));
+		    warnings.push(ParseWarning::from(ParseError::DuplicateHeader(name.to_string())));
+		}
+	    }
+	}
+
+	self.method = method;
+	self.full_path = full_path.to_string();
+	self.version = version;
+	self.headers = headers;
+	self.warnings = warnings;
+	self.anomalies = anomalies;
+	self.body = Vec::new();
+	Some(self.parse_target(full_path, lenient))
+    }
+
+    fn parse_method_line(&mut self, line: &str, lenient: bool) -> Result<(), ParseError> {
+	let mut parts = line.split(' ');
+	let (method, full_path, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+	    (Some(method), Some(full_path), Some(version), None) => (method, full_path, version),
+	    _ => return Err(ParseError::InvalidRequestLine(line.to_string())),
+	};
+	self.method = method.parse()?;
+	self.version = version.parse()?;
+	self.full_path = full_path.to_string();
+	self.parse_target(full_path, lenient)
+    }
+
+    fn parse_target(&mut self, target: &str, lenient: bool) -> Result<(), ParseError> {
+	let uri: Uri = Uri::parse(target)?;
+	let path: &str = uri.path();
+	let query_string: Option<&str> = uri.query();
+	self.path = if self.percent_encoding { url_decode(path) } else { path.to_string() };
+	if let Some(query_string) = query_string {
+	    for query in query_string.split('&') {
+		let (name, value) = match query.split_once('=') {
+		    Some((name, value)) if !value.contains('=') => (name, value),
+		    _ => {
+			self.recover(ParseError::MalformedQuery(query.to_string()), lenient)?;
+			continue;
+		    }
+		};
+		let query: Query = if self.percent_encoding {
+		    Query::new(url::decode_query(name), url::decode_query(value))
+		} else {
+		    Query::new(name.to_string(), value.to_string())
+		};
+		self.query.push(query);
+	    }
+	}
+	Ok(())
+    }
+
+    fn parse_header_line(&mut self, line: &str, strict: bool) -> Result<(), ParseError> {
+	let (name, value) = match headers::split_header_line(line, strict) {
+	    Some((name, value)) => (name, value),
+	    None => return Err(ParseError::MalformedHeader(line.to_string())),
+	};
+
+	if !strict && line.split_once(':').is_some_and(|(raw_name, _)| raw_name.len() != name.len()) {
+	    self.anomalies.push(ValidationError::WhitespaceBeforeColon(name.to_string()));
+	}
+	if !headers::append_field(&mut self.headers, name, value) {
+	    self.anomalies.extend(validate::dropped_duplicate(&self.headers, name, value));
+	    self.warnings.push(ParseWarning::from(ParseError::DuplicateHeader(name.to_string())));
+	}
+	Ok(())
+    }
+}
+
+// Parses many independent requests, e.g. entries from an access-log or
+// capture corpus. Bodies are kept byte for byte, as `parse_from_bytes` does.
+// With the `rayon` feature the inputs are parsed in parallel; results are
+// always returned in input order.
+pub fn parse_batch(inputs: &[&[u8]]) -> Vec<Result<Request, ParseError>> {
+    #[cfg(feature = "rayon")]
+    {
+	use rayon::prelude::*;
+	inputs.par_iter().map(|input| Request::parse_from_bytes(input)).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+	inputs.iter().map(|input| Request::parse_from_bytes(input)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test_parse_batch {
+    use super::{parse_batch, Method, ParseError};
+
+    #[test]
+    fn test_parse_batch() {
+	let inputs: Vec<&[u8]> = vec![
+	    b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+	    b"BR@W /pot HTTP/1.1\r\n\r\n",
+	    b"POST /form HTTP/1.1\r\nHost: localhost\r\n\r\nbody",
+	    b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\n\xff\x00\xfe\x80",
+	];
+	let results = parse_batch(&inputs);
+	assert_eq!(results.len(), 4);
+	assert_eq!(*results[0].as_ref().unwrap().method(), Method::GET);
+	assert_eq!(results[1].as_ref().err(), Some(&ParseError::UnsupportedMethod("BR@W".to_string())));
+	assert_eq!(results[2].as_ref().unwrap().body(), "body");
+	assert_eq!(results[3].as_ref().unwrap().body_bytes(), b"\xff\x00\xfe\x80");
+    }
+}
+
+#[cfg(test)]
+mod test_header {
+    use super::Header;
+
+    #[test]
+    fn test_new() {
+	let header: Header = Header::new("name".to_string(), "value".to_string());
+	assert_eq!(header.name(), "name");
+	assert_eq!(header.value(), "value");
+    }
+}
+
+#[cfg(test)]
+mod test_query {
+    use super::Query;
+    
+    #[test]
+    fn test_new() {
+	let query: Query = Query::new("name".to_string(), "value".to_string());
+	assert_eq!(query.name(), "name");
+	assert_eq!(query.value(), "value");
+    }
+}
+
+#[cfg(test)]
+mod test_method {
+    use super::{Method, ParseError, Request};
+	
+	#[test]
+    fn test_display() {
+	assert_eq!(format!("{}", Method::GET), "GET");
+	assert_eq!(format!("{}", Method::POST), "POST");
+	assert_eq!(format!("{}", Method::PUT), "PUT");
+	assert_eq!(format!("{}", Method::DELETE), "DELETE");
+	assert_eq!(format!("{}", Method::HEAD), "HEAD");
+	assert_eq!(format!("{}", Method::OPTIONS), "OPTIONS");
+	assert_eq!(format!("{}", Method::CONNECT), "CONNECT");
+	assert_eq!(format!("{}", Method::TRACE), "TRACE");
+	assert_eq!(format!("{}", Method::PATCH), "PATCH");
+    }
+
+    #[test]
+    fn test_from_str() {
+	assert_eq!("PATCH".parse::<Method>(), Ok(Method::PATCH));
+	assert_eq!("PROPFIND".parse::<Method>(), Ok(Method::Extension("PROPFIND".to_string())));
+	assert_eq!("get".parse::<Method>(), Ok(Method::Extension("get".to_string())));
+	assert_eq!("GE T".parse::<Method>(), Err(ParseError::UnsupportedMethod("GE T".to_string())));
+	assert_eq!("".parse::<Method>(), Err(ParseError::UnsupportedMethod(String::new())));
+    }
+
+    #[test]
+    fn test_extension() {
+	assert_eq!(Method::Extension("GET".to_string()), Method::GET);
+	assert_ne!(Method::Extension("MKCOL".to_string()), Method::Extension("mkcol".to_string()));
+	assert_eq!(format!("{}", Method::Extension("MKCOL".to_string())), "MKCOL");
+	let request: Request = Request::parse("PROPFIND /files HTTP/1.1\r\nDepth: 1\r\n\r\n").unwrap();
+	assert_eq!(*request.method(), Method::Extension("PROPFIND".to_string()));
+	assert_eq!(request.build(), "PROPFIND /files HTTP/1.1\r\nDepth: 1\r\n\r\n");
+    }
+}
+
+#[cfg(test)]
+mod test_request {
+    use super::{Authorization, CacheControl, Encoding, ETag, ETagMatch, Forwarded, HttpDate, Request, RequestRef, Method, ParseError, ParseMode, ParseWarning, ParserConfig, Query, QueryError, Range, TrustPolicy, Uri};
+    
+    #[test]
+    fn test_new() {
+	let request: Request = Request::new();
+	assert_eq!(request.headers().len(), 0);
+	assert_eq!(request.query().len(), 0);
+	assert_eq!(request.body(), "");
+	assert_eq!(*request.method(), Method::GET);
+	assert_eq!(request.path(), "");
+    }
+
+    #[test]
+    fn test_parse_from_str() {
+	let mut request: Request = Request::new();
+	request.parse_from_str("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	assert_eq!(request.headers().len(), 1);
+	assert_eq!(request.headers[0].name, "Host");
+	assert_eq!(request.headers[0].value, "localhost");
+	assert_eq!(request.query().len(), 0);
+	assert_eq!(request.body(), "");
+	assert_eq!(*request.method(), Method::GET);
+	assert_eq!(request.path(), "/");
+    }
+
+    #[test]
+    fn test_parse_from_str_with_query() {
+	let mut request: Request = Request::new();
+	request.parse_from_str("GET /?name=value&test=test2 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	assert_eq!(request.headers().len(), 1);
+	assert_eq!(request.headers[0].name, "Host");
+	assert_eq!(request.headers[0].value, "localhost");
+	assert_eq!(request.query().len(), 2);
+	assert_eq!(request.query[0].name, "name");
+	assert_eq!(request.query[0].value, "value");
+	assert_eq!(request.query[1].name, "test");
+	assert_eq!(request.query[1].value, "test2");
+	assert_eq!(request.body(), "");
+	assert_eq!(*request.method(), Method::GET);
+	assert_eq!(request.path(), "/");
+    }
+
+    #[test]
+    fn test_parse_from_str_with_query_and_body() {
+	let mut request: Request = Request::new();
+	request.parse_from_str("POST /?name=value HTTP/1.1\r\nHost: localhost\r\nContent-Type: plain\r\n\r\nbody").unwrap();
+	assert_eq!(request.headers().len(), 2);
+	assert_eq!(request.headers[0].name, "Host");
+	assert_eq!(request.headers[0].value, "localhost");
+	assert_eq!(request.headers[1].name, "Content-Type");
 	assert_eq!(request.headers[1].value, "plain");
 	assert_eq!(request.query().len(), 1);
 	assert_eq!(request.query[0].name, "name");
@@ -503,6 +1520,541 @@ mod test_request {
 	assert_eq!(request.path(), "/");
     }
 
+    #[test]
+    fn test_parse_from_str_fast_path_matches_generic() {
+	let input: &str = "HEAD /index.html?lang=en HTTP/1.1\r\nHost: localhost\r\nAccept: */*\r\nhost: other\r\n\r\n";
+	let mut fast: Request = Request::new();
+	assert_eq!(fast.parse_fast(input, false), Some(Ok(())));
+	let mut generic: Request = Request::new();
+	generic.parse_request(input, false).unwrap();
+	assert_eq!(fast.build(), generic.build());
+	assert_eq!(fast.full_path(), generic.full_path());
+	assert_eq!(fast.headers().len(), 2);
+	assert_eq!(*fast.method(), Method::HEAD);
+	assert_eq!(fast.find_query("lang").unwrap().value(), "en");
+    }
+
+    #[test]
+    fn test_parse_fast_declines_other_requests() {
+	let mut request: Request = Request::new();
+	assert_eq!(request.parse_fast("POST / HTTP/1.1\r\nHost: localhost\r\n\r\n", false), None);
+	assert_eq!(request.parse_fast("GET / HTTP/1.1\r\nHost: localhost\r\n\r\nbody", false), None);
+	assert_eq!(request.parse_fast("GET / HTTP/1.1\nHost: localhost\n\n", false), None);
+	assert_eq!(request.parse_fast("GET / HTTP/1.1\r\nbroken header\r\n\r\n", false), None);
+	assert_eq!(request.path(), "");
+    }
+
+    #[test]
+    fn test_parse() {
+	let request: Request = Request::parse("DELETE /items/1 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	assert_eq!(*request.method(), Method::DELETE);
+	assert_eq!(request.path(), "/items/1");
+	assert_eq!(Request::parse("").err(), Some(ParseError::InvalidRequestLine(String::new())));
+	assert_eq!(Request::parse("GET /\r\n\r\n").err(), Some(ParseError::InvalidRequestLine("GET /".to_string())));
+	assert_eq!(Request::parse("BR@W / HTTP/1.1\r\n\r\n").err(), Some(ParseError::UnsupportedMethod("BR@W".to_string())));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+	assert_eq!(Request::parse("GET / HTTP/1.1\r\nHost localhost\r\n\r\n").err(), Some(ParseError::MalformedHeader("Host localhost".to_string())));
+	assert_eq!(Request::parse("GET / HTTP/1.1\r\nX-Odd a\r\n\r\n").err(), Some(ParseError::MalformedHeader("X-Odd a".to_string())));
+	assert_eq!(Request::parse("GET / HTTP/1.1\r\nX-Odd: a: b\r\n\r\n").unwrap().find_header("x-odd").unwrap().value(), "a: b");
+	assert_eq!(Request::parse("GET /?a=1&b HTTP/1.1\r\n\r\n").err(), Some(ParseError::MalformedQuery("b".to_string())));
+	assert_eq!(Request::parse("POST /?a HTTP/1.1\r\n\r\nbody").err(), Some(ParseError::MalformedQuery("a".to_string())));
+	assert_eq!(Request::parse("GET / HTTP/11\r\n\r\n").err(), Some(ParseError::UnsupportedVersion("HTTP/11".to_string())));
+	assert_eq!(Request::parse("POST / HTTP/1.2\r\n\r\nbody").err(), Some(ParseError::UnsupportedVersion("HTTP/1.2".to_string())));
+
+	let mut request: Request = Request::new();
+	assert!(request.parse_from_str("GET / HTTP/1.1\r\nHost: localhost\r\nbroken\r\n\r\n").is_err());
+	assert_eq!(request.headers().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_with_config() {
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_uri_length(8);
+	config.set_max_headers(2);
+	config.set_max_header_line_length(16);
+	config.set_max_body_size(4);
+	let limit = |name: &str| Some(ParseError::LimitExceeded(name.to_string()));
+
+	assert!(Request::parse_with_config("GET /short HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n", &config).is_ok());
+	assert_eq!(Request::parse_with_config("GET /too/long HTTP/1.1\r\n\r\n", &config).err(), limit("max_uri_length"));
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\nA: 1\r\nA: 2\r\nA: 3\r\n\r\n", &config).err(), limit("max_headers"));
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\nX-Long: 0123456789\r\n\r\n", &config).err(), limit("max_header_line_length"));
+	assert_eq!(Request::parse_with_config("POST /too/long HTTP/1.1\r\n\r\n", &config).err(), limit("max_uri_length"));
+	assert_eq!(Request::parse_with_config("POST / HTTP/1.1\r\n\r\nhello", &config).err(), limit("max_body_size"));
+
+	let mut request: Request = Request::new();
+	request.set_config(config);
+	assert_eq!(request.parse_from_str("POST / HTTP/1.1\r\nA: 1\r\nA: 2\r\nA: 3\r\n\r\n").err(), limit("max_headers"));
+    }
+
+    #[test]
+    fn test_query_multi_values() {
+	let mut request: Request = Request::parse("GET /?tag=a&x=1&tag=b&ids[]=1&ids%5B%5D=2 HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.query_all("tag"), vec!["a", "b"]);
+	assert_eq!(request.query_all("ids"), vec!["1", "2"]);
+	assert_eq!(request.query_all("ids[]"), vec!["1", "2"]);
+	assert_eq!(request.find_query("tag").unwrap().value(), "a");
+	assert_eq!(request.build(), "GET /?tag=a&x=1&tag=b&ids%5B%5D=1&ids%5B%5D=2 HTTP/1.1\r\n\r\n");
+
+	request.add_query("tag", "c");
+	assert_eq!(request.query_all("tag"), vec!["a", "b", "c"]);
+	request.set_query("tag", "z");
+	assert_eq!(request.query_all("tag"), vec!["z"]);
+	assert_eq!(request.query()[0].name(), "tag");
+	assert!(request.query_all("missing").is_empty());
+    }
+
+    #[test]
+    fn test_authorization() {
+	let mut request: Request = Request::new();
+	assert_eq!(request.authorization(), Ok(None));
+	request.set_basic_auth("Aladdin", "open sesame");
+	assert_eq!(request.find_header("authorization").unwrap().value(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+	assert_eq!(request.authorization(), Ok(Some(Authorization::basic("Aladdin", "open sesame"))));
+	request.set_bearer_auth("token");
+	assert_eq!(request.headers().len(), 1);
+	assert_eq!(request.authorization(), Ok(Some(Authorization::Bearer("token".to_string()))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() {
+	use crate::BodyError;
+
+	#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+	struct Item {
+	    id: u32,
+	    name: String,
+	}
+
+	let mut request: Request = Request::new();
+	request.set_path("/items");
+	request.set_json(&Item { id: 1, name: "pen".to_string() }).unwrap();
+	assert_eq!(request.build(), "GET /items HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 21\r\n\r\n{\"id\":1,\"name\":\"pen\"}");
+	assert_eq!(request.json::<Item>(), Ok(Item { id: 1, name: "pen".to_string() }));
+
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nContent-Type: application/vnd.api+json\r\n\r\n{\"id\":2}").unwrap();
+	assert!(matches!(request.json::<Item>(), Err(BodyError::Json(_))));
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n{}").unwrap();
+	assert_eq!(request.json::<Item>(), Err(BodyError::UnexpectedContentType("text/plain".to_string())));
+	let request: Request = Request::parse("POST / HTTP/1.1\r\n\r\n{}").unwrap();
+	assert_eq!(request.json::<Item>(), Err(BodyError::MissingContentType));
+    }
+
+    #[test]
+    fn test_decoded_body() {
+	let mut request: Request = Request::parse("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+	assert_eq!(request.decoded_body().unwrap().as_ref(), b"hello");
+	request.set_header("Content-Encoding", "zstd");
+	assert_eq!(request.decoded_body(), Err(ParseError::UnsupportedEncoding("zstd".to_string())));
+	request.set_body_compressed(b"plain", Encoding::Identity);
+	assert!(request.find_header("Content-Encoding").is_none());
+	assert_eq!(request.decoded_body().unwrap().as_ref(), b"plain");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_body() {
+	let mut request: Request = Request::new();
+	request.set_path("/upload");
+	request.set_body_compressed(b"hello hello hello", Encoding::Gzip);
+	assert_eq!(request.find_header("Content-Encoding").unwrap().value(), "gzip");
+	let parsed: Request = Request::parse_from_reader(&mut request.to_bytes().as_slice()).unwrap();
+	assert_eq!(parsed.decoded_body().unwrap().as_ref(), b"hello hello hello");
+
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_body_size(8);
+	let mut parsed: Request = parsed.clone();
+	parsed.set_config(config);
+	assert_eq!(parsed.decoded_body(), Err(ParseError::LimitExceeded("max_body_size".to_string())));
+    }
+
+    #[test]
+    fn test_forwarded() {
+	let mut request: Request = Request::parse("GET / HTTP/1.1\r\nHost: app.internal\r\nForwarded: for=\"[2001:db8::1]:4711\";proto=https;host=example.com\r\nX-Forwarded-For: 10.9.9.9\r\n\r\n").unwrap();
+	assert_eq!(request.client_ip(&TrustPolicy::Hops(1)), Some("2001:db8::1".parse().unwrap()));
+	assert_eq!(request.original_scheme(&TrustPolicy::Hops(1)).as_deref(), Some("https"));
+	assert_eq!(request.original_host(&TrustPolicy::Hops(1)).as_deref(), Some("example.com"));
+	assert_eq!(request.client_ip(&TrustPolicy::Never), None);
+
+	let mut hop: Forwarded = Forwarded::new();
+	hop.set_for_ip("192.0.2.7".parse().unwrap());
+	hop.set_proto("http");
+	request.append_forwarded(&hop);
+	assert_eq!(request.forwarded().unwrap().len(), 2);
+	assert_eq!(request.client_ip(&TrustPolicy::Hops(1)), Some("192.0.2.7".parse().unwrap()));
+	assert_eq!(request.find_header("forwarded").unwrap().value(), "for=\"[2001:db8::1]:4711\";proto=https;host=example.com, for=192.0.2.7;proto=http");
+
+	request.remove_header("Forwarded");
+	request.append_x_forwarded("192.0.2.8".parse().unwrap(), "https", "example.org");
+	assert_eq!(request.find_header("x-forwarded-for").unwrap().value(), "10.9.9.9, 192.0.2.8");
+	assert_eq!(request.client_ip(&TrustPolicy::Hops(1)), Some("192.0.2.8".parse().unwrap()));
+	assert_eq!(request.original_host(&TrustPolicy::Hops(1)).as_deref(), Some("example.org"));
+	assert_eq!(request.original_scheme(&TrustPolicy::Hops(2)), None);
+
+	request.append_via("proxy-a");
+	request.set_version(super::Version::Http10);
+	request.append_via("proxy-b");
+	assert_eq!(request.via(), vec!["1.1 proxy-a".to_string(), "1.0 proxy-b".to_string()]);
+
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nForwarded: for\r\n\r\n").unwrap();
+	assert_eq!(request.forwarded(), Err(ParseError::InvalidForwarded("for".to_string())));
+	assert_eq!(request.client_ip(&TrustPolicy::All), None);
+    }
+
+    #[test]
+    fn test_keep_alive() {
+	assert!(Request::parse("GET / HTTP/1.1\r\n\r\n").unwrap().keep_alive());
+	assert!(!Request::parse("GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap().keep_alive());
+	assert!(!Request::parse("GET / HTTP/1.0\r\n\r\n").unwrap().keep_alive());
+
+	let request: Request = Request::parse("GET / HTTP/1.0\r\nConnection: Keep-Alive, Upgrade\r\nKeep-Alive: timeout=5\r\n\r\n").unwrap();
+	assert!(request.keep_alive());
+	assert_eq!(request.connection_options(), vec!["keep-alive", "upgrade"]);
+	assert_eq!(request.keep_alive_header().unwrap().timeout(), Some(5));
+
+	let mut request: Request = request.clone();
+	request.set_keep_alive(false);
+	assert!(!request.keep_alive());
+	assert_eq!(request.find_header("Connection").unwrap().value(), "Upgrade, close");
+	assert!(request.keep_alive_header().is_none());
+    }
+
+    #[test]
+    fn test_date() {
+	let mut request: Request = Request::new();
+	assert_eq!(request.date(), Ok(None));
+	request.set_date(HttpDate::from_unix_seconds(784111777));
+	assert_eq!(request.find_header("Date").unwrap().value(), "Sun, 06 Nov 1994 08:49:37 GMT");
+	#[cfg(feature = "std")]
+	{
+	    request.set_date_now();
+	    assert!(request.date().unwrap().unwrap() > HttpDate::from_unix_seconds(784111777));
+	}
+	request.set_header("Date", "yesterday");
+	assert_eq!(request.date(), Err(ParseError::InvalidDate("yesterday".to_string())));
+    }
+
+    #[test]
+    fn test_conditional_headers() {
+	let request: Request = Request::parse("GET /doc HTTP/1.1\r\nCache-Control: no-cache, max-age=0\r\nIf-None-Match: \"v1\", W/\"v2\"\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n").unwrap();
+	let cache_control: CacheControl = request.cache_control().unwrap().unwrap();
+	assert!(cache_control.no_cache());
+	assert_eq!(cache_control.max_age(), Some(0));
+	assert_eq!(request.if_none_match(), Ok(Some(ETagMatch::Tags(vec![ETag::strong("v1"), ETag::weak("v2")]))));
+	assert_eq!(request.if_modified_since(), Ok(Some(HttpDate::from_unix_seconds(784111777))));
+	assert_eq!(request.if_match(), Ok(None));
+	let mut appended: Request = request.clone();
+	appended.append_header("If-None-Match", "\"v3\"");
+	assert_eq!(appended.if_none_match(), Ok(Some(ETagMatch::Tags(vec![ETag::strong("v1"), ETag::weak("v2"), ETag::strong("v3")]))));
+
+	assert!(request.is_not_modified(Some(&ETag::strong("v2")), None));
+	assert!(!request.is_not_modified(Some(&ETag::strong("v3")), Some(HttpDate::from_unix_seconds(0))));
+	assert!(!request.is_precondition_failed(Some(&ETag::strong("v3")), None));
+
+	let request: Request = Request::parse("GET /doc HTTP/1.1\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n").unwrap();
+	assert!(request.is_not_modified(None, Some(HttpDate::from_unix_seconds(784111777))));
+	assert!(!request.is_not_modified(None, Some(HttpDate::from_unix_seconds(784111778))));
+	assert!(!request.is_not_modified(None, None));
+
+	let request: Request = Request::parse("PUT /doc HTTP/1.1\r\nIf-Match: \"v1\"\r\n\r\n").unwrap();
+	assert!(!request.is_precondition_failed(Some(&ETag::strong("v1")), None));
+	assert!(request.is_precondition_failed(Some(&ETag::weak("v1")), None));
+	assert!(request.is_precondition_failed(None, None));
+	let request: Request = Request::parse("PUT /doc HTTP/1.1\r\nIf-None-Match: *\r\n\r\n").unwrap();
+	assert!(request.is_precondition_failed(Some(&ETag::strong("v1")), None));
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nIf-Modified-Since: Sunday, 06-Nov-94 08:49:37 GMT\r\n\r\n").unwrap();
+	assert_eq!(request.if_modified_since(), Ok(Some(HttpDate::from_unix_seconds(784111777))));
+	assert_eq!(Request::parse("GET / HTTP/1.1\r\nIf-Match: v1\r\n\r\n").unwrap().if_match(), Err(ParseError::InvalidETag("v1".to_string())));
+    }
+
+    #[test]
+    fn test_range() {
+	let request: Request = Request::parse("GET /video HTTP/1.1\r\nRange: bytes=0-99, -50\r\n\r\n").unwrap();
+	let range: Range = request.range().unwrap().unwrap();
+	assert_eq!(range.spans(1000), Some(vec![0..100, 950..1000]));
+	assert_eq!(Request::parse("GET / HTTP/1.1\r\n\r\n").unwrap().range(), Ok(None));
+	assert_eq!(Request::parse("GET / HTTP/1.1\r\nRange: lines=1-2\r\n\r\n").unwrap().range(), Err(ParseError::InvalidRange("lines=1-2".to_string())));
+    }
+
+    #[test]
+    fn test_target_forms() {
+	let request: Request = Request::parse("GET http://example.com:8080/a%20b?x=1#frag HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.path(), "/a b");
+	assert_eq!(request.find_query("x").unwrap().value(), "1");
+	let uri: Uri = request.uri().unwrap();
+	assert_eq!(uri.host(), Some("example.com"));
+	assert_eq!(uri.port(), Some(8080));
+	assert_eq!(request.build(), "GET http://example.com:8080/a%20b?x=1 HTTP/1.1\r\n\r\n");
+
+	let request: Request = Request::parse("CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n").unwrap();
+	assert!(request.uri().unwrap().is_authority_form());
+	assert_eq!(request.build(), "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n");
+
+	let request: Request = Request::parse("OPTIONS * HTTP/1.1\r\n\r\n").unwrap();
+	assert!(request.uri().unwrap().is_asterisk());
+	assert_eq!(request.build(), "OPTIONS * HTTP/1.1\r\n\r\n");
+
+	assert_eq!(Request::parse("GET example.com HTTP/1.1\r\n\r\n").err(), Some(ParseError::InvalidUri("example.com".to_string())));
+    }
+
+    #[test]
+    fn test_parse_modes() {
+	let sloppy: &str = "POST /submit HTTP/1.1\nHost : localhost\nX-Folded: a,\n  b\nContent-Length:4\n\nbody";
+	let request: Request = Request::parse(sloppy).unwrap();
+	assert_eq!(request.find_header("host").unwrap().value(), "localhost");
+	assert_eq!(request.find_header("x-folded").unwrap().value(), "a, b");
+	assert_eq!(request.content_length(), Ok(Some(4)));
+	assert_eq!(request.body(), "body");
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\nX-Note: one\r\n \t two\r\n\tthree\r\n\r\n").unwrap();
+	assert_eq!(request.find_header("x-note").unwrap().value(), "one two three");
+
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_mode(ParseMode::Strict);
+	assert_eq!(Request::parse_with_config(sloppy, &config).err(), Some(ParseError::InvalidRequestLine(sloppy.to_string())));
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\nHost : localhost\r\n\r\n", &config).err(), Some(ParseError::MalformedHeader("Host : localhost".to_string())));
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\nX-Folded: a,\r\n  b\r\n\r\n", &config).err(), Some(ParseError::ObsoleteLineFolding("  b".to_string())));
+	let request: Request = Request::parse_with_config("POST / HTTP/1.1\r\nHost:localhost\r\n\r\nline\nline", &config).unwrap();
+	assert_eq!(request.find_header("host").unwrap().value(), "localhost");
+	assert_eq!(request.body(), "line\nline");
+    }
+
+    #[test]
+    fn test_duplicate_headers() {
+	let raw: &str = "GET / HTTP/1.1\r\nHost: a\r\nAccept: text/html\r\nCookie: a=1\r\nX-Tag: 1\r\naccept: */*\r\nCookie: b=2\r\nX-Tag: 2\r\nContent-Type: text/plain\r\nContent-Type: text/html\r\n\r\n";
+	let mut request: Request = Request::new();
+	request.parse_from_str(&raw.replace("\r\n", "\n")).unwrap();
+	for request in [Request::parse(raw).unwrap(), request, RequestRef::parse(raw).unwrap().to_request()] {
+	    assert_eq!(request.find_header("accept").unwrap().value(), "text/html, */*");
+	    assert_eq!(request.find_header("cookie").unwrap().value(), "a=1; b=2");
+	    assert_eq!(request.find_headers("x-tag").len(), 2);
+	    assert_eq!(request.find_header("content-type").unwrap().value(), "text/plain");
+	    assert_eq!(request.headers().len(), 6);
+	    assert_eq!(request.warnings(), &vec![ParseWarning::from(ParseError::DuplicateHeader("Content-Type".to_string()))]);
+	}
+    }
+
+    #[test]
+    fn test_host() {
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n").unwrap();
+	assert_eq!(request.host(), Ok(Some("[::1]".to_string())));
+	assert_eq!(request.port(), Ok(Some(8080)));
+	let request: Request = Request::parse("GET http://example.com/a HTTP/1.1\r\nHost: other.example:81\r\n\r\n").unwrap();
+	assert_eq!(request.host(), Ok(Some("example.com".to_string())));
+	assert_eq!(request.port(), Ok(None));
+	let request: Request = Request::parse("CONNECT example.com:443 HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.host(), Ok(Some("example.com".to_string())));
+	assert_eq!(request.port(), Ok(Some(443)));
+	let request: Request = Request::parse("GET / HTTP/1.0\r\n\r\n").unwrap();
+	assert_eq!(request.host(), Ok(None));
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: bad host\r\n\r\n").unwrap();
+	assert_eq!(request.host(), Err(ParseError::InvalidHost("bad host".to_string())));
+
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_mode(ParseMode::Strict);
+	assert!(Request::parse_with_config("GET / HTTP/1.1\r\nHost: localhost:8080\r\n\r\n", &config).is_ok());
+	assert!(Request::parse_with_config("GET / HTTP/1.0\r\n\r\n", &config).is_ok());
+	assert!(Request::parse_with_config("GET http://example.com/ HTTP/1.1\r\nHost:\r\n\r\n", &config).is_ok());
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\n\r\n", &config).err(), Some(ParseError::MissingHost));
+	assert_eq!(Request::parse_with_config("POST / HTTP/1.1\r\nContent-Length: 0\r\n\r\n", &config).err(), Some(ParseError::MissingHost));
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\nHost: a\r\nhost: b\r\n\r\n", &config).err(), Some(ParseError::DuplicateHeader("host".to_string())));
+	assert_eq!(Request::parse_with_config("GET / HTTP/1.1\r\nHost: [::1\r\n\r\n", &config).err(), Some(ParseError::InvalidHost("[::1".to_string())));
+    }
+
+    #[test]
+    fn test_normalized_path() {
+	let request: Request = Request::parse("GET /static//css/../js/./app.js HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	assert_eq!(request.normalized_path(), "/static/js/app.js");
+	let request: Request = Request::parse("GET /a/%2e%2e/%2E%2E/%2e%2e/etc/passwd HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	assert_eq!(request.normalized_path(), "/etc/passwd");
+	let request: Request = Request::parse("GET /a/%252e%252e/b HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	assert_eq!(request.normalized_path(), "/a/%2e%2e/b");
+
+	let mut raw: Request = Request::new();
+	raw.set_percent_encoding(false);
+	raw.parse_from_str("GET /docs/..%2F..%2Fsecret HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	assert_eq!(raw.normalized_path(), "/secret");
+
+	#[cfg(feature = "std")]
+	{
+	    use std::path::{Path, PathBuf};
+
+	    let root: &Path = Path::new("/srv/www");
+	    let request: Request = Request::parse("GET /../../etc/passwd HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	    assert_eq!(request.resolve_under(root), Some(PathBuf::from("/srv/www/etc/passwd")));
+	    let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	    assert_eq!(request.resolve_under(root), Some(PathBuf::from("/srv/www")));
+	    for target in ["/..%5C..%5Cwindows", "/a%00.txt"] {
+		let request: Request = Request::parse(&format!("GET {} HTTP/1.1\r\nHost: a\r\n\r\n", target)).unwrap();
+		assert_eq!(request.resolve_under(root), None, "{}", target);
+	    }
+	}
+    }
+
+    #[test]
+    fn test_parse_from_bytes() {
+	let request: Request = Request::parse_from_bytes(b"POST /upload HTTP/1.1\r\nHost: localhost\r\nX-Name: caf\xe9\r\nX-Utf8: caf\xc3\xa9\r\n\r\n\x00\xff\r\n\r\nend").unwrap();
+	assert_eq!(request.find_header("x-name").unwrap().value(), "caf\u{e9}");
+	assert_eq!(request.find_header("x-utf8").unwrap().value(), "caf\u{e9}");
+	assert_eq!(request.body_bytes(), b"\x00\xff\r\n\r\nend");
+	let request: Request = Request::parse_from_bytes(b"GET /a HTTP/1.1\nHost: localhost\n\nbody").unwrap();
+	assert_eq!(request.path(), "/a");
+	assert_eq!(request.body(), "body");
+
+	assert_eq!(Request::parse_from_bytes(b"G\xc9T / HTTP/1.1\r\n\r\n").err(), Some(ParseError::UnsupportedMethod("G\u{c9}T".to_string())));
+	assert_eq!(Request::parse_from_bytes(b"GET / HTTP/1.1\r\nX\xffName: v\r\n\r\n").err(), Some(ParseError::MalformedHeader("X\u{ff}Name: v".to_string())));
+	assert_eq!(Request::parse_from_bytes(b"GET / HTTP/1.1\r\nX-Bell: a\x07b\r\n\r\n").err(), Some(ParseError::MalformedHeader("X-Bell: a\x07b".to_string())));
+	for input in [&b""[..], b"\r\n\r\n", b"\xff\xfe\xfd", b"GET", b"GET / HTTP/1.1\r\n:\r\n\r\n"] {
+	    assert!(Request::parse_from_bytes(input).is_err());
+	}
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+	let request: Request = Request::parse_lenient("POST /?a=1&broken HTTP/1.1\r\nHost: localhost\r\nhost: other\r\nno-colon\r\nX Odd: b\r\n\r\nbody");
+	assert_eq!(request.headers().len(), 1);
+	assert_eq!(request.query().len(), 1);
+	assert_eq!(request.body(), "body");
+	assert_eq!(request.warnings(), &vec![
+	    ParseWarning::from(ParseError::MalformedQuery("broken".to_string())),
+	    ParseWarning::from(ParseError::DuplicateHeader("host".to_string())),
+	    ParseWarning::from(ParseError::MalformedHeader("no-colon".to_string())),
+	    ParseWarning::from(ParseError::MalformedHeader("X Odd: b".to_string())),
+	]);
+
+	let request: Request = Request::parse_lenient("BR@W /pot HTTP/1.1\r\nHost: localhost\r\n\r\n");
+	assert_eq!(request.headers().len(), 1);
+	assert_eq!(request.warnings(), &vec![ParseWarning::from(ParseError::UnsupportedMethod("BR@W".to_string()))]);
+    }
+
+    #[test]
+    fn test_body_bytes() {
+	let mut request: Request = Request::new();
+	request.set_body_bytes(&[0x1f, 0x8b, 0xff, b'a']);
+	assert_eq!(request.body_bytes(), &[0x1f, 0x8b, 0xff, b'a']);
+	assert_eq!(request.body(), "\u{1f}\u{fffd}\u{fffd}a");
+	request.set_body("text");
+	assert_eq!(request.body_bytes(), b"text");
+    }
+
+    #[test]
+    fn test_body_framing() {
+	let request: Request = Request::parse("PATCH / HTTP/1.1\r\nContent-Length: 3\r\n\r\nabc").unwrap();
+	assert_eq!(request.body(), "abc");
+	let request: Request = Request::parse("DELETE / HTTP/1.1\r\n\r\nX-Not-A-Header: 1").unwrap();
+	assert!(request.find_header("x-not-a-header").is_none());
+	assert_eq!(request.body(), "X-Not-A-Header: 1");
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nhiGET / HTTP/1.1").unwrap();
+	assert_eq!(request.body(), "hi");
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\na\r\n\r\nb\nc\r\n").unwrap();
+	assert_eq!(request.body(), "a\r\n\r\nb\nc\r\n");
+	let request: Request = Request::parse_lenient("POST / HTTP/1.1\n\na\r\n\r\n");
+	assert_eq!(request.body(), "a\r\n\r\n");
+
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\nExpires: never\r\n\r\n").unwrap();
+	assert_eq!(request.body(), "hi");
+	assert_eq!(request.trailers().get("expires").unwrap().value(), "never");
+	assert_eq!(Request::parse("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhi").err(), Some(ParseError::InvalidChunk("unexpected end of body".to_string())));
+	let request: Request = Request::parse_from_bytes(b"PUT / HTTP/1.1\r\nContent-Length: 2\r\n\r\n\xff\x00\x01").unwrap();
+	assert_eq!(request.body_bytes(), b"\xff\x00");
+
+	// Framing the streaming parser rejects is rejected here too.
+	let invalid: &str = "POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\nbody";
+	assert_eq!(Request::parse(invalid).err(), Some(ParseError::InvalidContentLength("ten".to_string())));
+	assert_eq!(Request::parse_from_bytes(invalid.as_bytes()).err(), Some(ParseError::InvalidContentLength("ten".to_string())));
+	let request: Request = Request::parse_lenient(invalid);
+	assert_eq!(request.body(), "body");
+	assert_eq!(request.warnings(), &vec![ParseWarning::from(ParseError::InvalidContentLength("ten".to_string()))]);
+
+	let short: &str = "POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort";
+	assert_eq!(Request::parse(short).err(), Some(ParseError::IncompleteBody("5 of 10 bytes".to_string())));
+	assert_eq!(Request::parse_from_bytes(short.as_bytes()).err(), Some(ParseError::IncompleteBody("5 of 10 bytes".to_string())));
+	assert_eq!(Request::parse_lenient(short).body(), "short");
+    }
+
+    #[test]
+    fn test_percent_encoding() {
+	let request: Request = Request::parse("GET /my%20files/?name=hello%20world&q=a%26b&s=x+y HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.path(), "/my files/");
+	assert_eq!(request.find_query("name").unwrap().value(), "hello world");
+	assert_eq!(request.find_query("q").unwrap().value(), "a&b");
+	assert_eq!(request.find_query("s").unwrap().value(), "x y");
+	assert_eq!(request.full_path(), "/my%20files/?name=hello%20world&q=a%26b&s=x+y");
+	assert_eq!(request.build(), "GET /my%20files/?name=hello%20world&q=a%26b&s=x%20y HTTP/1.1\r\n\r\n");
+	assert_eq!(Request::parse(&request.build()).unwrap().find_query("q").unwrap().value(), "a&b");
+
+	let mut raw: Request = Request::new();
+	raw.set_percent_encoding(false);
+	raw.parse_from_str("GET /my%20files/?q=a%26b HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(raw.path(), "/my%20files/");
+	assert_eq!(raw.find_query("q").unwrap().value(), "a%26b");
+	assert_eq!(raw.build(), "GET /my%20files/?q=a%26b HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    fn test_form() {
+	let request: Request = Request::parse("POST /login HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\n\r\nuser=j+doe&pass=a%26b%3D&remember&&").unwrap();
+	let form: Vec<Query> = request.form();
+	assert_eq!(form.len(), 3);
+	assert_eq!((form[0].name().as_str(), form[0].value().as_str()), ("user", "j doe"));
+	assert_eq!((form[1].name().as_str(), form[1].value().as_str()), ("pass", "a&b="));
+	assert_eq!((form[2].name().as_str(), form[2].value().as_str()), ("remember", ""));
+
+	let mut request: Request = Request::new();
+	request.set_method(Method::POST);
+	request.set_form(&[("user", "j doe"), ("note", "1+1=2")]);
+	assert_eq!(request.body(), "user=j+doe&note=1%2B1%3D2");
+	assert!(request.content_type().unwrap().unwrap().is_form());
+	assert_eq!(request.find_header("content-length").unwrap().value(), "25");
+	assert_eq!(request.form()[1].value(), "1+1=2");
+    }
+
+    #[test]
+    fn test_multi_value_headers() {
+	let mut request: Request = Request::new();
+	request.append_header("Accept", "text/html");
+	request.append_header("accept", "application/json");
+	request.set_header("Host", "localhost");
+	assert_eq!(request.find_headers("ACCEPT").len(), 2);
+	assert_eq!(request.find_header("accept").unwrap().value(), "text/html");
+	assert_eq!(request.build(), "GET  HTTP/1.1\r\nAccept: text/html\r\naccept: application/json\r\nHost: localhost\r\n\r\n");
+	assert_eq!(request.remove_header("Accept").len(), 2);
+	assert_eq!(request.headers().len(), 1);
+    }
+
+    #[test]
+    fn test_query_as() {
+	let request: Request = Request::parse("GET /?page=3&limit=ten HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.query_as::<u32>("page"), Ok(Some(3)));
+	assert_eq!(request.query_as::<u32>("offset"), Ok(None));
+	assert_eq!(request.query_as::<u32>("limit"), Err(QueryError::InvalidValue("limit".to_string(), "ten".to_string())));
+    }
+
+    #[test]
+    fn test_collection_helpers() {
+	let mut request: Request = Request::parse("GET /?a=1&b=2&a=3 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	assert_eq!(&request["HOST"], "localhost");
+	request.extend_headers([("Accept", "*/*"), ("X-Id", "7")]);
+	request.headers_mut().insert("x-id", "8");
+	assert_eq!(&request["x-id"], "8");
+	assert_eq!(request.headers().len(), 3);
+	assert_eq!(request.remove_query("a").len(), 2);
+	assert_eq!(request.build(), "GET /?b=2 HTTP/1.1\r\nHost: localhost\r\nAccept: */*\r\nX-Id: 8\r\n\r\n");
+    }
+
+    #[test]
+    fn test_cookies() {
+	let mut request: Request = Request::parse("GET / HTTP/1.1\r\nCookie: theme=dark; session=abc; broken\r\n\r\n").unwrap();
+	assert_eq!(request.cookies().len(), 2);
+	assert_eq!(request.find_cookie("session").unwrap().value(), "abc");
+	request.set_cookie("theme", "light");
+	request.set_cookie("lang", "en");
+	assert_eq!(request.find_header("cookie").unwrap().value(), "theme=light; session=abc; lang=en");
+    }
+
     #[test]
     fn test_build() {
 	let mut request: Request = Request::new();
@@ -515,6 +2067,38 @@ mod test_request {
 	request.add_query("name", "value2");
 	request.set_query("name2", "value");
 	request.set_body("body");
-	assert_eq!(request.build(), "POST /?name=value2&name2=value HTTP/1.1\r\nHost: localhost2\r\nContent-Type: plain\r\n\r\nbody");
+	assert_eq!(request.build(), "POST /?name=value&name=value2&name2=value HTTP/1.1\r\nHost: localhost2\r\nContent-Type: plain\r\nContent-Length: 4\r\n\r\nbody");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_to() {
+	let mut request: Request = Request::new();
+	request.set_method(Method::PUT);
+	request.set_path("/image");
+	request.set_body_bytes(&[0x89, b'P', 0xff]);
+	let mut written: Vec<u8> = Vec::new();
+	assert_eq!(request.write_to(&mut written).unwrap(), 45);
+	assert_eq!(written, b"PUT /image HTTP/1.1\r\nContent-Length: 3\r\n\r\n\x89P\xff");
+	assert_eq!(request.to_bytes(), written);
+    }
+
+    #[test]
+    fn test_auto_content_length() {
+	let mut request: Request = Request::parse("POST / HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 2\r\n\r\nhi").unwrap();
+	request.set_body("body");
+	assert_eq!(request.content_length(), Ok(Some(2)));
+	assert_eq!(request.build(), "POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody");
+
+	request.set_header("Transfer-Encoding", "chunked");
+	assert_eq!(request.build(), "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nbody\r\n0\r\n\r\n");
+	request.set_auto_content_length(false);
+	assert_eq!(request.to_bytes(), b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nbody\r\n0\r\n\r\n");
+
+	let mut request: Request = Request::parse("GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n").unwrap();
+	assert_eq!(request.content_length(), Err(ParseError::InvalidContentLength("x".to_string())));
+	assert_eq!(request.build(), "GET / HTTP/1.1\r\nContent-Length: 0\r\n\r\n");
+	request.set_auto_content_length(false);
+	assert_eq!(request.build(), "GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n");
     }
 }
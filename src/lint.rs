@@ -0,0 +1,209 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{Method, Request, Version};
+
+const MAX_FIELD_LENGTH: usize = 8192;
+const MAX_TARGET_LENGTH: usize = 8000;
+const SENSITIVE_TRACE_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    Severity::Warning => write!(f, "warning"),
+	    Severity::Error => write!(f, "error"),
+	}
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: &'static str,
+    message: String,
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+impl Diagnostic {
+    fn new(severity: Severity, code: &'static str, message: String) -> Diagnostic {
+	Diagnostic {
+	    severity,
+	    code,
+	    message,
+	}
+    }
+
+    pub fn severity(&self) -> Severity {
+	self.severity
+    }
+
+    pub fn code(&self) -> &'static str {
+	self.code
+    }
+
+    pub fn message(&self) -> &String {
+	&self.message
+    }
+}
+
+// Checks a request against RFC 9112 / RFC 9110 and reports every problem
+// found. An empty result means the message is clean.
+//
+// Rule codes:
+//   L001 missing Host header          L006 oversized field or target
+//   L002 invalid header name          L007 content on a method that forbids it
+//   L003 invalid header value         L008 sensitive header on TRACE
+//   L004 conflicting framing headers  L009 invalid request target
+//   L005 invalid Content-Length       L010 version without HTTP/1.x syntax
+pub fn lint(request: &Request) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    if matches!(request.version, Version::Http2 | Version::Http3) {
+	diagnostics.push(Diagnostic::new(Severity::Error, "L010", format!("{} cannot be sent as an HTTP/1.x message", request.version)));
+    }
+
+    let host_count: usize = request.headers.iter().filter(|header| header.name().eq_ignore_ascii_case("host")).count();
+    if host_count == 0 && request.version == Version::Http11 {
+	diagnostics.push(Diagnostic::new(Severity::Error, "L001", "HTTP/1.1 request has no Host header".to_string()));
+    }
+
+    lint_target(request, &mut diagnostics);
+
+    for header in &request.headers {
+	if header.name().is_empty() || !header.name().bytes().all(is_token_char) {
+	    diagnostics.push(Diagnostic::new(Severity::Error, "L002", format!("Header name `{}` contains characters outside the token set", header.name())));
+	}
+	if header.value().bytes().any(|byte| byte != b'\t' && (byte < 0x20 || byte == 0x7F)) {
+	    diagnostics.push(Diagnostic::new(Severity::Error, "L003", format!("Header `{}` has control characters in its value", header.name())));
+	}
+	if header.name().len() + header.value().len() + 2 > MAX_FIELD_LENGTH {
+	    diagnostics.push(Diagnostic::new(Severity::Warning, "L006", format!("Header `{}` is longer than {} bytes", header.name(), MAX_FIELD_LENGTH)));
+	}
+	if request.method == Method::TRACE && SENSITIVE_TRACE_HEADERS.contains(&header.name().to_lowercase().as_str()) {
+	    diagnostics.push(Diagnostic::new(Severity::Warning, "L008", format!("TRACE request carries sensitive header `{}`", header.name())));
+	}
+    }
+
+    lint_framing(request, &mut diagnostics);
+
+    diagnostics
+}
+
+fn lint_target(request: &Request, diagnostics: &mut Vec<Diagnostic>) {
+    let target: &str = if request.full_path.is_empty() { &request.path } else { &request.full_path };
+    let valid: bool = match request.method {
+	Method::CONNECT => !target.is_empty() && !target.starts_with('/'),
+	Method::OPTIONS => target == "*" || target.starts_with('/'),
+	_ => target.starts_with('/') || target.starts_with("http://") || target.starts_with("https://"),
+    };
+    if !valid {
+	diagnostics.push(Diagnostic::new(Severity::Error, "L009", format!("Invalid request target `{}` for {}", target, request.method)));
+    }
+    if target.len() > MAX_TARGET_LENGTH {
+	diagnostics.push(Diagnostic::new(Severity::Warning, "L006", format!("Request target is longer than {} bytes", MAX_TARGET_LENGTH)));
+    }
+}
+
+fn lint_framing(request: &Request, diagnostics: &mut Vec<Diagnostic>) {
+    let content_lengths: Vec<&str> = request.headers.iter()
+	.filter(|header| header.name().eq_ignore_ascii_case("content-length"))
+	.map(|header| header.value().as_str())
+	.collect();
+    let has_transfer_encoding: bool = request.headers.iter().any(|header| header.name().eq_ignore_ascii_case("transfer-encoding"));
+
+    if has_transfer_encoding && !content_lengths.is_empty() {
+	diagnostics.push(Diagnostic::new(Severity::Error, "L004", "Both Content-Length and Transfer-Encoding are present".to_string()));
+    }
+    for value in &content_lengths {
+	if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+	    diagnostics.push(Diagnostic::new(Severity::Error, "L005", format!("Content-Length `{}` is not a non-negative integer", value)));
+	} else if !has_transfer_encoding && value.parse::<usize>().ok() != Some(request.body.len()) {
+	    diagnostics.push(Diagnostic::new(Severity::Error, "L005", format!("Content-Length `{}` does not match the body length {}", value, request.body.len())));
+	}
+    }
+
+    if !request.body.is_empty() {
+	match request.method {
+	    Method::TRACE => {
+		diagnostics.push(Diagnostic::new(Severity::Error, "L007", "TRACE requests must not carry content".to_string()));
+	    }
+	    Method::GET | Method::HEAD | Method::DELETE | Method::CONNECT => {
+		diagnostics.push(Diagnostic::new(Severity::Warning, "L007", format!("{} request content has no defined semantics", request.method)));
+	    }
+	    _ => {}
+	}
+	if content_lengths.is_empty() && !has_transfer_encoding {
+	    diagnostics.push(Diagnostic::new(Severity::Error, "L004", "Request has a body but no Content-Length or Transfer-Encoding".to_string()));
+	}
+    }
+}
+
+pub(crate) fn is_token_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+#[cfg(test)]
+mod test_lint {
+    use super::{lint, Diagnostic, Severity};
+    use crate::{Method, Request, Version};
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+	diagnostics.iter().map(|diagnostic| diagnostic.code()).collect()
+    }
+
+    #[test]
+    fn test_clean_request() {
+	let mut request: Request = Request::new();
+	request.parse_from_str("POST /submit HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbody").unwrap();
+	assert_eq!(lint(&request), vec![]);
+    }
+
+    #[test]
+    fn test_missing_host_and_bad_tokens() {
+	let mut request: Request = Request::new();
+	request.set_path("/");
+	request.add_header("Bad Name", "value");
+	request.add_header("X-Value", "a\u{0}b");
+	let diagnostics: Vec<Diagnostic> = lint(&request);
+	assert_eq!(codes(&diagnostics), vec!["L001", "L002", "L003"]);
+	assert!(diagnostics.iter().all(|diagnostic| diagnostic.severity() == Severity::Error));
+    }
+
+    #[test]
+    fn test_framing() {
+	let mut request: Request = Request::new();
+	request.set_method(Method::POST);
+	request.set_path("/");
+	request.add_header("Host", "localhost");
+	request.add_header("Content-Length", "10");
+	request.add_header("Transfer-Encoding", "chunked");
+	assert_eq!(codes(&lint(&request)), vec!["L004"]);
+
+	let mut request: Request = Request::new();
+	request.set_method(Method::TRACE);
+	request.set_path("/");
+	request.add_header("Host", "localhost");
+	request.add_header("Cookie", "a=b");
+	request.add_header("Content-Length", "x");
+	request.set_body("body");
+	assert_eq!(codes(&lint(&request)), vec!["L008", "L005", "L007"]);
+    }
+
+    #[test]
+    fn test_target_and_version() {
+	let mut request: Request = Request::new();
+	request.set_method(Method::CONNECT);
+	request.set_path("/");
+	request.set_version(Version::Http2);
+	assert_eq!(codes(&lint(&request)), vec!["L010", "L009"]);
+	assert_eq!(format!("{}", lint(&request)[0]), "error[L010]: HTTP/2 cannot be sent as an HTTP/1.x message");
+    }
+}
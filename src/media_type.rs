@@ -0,0 +1,199 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{lint, ParseError};
+
+// A parsed `Content-Type` value such as `text/html; charset=utf-8`. Type,
+// subtype and parameter names are case-insensitive and stored lowercased;
+// parameter values keep their case, with quoting removed.
+#[derive(PartialEq, Clone, Debug)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    parameters: Vec<(String, String)>,
+}
+impl MediaType {
+    pub fn new(type_: &str, subtype: &str) -> MediaType {
+	MediaType {
+	    type_: type_.to_ascii_lowercase(),
+	    subtype: subtype.to_ascii_lowercase(),
+	    parameters: Vec::new(),
+	}
+    }
+
+    pub fn parse(value: &str) -> Result<MediaType, ParseError> {
+	let invalid = || ParseError::InvalidMediaType(value.to_string());
+	let (essence, mut rest) = match value.find(';') {
+	    Some(semicolon) => (&value[..semicolon], &value[semicolon + 1..]),
+	    None => (value, ""),
+	};
+	let (type_, subtype) = essence.trim().split_once('/').ok_or_else(invalid)?;
+	if !is_token(type_) || !is_token(subtype) {
+	    return Err(invalid());
+	}
+	let mut media_type: MediaType = MediaType::new(type_, subtype);
+
+	while !rest.trim().is_empty() {
+	    let (name, after_name) = rest.split_once('=').ok_or_else(invalid)?;
+	    let name: &str = name.trim();
+	    if !is_token(name) {
+		return Err(invalid());
+	    }
+	    let after_name: &str = after_name.trim_start();
+	    let (parameter_value, remaining) = if let Some(quoted) = after_name.strip_prefix('"') {
+		let (unquoted, length) = unquote(quoted).ok_or_else(invalid)?;
+		let remaining: &str = quoted[length..].trim_start();
+		if !remaining.is_empty() && !remaining.starts_with(';') {
+		    return Err(invalid());
+		}
+		(unquoted, remaining.strip_prefix(';').unwrap_or(remaining))
+	    } else {
+		let (token, remaining) = after_name.split_once(';').unwrap_or((after_name, ""));
+		let token: &str = token.trim_end();
+		if !is_token(token) {
+		    return Err(invalid());
+		}
+		(token.to_string(), remaining)
+	    };
+	    media_type.parameters.push((name.to_ascii_lowercase(), parameter_value));
+	    rest = remaining;
+	}
+	Ok(media_type)
+    }
+
+    pub fn type_(&self) -> &str {
+	&self.type_
+    }
+
+    pub fn subtype(&self) -> &str {
+	&self.subtype
+    }
+
+    // `type/subtype` without parameters.
+    pub fn essence(&self) -> String {
+	format!("{}/{}", self.type_, self.subtype)
+    }
+
+    pub fn parameters(&self) -> &Vec<(String, String)> {
+	&self.parameters
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+	self.parameters.iter().find(|(parameter, _)| parameter.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: &str) {
+	let name: String = name.to_ascii_lowercase();
+	match self.parameters.iter_mut().find(|(parameter, _)| *parameter == name) {
+	    Some((_, existing)) => *existing = value.to_string(),
+	    None => self.parameters.push((name, value.to_string())),
+	}
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+	self.parameter("charset")
+    }
+
+    pub fn boundary(&self) -> Option<&str> {
+	self.parameter("boundary")
+    }
+
+    // `application/json` and structured-syntax suffixes like `+json`.
+    pub fn is_json(&self) -> bool {
+	(self.type_ == "application" && self.subtype == "json") || self.subtype.ends_with("+json")
+    }
+
+    pub fn is_form(&self) -> bool {
+	self.type_ == "application" && self.subtype == "x-www-form-urlencoded"
+    }
+
+    pub fn is_multipart(&self) -> bool {
+	self.type_ == "multipart"
+    }
+
+    pub fn is_text(&self) -> bool {
+	self.type_ == "text"
+    }
+}
+impl FromStr for MediaType {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<MediaType, ParseError> {
+	MediaType::parse(value)
+    }
+}
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "{}/{}", self.type_, self.subtype)?;
+	for (name, value) in &self.parameters {
+	    if is_token(value) {
+		write!(f, "; {}={}", name, value)?;
+	    } else {
+		write!(f, "; {}=\"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+	    }
+	}
+	Ok(())
+    }
+}
+
+pub(crate) fn is_token(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(lint::is_token_char)
+}
+
+// Decodes a quoted-string body (after the opening quote). Returns the value
+// and the number of bytes consumed, including the closing quote.
+pub(crate) fn unquote(quoted: &str) -> Option<(String, usize)> {
+    let mut value: String = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+	match c {
+	    '"' => return Some((value, i + 1)),
+	    '\\' => value.push(chars.next()?.1),
+	    _ => value.push(c),
+	}
+    }
+    None
+}
+
+#[cfg(test)]
+mod test_media_type {
+    use super::MediaType;
+    use crate::ParseError;
+
+    #[test]
+    fn test_parse() {
+	let media_type: MediaType = MediaType::parse("Text/HTML; Charset=UTF-8").unwrap();
+	assert_eq!(media_type.type_(), "text");
+	assert_eq!(media_type.subtype(), "html");
+	assert_eq!(media_type.essence(), "text/html");
+	assert_eq!(media_type.charset(), Some("UTF-8"));
+	assert!(media_type.is_text());
+	assert_eq!(media_type, "text/html;charset=UTF-8".parse().unwrap());
+
+	let media_type: MediaType = MediaType::parse("multipart/form-data; boundary=\"a b\\\"c\"; x=1").unwrap();
+	assert!(media_type.is_multipart());
+	assert_eq!(media_type.boundary(), Some("a b\"c"));
+	assert_eq!(media_type.parameter("X"), Some("1"));
+	assert_eq!(media_type.to_string(), "multipart/form-data; boundary=\"a b\\\"c\"; x=1");
+    }
+
+    #[test]
+    fn test_helpers() {
+	assert!(MediaType::parse("application/json").unwrap().is_json());
+	assert!(MediaType::parse("application/problem+json; charset=utf-8").unwrap().is_json());
+	assert!(!MediaType::parse("application/jsonp").unwrap().is_json());
+	assert!(MediaType::parse("application/x-www-form-urlencoded").unwrap().is_form());
+
+	let mut media_type: MediaType = MediaType::new("Text", "Plain");
+	media_type.set_parameter("charset", "utf-8");
+	assert_eq!(media_type.to_string(), "text/plain; charset=utf-8");
+    }
+
+    #[test]
+    fn test_invalid() {
+	for value in ["", "text", "text/", "te xt/html", "text/html; charset", "text/html; charset=\"open", "text/html; a=\"b\" c"] {
+	    assert_eq!(MediaType::parse(value), Err(ParseError::InvalidMediaType(value.to_string())));
+	}
+    }
+}
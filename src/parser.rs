@@ -0,0 +1,239 @@
+use crate::prelude::*;
+use crate::chunked::Decoder;
+use crate::{headers, scan, ParseError, ParserConfig, Request};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ParseStatus {
+    Partial,
+    HeadersComplete,
+    Complete,
+}
+
+// Incremental request parser for data that arrives in chunks, e.g. from
+// successive `TcpStream::read` calls. Bytes are buffered until the head is
+// terminated by an empty line, then the body is collected until
+// Content-Length bytes have arrived or, with chunked transfer coding, until
+// the last chunk and trailers have.
+pub struct RequestParser {
+    buffer: Vec<u8>,
+    scanned: usize,
+    head_length: Option<usize>,
+    // The size of the body as sent, i.e. before chunked decoding.
+    body_length: usize,
+    chunked: Option<Decoder>,
+    request: Option<Request>,
+    status: ParseStatus,
+    config: ParserConfig,
+}
+impl Default for RequestParser {
+    fn default() -> RequestParser {
+	RequestParser::new()
+    }
+}
+impl RequestParser {
+    pub fn new() -> RequestParser {
+	RequestParser::with_config(ParserConfig::new())
+    }
+
+    pub fn with_config(config: ParserConfig) -> RequestParser {
+	RequestParser {
+	    buffer: Vec::new(),
+	    scanned: 0,
+	    head_length: None,
+	    body_length: 0,
+	    chunked: None,
+	    request: None,
+	    status: ParseStatus::Partial,
+	    config,
+	}
+    }
+
+    pub fn status(&self) -> ParseStatus {
+	self.status
+    }
+
+    // The request parsed so far; available once the headers are complete.
+    pub fn request(&self) -> Option<&Request> {
+	self.request.as_ref()
+    }
+
+    // Bytes received after the end of a complete request, i.e. the start of
+    // the next pipelined request.
+    pub fn remainder(&self) -> &[u8] {
+	match (self.status, self.head_length) {
+	    (ParseStatus::Complete, Some(head_length)) => &self.buffer[head_length + self.body_length..],
+	    _ => &[],
+	}
+    }
+
+    pub fn into_request(self) -> Option<Request> {
+	match self.status {
+	    ParseStatus::Complete => self.request,
+	    _ => None,
+	}
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<ParseStatus, ParseError> {
+	if self.status == ParseStatus::Complete {
+	    return Ok(ParseStatus::Complete);
+	}
+	self.buffer.extend_from_slice(bytes);
+
+	if self.head_length.is_none() {
+	    let start: usize = self.scanned.saturating_sub(3);
+	    match scan::find_head_end(&self.buffer[start..]) {
+		Some(position) => self.parse_head(start + position + 4)?,
+		None => {
+		    self.config.check_head_size(self.buffer.len())?;
+		    self.scanned = self.buffer.len();
+		    return Ok(ParseStatus::Partial);
+		}
+	    }
+	}
+
+	let head_length: usize = self.head_length.unwrap_or(0);
+	if let Some(decoder) = self.chunked.as_mut() {
+	    match decoder.decode(&self.buffer[head_length..], &self.config)? {
+		Some(length) => self.body_length = length,
+		None => {
+		    self.status = ParseStatus::HeadersComplete;
+		    return Ok(self.status);
+		}
+	    }
+	} else if self.buffer.len() - head_length < self.body_length {
+	    self.status = ParseStatus::HeadersComplete;
+	    return Ok(self.status);
+	}
+
+	if let Some(request) = self.request.as_mut() {
+	    match self.chunked.take() {
+		Some(decoder) => (request.body, request.trailers) = decoder.into_parts(),
+		None => request.set_body_bytes(&self.buffer[head_length..head_length + self.body_length]),
+	    }
+	}
+	self.status = ParseStatus::Complete;
+	Ok(self.status)
+    }
+
+    fn parse_head(&mut self, head_length: usize) -> Result<(), ParseError> {
+	self.config.check_head_size(head_length)?;
+	let request: Request = Request::parse_with_config(&String::from_utf8_lossy(&self.buffer[..head_length]), &self.config)?;
+	// Transfer-Encoding overrides Content-Length (RFC 9112 section 6.3). A
+	// request whose final coding is not chunked has no length we can find.
+	if let Some(codings) = request.headers.get_joined("transfer-encoding") {
+	    if !headers::is_chunked(&request.headers) {
+		return Err(ParseError::UnsupportedEncoding(codings));
+	    }
+	    self.chunked = Some(Decoder::new());
+	} else if let Some(length) = request.content_length()? {
+	    self.body_length = usize::try_from(length).unwrap_or(usize::MAX);
+	    self.config.check_body_size(self.body_length)?;
+	}
+	self.head_length = Some(head_length);
+	self.request = Some(request);
+	Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_request_parser {
+    use super::{ParseStatus, RequestParser};
+    use crate::{Method, ParseError, ParserConfig, Request};
+
+    #[test]
+    fn test_feed_in_chunks() {
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"POST /upload HT"), Ok(ParseStatus::Partial));
+	assert_eq!(parser.feed(b"TP/1.1\r\nHost: localhost\r\nContent-Length: 14\r"), Ok(ParseStatus::Partial));
+	assert!(parser.request().is_none());
+	assert_eq!(parser.feed(b"\n\r\nhello"), Ok(ParseStatus::HeadersComplete));
+	assert_eq!(*parser.request().unwrap().method(), Method::POST);
+	assert_eq!(parser.feed(b"\r\n\r\nworld"), Ok(ParseStatus::Complete));
+
+	let request: Request = parser.into_request().unwrap();
+	assert_eq!(request.path(), "/upload");
+	assert_eq!(request.body(), "hello\r\n\r\nworld");
+    }
+
+    #[test]
+    fn test_feed_chunked() {
+	let input: &[u8] = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nX-Checksum: abc\r\n\r\nGET /next HTTP/1.1\r\n\r\n";
+	let mut parser = Request::parser();
+	// The last piece completes the message and starts the next one.
+	let (message, rest) = input.split_at(input.len() - 24);
+	for piece in message.chunks(7) {
+	    assert_ne!(parser.feed(piece).unwrap(), ParseStatus::Complete);
+	}
+	assert_eq!(parser.feed(rest), Ok(ParseStatus::Complete));
+	assert_eq!(parser.remainder(), b"GET /next HTTP/1.1\r\n\r\n");
+	let request: Request = parser.into_request().unwrap();
+	assert_eq!(request.body(), "hello, world");
+	assert_eq!(request.trailers().get("x-checksum").unwrap().value(), "abc");
+
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nabc\r\n"), Err(ParseError::InvalidChunk("c".to_string())));
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n"), Err(ParseError::UnsupportedEncoding("gzip".to_string())));
+    }
+
+    #[test]
+    fn test_feed_without_body() {
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"), Ok(ParseStatus::Complete));
+	assert_eq!(parser.status(), ParseStatus::Complete);
+	assert!(parser.remainder().is_empty());
+	assert_eq!(parser.into_request().unwrap().body(), "");
+    }
+
+    #[test]
+    fn test_feed_binary_body() {
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"PUT /image HTTP/1.1\r\nContent-Length: 4\r\n\r\n\x89PN\xff"), Ok(ParseStatus::Complete));
+	assert_eq!(parser.into_request().unwrap().body_bytes(), b"\x89PN\xff");
+    }
+
+    #[test]
+    fn test_feed_pipelined() {
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"POST /a HTTP/1.1\r\nContent-Length: 2\r\n\r\nokGET /b HTTP/1.1\r\n"), Ok(ParseStatus::Complete));
+	assert_eq!(parser.remainder(), b"GET /b HTTP/1.1\r\n");
+	let mut next = Request::parser();
+	assert_eq!(next.feed(parser.remainder()), Ok(ParseStatus::Partial));
+	assert_eq!(parser.into_request().unwrap().body(), "ok");
+	assert_eq!(next.feed(b"\r\n"), Ok(ParseStatus::Complete));
+	assert_eq!(next.into_request().unwrap().path(), "/b");
+    }
+
+    #[test]
+    fn test_feed_expect_continue() {
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"PUT /file HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 4\r\n\r\n"), Ok(ParseStatus::HeadersComplete));
+	assert!(parser.request().unwrap().expects_continue());
+	assert_eq!(parser.feed(b"data"), Ok(ParseStatus::Complete));
+	assert_eq!(parser.into_request().unwrap().body(), "data");
+    }
+
+    #[test]
+    fn test_feed_errors() {
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"BR@W / HTTP/1.1\r\n\r\n"), Err(ParseError::UnsupportedMethod("BR@W".to_string())));
+
+	let mut parser = Request::parser();
+	assert_eq!(parser.feed(b"POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\n"), Err(ParseError::InvalidContentLength("ten".to_string())));
+	assert!(parser.into_request().is_none());
+    }
+
+    #[test]
+    fn test_feed_limits() {
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_head_size(32);
+	let mut parser: RequestParser = RequestParser::with_config(config);
+	assert_eq!(parser.feed(b"GET / HTTP/1.1\r\n"), Ok(ParseStatus::Partial));
+	assert_eq!(parser.feed(b"X-Padding: aaaaaaaaaaaaaaaaaaaa"), Err(ParseError::LimitExceeded("max_head_size".to_string())));
+
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_body_size(10);
+	let mut parser: RequestParser = RequestParser::with_config(config);
+	assert_eq!(parser.feed(b"POST / HTTP/1.1\r\nContent-Length: 11\r\n\r\n"), Err(ParseError::LimitExceeded("max_body_size".to_string())));
+    }
+}
@@ -0,0 +1,5 @@
+// The `alloc` items the crate uses that are otherwise in the std prelude, so
+// modules build the same with and without the `std` feature.
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+pub(crate) use alloc::{format, vec};
@@ -0,0 +1,282 @@
+use core::fmt;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::prelude::*;
+use crate::{ParseError, Request};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LENGTH: usize = 107;
+const V2_SIGNATURE: [u8; 12] = [0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A];
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum ProxyError {
+    Incomplete,
+    InvalidSignature,
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    InvalidAddress,
+    Request(ParseError),
+}
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ProxyError::Incomplete => write!(f, "Incomplete PROXY protocol header"),
+	    ProxyError::InvalidSignature => write!(f, "Missing PROXY protocol signature"),
+	    ProxyError::InvalidHeader => write!(f, "Invalid PROXY protocol header"),
+	    ProxyError::UnsupportedVersion(version) => write!(f, "Unsupported PROXY protocol version: `{}`", version),
+	    ProxyError::InvalidAddress => write!(f, "Invalid address in PROXY protocol header"),
+	    ProxyError::Request(err) => write!(f, "Invalid request after PROXY protocol header: {}", err),
+	}
+    }
+}
+impl core::error::Error for ProxyError {}
+impl From<ParseError> for ProxyError {
+    fn from(err: ParseError) -> ProxyError {
+	ProxyError::Request(err)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ProxyVersion {
+    V1,
+    V2,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct ProxyHeader {
+    version: ProxyVersion,
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+}
+impl ProxyHeader {
+    pub fn new(source: SocketAddr, destination: SocketAddr) -> ProxyHeader {
+	ProxyHeader {
+	    version: ProxyVersion::V1,
+	    source: Some(source),
+	    destination: Some(destination),
+	}
+    }
+
+    // A header that carries no addresses (`PROXY UNKNOWN` / v2 `LOCAL`),
+    // as sent by load balancers for their own health checks.
+    pub fn local() -> ProxyHeader {
+	ProxyHeader {
+	    version: ProxyVersion::V1,
+	    source: None,
+	    destination: None,
+	}
+    }
+
+    pub fn version(&self) -> ProxyVersion {
+	self.version
+    }
+
+    pub fn source(&self) -> Option<&SocketAddr> {
+	self.source.as_ref()
+    }
+
+    pub fn destination(&self) -> Option<&SocketAddr> {
+	self.destination.as_ref()
+    }
+
+    pub fn build_v1(&self) -> String {
+	match (self.source, self.destination) {
+	    (Some(SocketAddr::V4(source)), Some(SocketAddr::V4(destination))) => {
+		format!("PROXY TCP4 {} {} {} {}\r\n", source.ip(), destination.ip(), source.port(), destination.port())
+	    }
+	    (Some(SocketAddr::V6(source)), Some(SocketAddr::V6(destination))) => {
+		format!("PROXY TCP6 {} {} {} {}\r\n", source.ip(), destination.ip(), source.port(), destination.port())
+	    }
+	    _ => "PROXY UNKNOWN\r\n".to_string(),
+	}
+    }
+
+    pub fn build_v2(&self) -> Vec<u8> {
+	let mut bytes: Vec<u8> = V2_SIGNATURE.to_vec();
+	match (self.source, self.destination) {
+	    (Some(SocketAddr::V4(source)), Some(SocketAddr::V4(destination))) => {
+		bytes.extend_from_slice(&[0x21, 0x11, 0x00, 12]);
+		bytes.extend_from_slice(&source.ip().octets());
+		bytes.extend_from_slice(&destination.ip().octets());
+		bytes.extend_from_slice(&source.port().to_be_bytes());
+		bytes.extend_from_slice(&destination.port().to_be_bytes());
+	    }
+	    (Some(SocketAddr::V6(source)), Some(SocketAddr::V6(destination))) => {
+		bytes.extend_from_slice(&[0x21, 0x21, 0x00, 36]);
+		bytes.extend_from_slice(&source.ip().octets());
+		bytes.extend_from_slice(&destination.ip().octets());
+		bytes.extend_from_slice(&source.port().to_be_bytes());
+		bytes.extend_from_slice(&destination.port().to_be_bytes());
+	    }
+	    _ => bytes.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]),
+	}
+	bytes
+    }
+}
+
+// Parses a PROXY protocol preamble (v1 or v2) from the start of a connection
+// and returns it together with the bytes that follow it.
+pub fn parse(input: &[u8]) -> Result<(ProxyHeader, &[u8]), ProxyError> {
+    if input.starts_with(&V2_SIGNATURE) {
+	return parse_v2(input);
+    }
+    if input.starts_with(V1_PREFIX) {
+	return parse_v1(input);
+    }
+    if V2_SIGNATURE.starts_with(input) || V1_PREFIX.starts_with(input) {
+	return Err(ProxyError::Incomplete);
+    }
+    Err(ProxyError::InvalidSignature)
+}
+
+// Strips the PROXY protocol preamble and parses the remaining bytes as an HTTP request.
+pub fn parse_request(input: &[u8]) -> Result<(ProxyHeader, Request), ProxyError> {
+    let (header, rest) = parse(input)?;
+    let request: Request = Request::parse(&String::from_utf8_lossy(rest))?;
+    Ok((header, request))
+}
+
+fn parse_v1(input: &[u8]) -> Result<(ProxyHeader, &[u8]), ProxyError> {
+    let end: usize = match input.windows(2).position(|window| window == b"\r\n") {
+	Some(end) => end,
+	None if input.len() < V1_MAX_LENGTH => return Err(ProxyError::Incomplete),
+	None => return Err(ProxyError::InvalidHeader),
+    };
+    if end + 2 > V1_MAX_LENGTH {
+	return Err(ProxyError::InvalidHeader);
+    }
+    let line: &str = core::str::from_utf8(&input[..end]).map_err(|_| ProxyError::InvalidHeader)?;
+    let rest: &[u8] = &input[end + 2..];
+
+    let parts: Vec<&str> = line.split(' ').collect();
+    if parts.len() >= 2 && parts[1] == "UNKNOWN" {
+	return Ok((ProxyHeader::local(), rest));
+    }
+    if parts.len() != 6 {
+	return Err(ProxyError::InvalidHeader);
+    }
+    let source_ip: IpAddr = parts[2].parse().map_err(|_| ProxyError::InvalidAddress)?;
+    let destination_ip: IpAddr = parts[3].parse().map_err(|_| ProxyError::InvalidAddress)?;
+    match (parts[1], source_ip, destination_ip) {
+	("TCP4", IpAddr::V4(_), IpAddr::V4(_)) | ("TCP6", IpAddr::V6(_), IpAddr::V6(_)) => {}
+	_ => return Err(ProxyError::InvalidAddress),
+    }
+    let source_port: u16 = parts[4].parse().map_err(|_| ProxyError::InvalidAddress)?;
+    let destination_port: u16 = parts[5].parse().map_err(|_| ProxyError::InvalidAddress)?;
+
+    let header: ProxyHeader = ProxyHeader::new(SocketAddr::new(source_ip, source_port), SocketAddr::new(destination_ip, destination_port));
+    Ok((header, rest))
+}
+
+fn parse_v2(input: &[u8]) -> Result<(ProxyHeader, &[u8]), ProxyError> {
+    if input.len() < 16 {
+	return Err(ProxyError::Incomplete);
+    }
+    let version: u8 = input[12] >> 4;
+    if version != 2 {
+	return Err(ProxyError::UnsupportedVersion(version));
+    }
+    let command: u8 = input[12] & 0x0F;
+    let family: u8 = input[13];
+    let length: usize = u16::from_be_bytes([input[14], input[15]]) as usize;
+    if input.len() < 16 + length {
+	return Err(ProxyError::Incomplete);
+    }
+    let addresses: &[u8] = &input[16..16 + length];
+    let rest: &[u8] = &input[16 + length..];
+
+    let mut header: ProxyHeader = ProxyHeader::local();
+    header.version = ProxyVersion::V2;
+    match command {
+	0x0 => return Ok((header, rest)),
+	0x1 => {}
+	_ => return Err(ProxyError::InvalidHeader),
+    }
+
+    match family >> 4 {
+	0x1 => {
+	    if addresses.len() < 12 {
+		return Err(ProxyError::InvalidAddress);
+	    }
+	    let source_ip: Ipv4Addr = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+	    let destination_ip: Ipv4Addr = Ipv4Addr::new(addresses[4], addresses[5], addresses[6], addresses[7]);
+	    header.source = Some(SocketAddr::new(IpAddr::V4(source_ip), u16::from_be_bytes([addresses[8], addresses[9]])));
+	    header.destination = Some(SocketAddr::new(IpAddr::V4(destination_ip), u16::from_be_bytes([addresses[10], addresses[11]])));
+	}
+	0x2 => {
+	    if addresses.len() < 36 {
+		return Err(ProxyError::InvalidAddress);
+	    }
+	    let mut source_octets: [u8; 16] = [0; 16];
+	    let mut destination_octets: [u8; 16] = [0; 16];
+	    source_octets.copy_from_slice(&addresses[0..16]);
+	    destination_octets.copy_from_slice(&addresses[16..32]);
+	    header.source = Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(source_octets)), u16::from_be_bytes([addresses[32], addresses[33]])));
+	    header.destination = Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(destination_octets)), u16::from_be_bytes([addresses[34], addresses[35]])));
+	}
+	// AF_UNSPEC and AF_UNIX carry no address we can expose as a SocketAddr
+	_ => {}
+    }
+    Ok((header, rest))
+}
+
+#[cfg(test)]
+mod test_proxy {
+    use super::{parse, parse_request, ProxyError, ProxyHeader, ProxyVersion};
+    use crate::{Method, ParseError};
+    use std::net::SocketAddr;
+
+    #[test]
+    fn test_parse_v1() {
+	let (header, rest) = parse(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET / HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(header.version(), ProxyVersion::V1);
+	assert_eq!(header.source(), Some(&"192.168.0.1:56324".parse::<SocketAddr>().unwrap()));
+	assert_eq!(header.destination(), Some(&"192.168.0.11:443".parse::<SocketAddr>().unwrap()));
+	assert_eq!(rest, b"GET / HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    fn test_parse_v1_unknown() {
+	let (header, rest) = parse(b"PROXY UNKNOWN\r\n").unwrap();
+	assert_eq!(header.source(), None);
+	assert_eq!(rest, b"");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+	assert_eq!(parse(b"PROXY TCP4 1.2.3.4"), Err(ProxyError::Incomplete));
+	assert_eq!(parse(b"PROXY TCP4 1.2.3.4 ::1 1 2\r\n"), Err(ProxyError::InvalidAddress));
+	assert_eq!(parse(b"GET / HTTP/1.1\r\n\r\n"), Err(ProxyError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_v2_round_trip() {
+	let header: ProxyHeader = ProxyHeader::new("[2001:db8::1]:4000".parse().unwrap(), "[2001:db8::2]:80".parse().unwrap());
+	let mut bytes: Vec<u8> = header.build_v2();
+	bytes.extend_from_slice(b"rest");
+	let (parsed, rest) = parse(&bytes).unwrap();
+	assert_eq!(parsed.version(), ProxyVersion::V2);
+	assert_eq!(parsed.source(), header.source());
+	assert_eq!(parsed.destination(), header.destination());
+	assert_eq!(rest, b"rest");
+    }
+
+    #[test]
+    fn test_build_v1() {
+	let header: ProxyHeader = ProxyHeader::new("10.0.0.1:1234".parse().unwrap(), "10.0.0.2:80".parse().unwrap());
+	assert_eq!(header.build_v1(), "PROXY TCP4 10.0.0.1 10.0.0.2 1234 80\r\n");
+	assert_eq!(ProxyHeader::local().build_v1(), "PROXY UNKNOWN\r\n");
+    }
+
+    #[test]
+    fn test_parse_request() {
+	let (header, request) = parse_request(b"PROXY TCP4 10.0.0.1 10.0.0.2 1234 80\r\nPOST /submit HTTP/1.1\r\nHost: localhost\r\n\r\nbody").unwrap();
+	assert_eq!(header.source().unwrap().port(), 1234);
+	assert_eq!(*request.method(), Method::POST);
+	assert_eq!(request.path(), "/submit");
+	assert_eq!(request.body(), "body");
+
+	let err: Option<ProxyError> = parse_request(b"PROXY UNKNOWN\r\nBR@W / HTTP/1.1\r\n\r\n").err();
+	assert_eq!(err, Some(ProxyError::Request(ParseError::UnsupportedMethod("BR@W".to_string()))));
+    }
+}
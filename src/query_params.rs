@@ -0,0 +1,195 @@
+use core::fmt;
+use core::str::FromStr;
+
+use serde::de::value::{SeqDeserializer, StrDeserializer};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::{Query, QueryError};
+
+impl de::Error for QueryError {
+    fn custom<T: fmt::Display>(message: T) -> QueryError {
+	QueryError::Deserialize(message.to_string())
+    }
+}
+
+// Deserializes the query parameters into `T`, typically a struct. Repeated
+// and array-style (`tag[]`) parameters fill sequence fields; scalar fields
+// take the first value and are parsed from text, and a missing parameter
+// leaves an `Option` field at None.
+pub(crate) fn from_query<T: DeserializeOwned>(query: &[Query]) -> Result<T, QueryError> {
+    let mut params: Vec<(&str, Vec<&str>)> = Vec::new();
+    for query in query {
+	let name: &str = query.name().strip_suffix("[]").unwrap_or(query.name());
+	match params.iter_mut().find(|(param, _)| *param == name) {
+	    Some((_, values)) => values.push(query.value()),
+	    None => params.push((name, vec![query.value().as_str()])),
+	}
+    }
+    T::deserialize(QueryDeserializer { params })
+}
+
+struct QueryDeserializer<'a> {
+    params: Vec<(&'a str, Vec<&'a str>)>,
+}
+impl<'de> Deserializer<'de> for QueryDeserializer<'_> {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+	visitor.visit_map(QueryMap {
+	    params: self.params.into_iter(),
+	    values: None,
+	})
+    }
+
+    forward_to_deserialize_any! {
+	bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+	bytes byte_buf option unit unit_struct newtype_struct seq tuple
+	tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct QueryMap<'a> {
+    params: alloc::vec::IntoIter<(&'a str, Vec<&'a str>)>,
+    values: Option<(&'a str, Vec<&'a str>)>,
+}
+impl<'de> MapAccess<'de> for QueryMap<'_> {
+    type Error = QueryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, QueryError> {
+	match self.params.next() {
+	    Some((name, values)) => {
+		self.values = Some((name, values));
+		let key: StrDeserializer<'_, QueryError> = name.into_deserializer();
+		seed.deserialize(key).map(Some)
+	    }
+	    None => Ok(None),
+	}
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, QueryError> {
+	let (name, values) = self.values.take().ok_or_else(|| QueryError::Deserialize("value requested before key".to_string()))?;
+	seed.deserialize(ValuesDeserializer { name, values })
+    }
+}
+
+// Every value of one parameter.
+struct ValuesDeserializer<'a> {
+    name: &'a str,
+    values: Vec<&'a str>,
+}
+impl ValuesDeserializer<'_> {
+    fn first(&self) -> &str {
+	self.values.first().copied().unwrap_or("")
+    }
+
+    fn parse<T: FromStr>(&self) -> Result<T, QueryError> {
+	self.first().parse().map_err(|_| QueryError::InvalidValue(self.name.to_string(), self.first().to_string()))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+	$(
+	    fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+		visitor.$visit(self.parse()?)
+	    }
+	)*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValuesDeserializer<'_> {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+	visitor.visit_str(self.first())
+    }
+
+    deserialize_parsed! {
+	deserialize_bool => visit_bool,
+	deserialize_i8 => visit_i8,
+	deserialize_i16 => visit_i16,
+	deserialize_i32 => visit_i32,
+	deserialize_i64 => visit_i64,
+	deserialize_u8 => visit_u8,
+	deserialize_u16 => visit_u16,
+	deserialize_u32 => visit_u32,
+	deserialize_u64 => visit_u64,
+	deserialize_f32 => visit_f32,
+	deserialize_f64 => visit_f64,
+	deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+	visitor.visit_some(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+	let name: &str = self.name;
+	let values = self.values.into_iter().map(|value| ValuesDeserializer { name, values: vec![value] });
+	visitor.visit_seq(SeqDeserializer::<_, QueryError>::new(values))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, QueryError> {
+	let variant: StrDeserializer<'_, QueryError> = self.first().into_deserializer();
+	visitor.visit_enum(variant)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, QueryError> {
+	visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+	i128 u128 str string bytes byte_buf unit unit_struct tuple
+	tuple_struct map struct identifier ignored_any
+    }
+}
+impl<'de> IntoDeserializer<'de, QueryError> for ValuesDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+	self
+    }
+}
+
+#[cfg(test)]
+mod test_query_params {
+    use serde::Deserialize;
+
+    use crate::{QueryError, Request};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+	Asc,
+	Desc,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Filter {
+	page: u32,
+	per_page: Option<u8>,
+	search: String,
+	tag: Vec<String>,
+	order: Order,
+	archived: bool,
+    }
+
+    #[test]
+    fn test_query_params() {
+	let request: Request = Request::parse("GET /items?page=2&search=a+b&tag[]=x&tag[]=y&order=desc&archived=false HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.query_params::<Filter>(), Ok(Filter {
+	    page: 2,
+	    per_page: None,
+	    search: "a b".to_string(),
+	    tag: vec!["x".to_string(), "y".to_string()],
+	    order: Order::Desc,
+	    archived: false,
+	}));
+
+	let request: Request = Request::parse("GET /items?page=two&search=&tag=x&order=asc&archived=true HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.query_params::<Filter>(), Err(QueryError::InvalidValue("page".to_string(), "two".to_string())));
+	let request: Request = Request::parse("GET /items?search= HTTP/1.1\r\n\r\n").unwrap();
+	assert_eq!(request.query_params::<Filter>(), Err(QueryError::Deserialize("missing field `page`".to_string())));
+    }
+}
@@ -0,0 +1,137 @@
+use core::fmt;
+use core::ops;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::ParseError;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ByteRange {
+    // `first-last`, both inclusive.
+    FromTo(u64, u64),
+    // `first-`, up to the end.
+    From(u64),
+    // `-length`, the final `length` bytes.
+    Suffix(u64),
+}
+impl ByteRange {
+    // The half-open byte span this range selects in a representation of
+    // `total_length` bytes, or None when it is unsatisfiable.
+    pub fn span(&self, total_length: u64) -> Option<ops::Range<u64>> {
+	match *self {
+	    ByteRange::FromTo(first, last) if first < total_length => Some(first..last.min(total_length - 1) + 1),
+	    ByteRange::From(first) if first < total_length => Some(first..total_length),
+	    ByteRange::Suffix(length) if length > 0 && total_length > 0 => Some(total_length.saturating_sub(length)..total_length),
+	    _ => None,
+	}
+    }
+}
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ByteRange::FromTo(first, last) => write!(f, "{}-{}", first, last),
+	    ByteRange::From(first) => write!(f, "{}-", first),
+	    ByteRange::Suffix(length) => write!(f, "-{}", length),
+	}
+    }
+}
+
+// A `Range: bytes=...` request header. Other range units are rejected.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Range {
+    ranges: Vec<ByteRange>,
+}
+impl Range {
+    pub fn new(ranges: Vec<ByteRange>) -> Range {
+	Range { ranges }
+    }
+
+    pub fn parse(value: &str) -> Result<Range, ParseError> {
+	let invalid = || ParseError::InvalidRange(value.to_string());
+	let (unit, set) = value.trim().split_once('=').ok_or_else(invalid)?;
+	if !unit.trim().eq_ignore_ascii_case("bytes") {
+	    return Err(invalid());
+	}
+	let mut ranges: Vec<ByteRange> = Vec::new();
+	for spec in set.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+	    let (first, last) = spec.split_once('-').ok_or_else(invalid)?;
+	    let range: ByteRange = match (parse_position(first), parse_position(last)) {
+		(Some(first), Some(last)) if first <= last => ByteRange::FromTo(first, last),
+		(Some(first), None) if last.is_empty() => ByteRange::From(first),
+		(None, Some(length)) if first.is_empty() => ByteRange::Suffix(length),
+		_ => return Err(invalid()),
+	    };
+	    ranges.push(range);
+	}
+	if ranges.is_empty() {
+	    return Err(invalid());
+	}
+	Ok(Range { ranges })
+    }
+
+    pub fn ranges(&self) -> &Vec<ByteRange> {
+	&self.ranges
+    }
+
+    // The satisfiable spans, in request order. None means no range can be
+    // served and the response should be 416 Range Not Satisfiable.
+    pub fn spans(&self, total_length: u64) -> Option<Vec<ops::Range<u64>>> {
+	let spans: Vec<ops::Range<u64>> = self.ranges.iter().filter_map(|range| range.span(total_length)).collect();
+	if spans.is_empty() {
+	    None
+	} else {
+	    Some(spans)
+	}
+    }
+
+    // The `Content-Range` value for a span returned by `spans`.
+    pub fn content_range(span: &ops::Range<u64>, total_length: u64) -> String {
+	format!("bytes {}-{}/{}", span.start, span.end - 1, total_length)
+    }
+}
+impl FromStr for Range {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Range, ParseError> {
+	Range::parse(value)
+    }
+}
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	let ranges: Vec<String> = self.ranges.iter().map(ByteRange::to_string).collect();
+	write!(f, "bytes={}", ranges.join(", "))
+    }
+}
+
+fn parse_position(position: &str) -> Option<u64> {
+    if position.is_empty() || !position.bytes().all(|byte| byte.is_ascii_digit()) {
+	return None;
+    }
+    position.parse().ok()
+}
+
+#[cfg(test)]
+mod test_range {
+    use super::{ByteRange, Range};
+    use crate::ParseError;
+
+    #[test]
+    fn test_parse() {
+	let range: Range = Range::parse("bytes=0-499, 500-999,-200, 9500-").unwrap();
+	assert_eq!(range.ranges(), &vec![ByteRange::FromTo(0, 499), ByteRange::FromTo(500, 999), ByteRange::Suffix(200), ByteRange::From(9500)]);
+	assert_eq!(range.to_string(), "bytes=0-499, 500-999, -200, 9500-");
+	for value in ["bytes=", "items=0-1", "bytes=5-1", "bytes=a-b", "bytes=-", "bytes=1-2-3", "0-1"] {
+	    assert_eq!(Range::parse(value), Err(ParseError::InvalidRange(value.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_spans() {
+	let range: Range = Range::parse("bytes=0-499,-200,9500-,20000-30000").unwrap();
+	assert_eq!(range.spans(10000), Some(vec![0..500, 9800..10000, 9500..10000]));
+	assert_eq!(range.spans(100), Some(vec![0..100, 0..100]));
+	assert_eq!(Range::parse("bytes=1000-").unwrap().spans(1000), None);
+	assert_eq!(Range::parse("bytes=-0").unwrap().spans(1000), None);
+	assert_eq!(Range::content_range(&(0..500), 10000), "bytes 0-499/10000");
+    }
+}
@@ -0,0 +1,307 @@
+use std::io::{ErrorKind, Read};
+
+use crate::chunked::{chunk_size, parse_trailer};
+use crate::{headers, HeaderMap, Method, ParseError, ParserConfig, Request, Response, StatusCode};
+
+// Reads a single request. The head and chunk framing are read one byte at a
+// time so nothing past the end of the request is consumed; wrap sockets in a
+// `BufReader` to keep that cheap.
+pub(crate) fn read_request<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+    let mut request: Request = read_head(reader, config)?;
+    read_body(reader, &mut request)?;
+    Ok(request)
+}
+
+// Reads up to the end of the header block and leaves the body unread, e.g.
+// so `100 Continue` can be sent before the client transmits it.
+pub(crate) fn read_head<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<Request, ParseError> {
+    let head: Vec<u8> = read_head_bytes(reader, config)?;
+    let mut request: Request = Request::new();
+    request.set_config(*config);
+    request.parse_from_str(&String::from_utf8_lossy(&head))?;
+    Ok(request)
+}
+
+// Reads the response to a request made with `method`. Interim 1xx responses
+// other than `101 Switching Protocols` are skipped, and a body with neither
+// Content-Length nor chunked framing runs to the end of the stream.
+pub(crate) fn read_response<R: Read>(reader: &mut R, method: &Method, config: &ParserConfig) -> Result<Response, ParseError> {
+    loop {
+	let head: Vec<u8> = read_head_bytes(reader, config)?;
+	let mut response: Response = Response::parse(&String::from_utf8_lossy(&head))?;
+	let status: StatusCode = response.status();
+	if status.is_informational() && status != StatusCode::SwitchingProtocols {
+	    continue;
+	}
+	let bodiless: bool = *method == Method::HEAD || status.is_informational() || status == StatusCode::NoContent || status == StatusCode::NotModified;
+	let body: Vec<u8> = if bodiless {
+	    Vec::new()
+	} else if headers::is_chunked(response.headers()) {
+	    read_chunked(reader, config)?.0
+	} else if let Some(length) = response.content_length()? {
+	    let length: usize = usize::try_from(length).unwrap_or(usize::MAX);
+	    config.check_body_size(length)?;
+	    let mut body: Vec<u8> = Vec::new();
+	    reader.take(length as u64).read_to_end(&mut body).map_err(io_error)?;
+	    if body.len() < length {
+		return Err(unexpected_eof());
+	    }
+	    body
+	} else {
+	    let mut body: Vec<u8> = Vec::new();
+	    reader.take((config.max_body_size() as u64).saturating_add(1)).read_to_end(&mut body).map_err(io_error)?;
+	    config.check_body_size(body.len())?;
+	    body
+	};
+	response.set_body_bytes(&body);
+	return Ok(response);
+    }
+}
+
+fn read_head_bytes<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<Vec<u8>, ParseError> {
+    let mut head: Vec<u8> = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+	config.check_head_size(head.len() + 1)?;
+	head.push(read_byte(reader)?);
+    }
+    Ok(head)
+}
+
+// Reads the body framed by the head of `request`, using its config limits.
+pub(crate) fn read_body<R: Read>(reader: &mut R, request: &mut Request) -> Result<(), ParseError> {
+    let config: ParserConfig = *request.config();
+    let body: Vec<u8> = if headers::is_chunked(&request.headers) {
+	let (body, trailers) = read_chunked(reader, &config)?;
+	request.trailers = trailers;
+	body
+    } else if let Some(length) = body_length(request, &config)? {
+	let mut body: Vec<u8> = Vec::new();
+	reader.take(length as u64).read_to_end(&mut body).map_err(io_error)?;
+	if body.len() < length {
+	    return Err(unexpected_eof());
+	}
+	body
+    } else {
+	Vec::new()
+    };
+    request.set_body_bytes(&body);
+    Ok(())
+}
+
+// Parses the request at the start of `bytes` and reports how many bytes it
+// took, so pipelined requests can be parsed one after another from a single
+// buffer. None means the buffer ends before the request does.
+pub(crate) fn parse_prefix(bytes: &[u8], config: &ParserConfig) -> Result<Option<(Request, usize)>, ParseError> {
+    let mut remaining: &[u8] = bytes;
+    match read_request(&mut remaining, config) {
+	Ok(request) => Ok(Some((request, bytes.len() - remaining.len()))),
+	Err(err) if err == unexpected_eof() => Ok(None),
+	Err(err) => Err(err),
+    }
+}
+
+fn read_chunked<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<(Vec<u8>, HeaderMap), ParseError> {
+    let mut body: Vec<u8> = Vec::new();
+    loop {
+	let size: usize = chunk_size(&read_line(reader, config)?, body.len(), config)?;
+	if size == 0 {
+	    break;
+	}
+	let start: usize = body.len();
+	reader.take(size as u64).read_to_end(&mut body).map_err(io_error)?;
+	if body.len() - start < size {
+	    return Err(unexpected_eof());
+	}
+	let end: String = read_line(reader, config)?;
+	if !end.is_empty() {
+	    return Err(ParseError::InvalidChunk(end));
+	}
+    }
+    let mut trailers: HeaderMap = HeaderMap::new();
+    loop {
+	let line: String = read_line(reader, config)?;
+	if line.is_empty() {
+	    break;
+	}
+	parse_trailer(&line, &mut trailers, config)?;
+    }
+    Ok((body, trailers))
+}
+
+// The Content-Length of a request without Transfer-Encoding. As in
+// `RequestParser`, a final transfer coding other than chunked leaves no way
+// to find the end of the body and is refused.
+pub(crate) fn body_length(request: &Request, config: &ParserConfig) -> Result<Option<usize>, ParseError> {
+    if let Some(codings) = request.headers.get_joined("transfer-encoding") {
+	if !headers::is_chunked(&request.headers) {
+	    return Err(ParseError::UnsupportedEncoding(codings));
+	}
+	return Ok(None);
+    }
+    let length: u64 = match request.content_length()? {
+	Some(length) => length,
+	None => return Ok(None),
+    };
+    let length: usize = usize::try_from(length).unwrap_or(usize::MAX);
+    config.check_body_size(length)?;
+    Ok(Some(length))
+}
+
+fn read_line<R: Read>(reader: &mut R, config: &ParserConfig) -> Result<String, ParseError> {
+    let mut line: Vec<u8> = Vec::new();
+    while !line.ends_with(b"\r\n") {
+	config.check_line_length(line.len() + 1)?;
+	line.push(read_byte(reader)?);
+    }
+    line.truncate(line.len() - 2);
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> Result<u8, ParseError> {
+    let mut byte: [u8; 1] = [0];
+    loop {
+	match reader.read(&mut byte) {
+	    Ok(0) => return Err(unexpected_eof()),
+	    Ok(_) => return Ok(byte[0]),
+	    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+	    Err(err) => return Err(io_error(err)),
+	}
+    }
+}
+
+pub(crate) fn io_error(err: std::io::Error) -> ParseError {
+    ParseError::Io(err.to_string())
+}
+
+pub(crate) fn unexpected_eof() -> ParseError {
+    ParseError::Io("unexpected end of stream".to_string())
+}
+
+#[cfg(test)]
+mod test_read_request {
+    use super::ParserConfig;
+    use crate::{Method, ParseError, Request};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_content_length() {
+	let mut reader: Cursor<&[u8]> = Cursor::new(b"POST /submit HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET / HTTP/1.1\r\n\r\n");
+	let request: Request = Request::parse_from_reader(&mut reader).unwrap();
+	assert_eq!(*request.method(), Method::POST);
+	assert_eq!(request.body(), "hello");
+	assert_eq!(reader.position(), 49);
+
+	let request: Request = Request::parse_from_reader(&mut reader).unwrap();
+	assert_eq!(request.path(), "/");
+	assert_eq!(request.body(), "");
+    }
+
+    #[test]
+    fn test_chunked() {
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nExpires: never\r\n\r\n";
+	let request: Request = Request::parse_from_reader(&mut reader).unwrap();
+	assert_eq!(request.body(), "hello, world");
+	assert_eq!(request.trailers().get("expires").unwrap().value(), "never");
+	assert!(reader.is_empty());
+
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+	assert_eq!(Request::parse_from_reader(&mut reader).err(), Some(ParseError::InvalidChunk("zz".to_string())));
+	for size in ["+3", " 3", "3 ", "0x3", "3 x"] {
+	    let mut reader: &[u8] = &[&b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"[..], size.as_bytes(), b"\r\nabc\r\n0\r\n\r\n"].concat();
+	    assert_eq!(Request::parse_from_reader(&mut reader).err(), Some(ParseError::InvalidChunk(size.to_string())));
+	}
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3 ;ext\r\nabc\r\n0\r\n\r\n";
+	assert_eq!(Request::parse_from_reader(&mut reader).unwrap().body(), "abc");
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_body_size(usize::MAX);
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\nffffffffffffffff\r\n";
+	assert_eq!(Request::parse_from_reader_with_config(&mut reader, &config).err(), Some(ParseError::InvalidChunk("ffffffffffffffff".to_string())));
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\nno colon\r\n\r\n";
+	assert_eq!(Request::parse_from_reader(&mut reader).err(), Some(ParseError::MalformedHeader("no colon".to_string())));
+    }
+
+    #[test]
+    fn test_chunked_round_trip() {
+	let mut request: Request = Request::builder().method(Method::POST).path("/upload").body("hello, world").trailer("X-Checksum", "abc123").build().unwrap();
+	request.set_header("Content-Length", "12");
+	let bytes: Vec<u8> = request.to_chunked_bytes(5);
+	assert_eq!(
+	    bytes,
+	    b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTrailer: X-Checksum\r\n\r\n5\r\nhello\r\n5\r\n, wor\r\n2\r\nld\r\n0\r\nX-Checksum: abc123\r\n\r\n"
+	);
+	let mut reader: &[u8] = &bytes;
+	let parsed: Request = Request::parse_from_reader(&mut reader).unwrap();
+	assert_eq!(parsed.body(), "hello, world");
+	assert_eq!(parsed.trailers().get("x-checksum").unwrap().value(), "abc123");
+    }
+
+    #[test]
+    fn test_config() {
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_head_size(16);
+	let mut reader: &[u8] = b"GET /a/long/path HTTP/1.1\r\n\r\n";
+	assert_eq!(Request::parse_from_reader_with_config(&mut reader, &config).err(), Some(ParseError::LimitExceeded("max_head_size".to_string())));
+
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_body_size(4);
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+	assert_eq!(Request::parse_from_reader_with_config(&mut reader, &config).err(), Some(ParseError::LimitExceeded("max_body_size".to_string())));
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n";
+	assert_eq!(Request::parse_from_reader_with_config(&mut reader, &config).err(), Some(ParseError::LimitExceeded("max_body_size".to_string())));
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+	let mut buffer: &[u8] = b"GET /a HTTP/1.1\r\n\r\nPOST /b HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcPUT /c HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1\r\nx\r\n0\r\n\r\nGET /d HT";
+	let mut paths: Vec<String> = Vec::new();
+	while let Some((request, consumed)) = Request::parse_prefix(buffer).unwrap() {
+	    paths.push(format!("{} {} {}", request.method(), request.path(), request.body()));
+	    buffer = &buffer[consumed..];
+	}
+	assert_eq!(paths, vec!["GET /a ", "POST /b abc", "PUT /c x"]);
+	assert_eq!(buffer, b"GET /d HT");
+
+	assert_eq!(Request::parse_prefix(b"").unwrap().map(|(_, consumed)| consumed), None);
+	assert_eq!(Request::parse_prefix(b"BR@W / HTTP/1.1\r\n\r\n").err(), Some(ParseError::UnsupportedMethod("BR@W".to_string())));
+    }
+
+    #[test]
+    fn test_expect_continue() {
+	let mut reader: &[u8] = b"PUT /file HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 4\r\n\r\ndata";
+	let mut request: Request = Request::parse_head_from_reader(&mut reader).unwrap();
+	assert!(request.expects_continue());
+	assert_eq!(request.body(), "");
+	assert_eq!(reader, b"data");
+	request.read_body_from_reader(&mut reader).unwrap();
+	assert_eq!(request.body(), "data");
+	assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_truncated() {
+	let mut reader: &[u8] = b"GET / HTTP/1.1\r\nHost: local";
+	assert_eq!(Request::parse_from_reader(&mut reader).err(), Some(ParseError::Io("unexpected end of stream".to_string())));
+	let mut reader: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort";
+	assert_eq!(Request::parse_from_reader(&mut reader).err(), Some(ParseError::Io("unexpected end of stream".to_string())));
+    }
+}
+
+#[cfg(test)]
+mod test_reader {
+    use super::read_request;
+    use crate::{ParseError, ParserConfig, Request};
+
+    #[test]
+    fn test_read_body() {
+	let config: ParserConfig = ParserConfig::new();
+	let mut input: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 9\r\n\r\n2\r\nhi\r\n0\r\n\r\nnext";
+	let request: Request = read_request(&mut input, &config).unwrap();
+	assert_eq!(request.body(), "hi");
+	assert_eq!(input, b"next");
+
+	let mut input: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\nContent-Length: 4\r\n\r\nbody";
+	assert_eq!(read_request(&mut input, &config).err(), Some(ParseError::UnsupportedEncoding("gzip".to_string())));
+	let mut input: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\nbody";
+	assert_eq!(read_request(&mut input, &config).err(), Some(ParseError::UnsupportedEncoding("chunked, gzip".to_string())));
+    }
+}
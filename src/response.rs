@@ -0,0 +1,478 @@
+use alloc::borrow::Cow;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use crate::prelude::*;
+use crate::{chunked, connection, encoding, headers, scan, CacheControl, Cookie, Encoding, ETag, Header, HeaderMap, HttpDate, MediaType, MessageDisplay, ParseError, ParserConfig, StatusCode, Version};
+
+#[derive(Clone)]
+pub struct Response {
+    headers: HeaderMap,
+    body: Vec<u8>,
+    status: StatusCode,
+    reason: String,
+    version: Version,
+    auto_content_length: bool,
+}
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	MessageDisplay::response(self).fmt(f)
+    }
+}
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	f.debug_struct("Response")
+	    .field("version", &self.version)
+	    .field("status", &self.status)
+	    .field("reason", &self.reason)
+	    .field("headers", &self.headers)
+	    .field("body", &String::from_utf8_lossy(&self.body))
+	    .finish_non_exhaustive()
+    }
+}
+impl Default for Response {
+    fn default() -> Response {
+	Response::new()
+    }
+}
+impl Response {
+    pub fn new() -> Response {
+	Response {
+	    headers: HeaderMap::new(),
+	    body: Vec::new(),
+	    status: StatusCode::Ok,
+	    reason: StatusCode::Ok.canonical_reason().to_string(),
+	    version: Version::Http11,
+	    auto_content_length: true,
+	}
+    }
+
+    // The interim `100 Continue` response telling a client that sent
+    // `Expect: 100-continue` to go ahead with the body.
+    pub fn continue_response() -> Response {
+	let mut response: Response = Response::new();
+	response.set_status(StatusCode::Continue);
+	response
+    }
+
+    pub fn parse(response: &str) -> Result<Response, ParseError> {
+	let mut parsed: Response = Response::new();
+	parsed.parse_from_str(response)?;
+	Ok(parsed)
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+	&self.headers
+    }
+
+    // The body as text; bytes that are not valid UTF-8 are replaced with U+FFFD.
+    // Use `body_bytes` for binary payloads.
+    pub fn body(&self) -> Cow<'_, str> {
+	String::from_utf8_lossy(&self.body)
+    }
+
+    pub fn set_body(&mut self, body: &str) {
+	self.set_body_bytes(body.as_bytes());
+    }
+
+    pub fn body_bytes(&self) -> &[u8] {
+	&self.body
+    }
+
+    pub fn set_body_bytes(&mut self, body: &[u8]) {
+	self.body = body.to_vec();
+    }
+
+    // The body with every `Content-Encoding` coding removed, limited to the
+    // default `max_body_size`.
+    pub fn decoded_body(&self) -> Result<Cow<'_, [u8]>, ParseError> {
+	let content_encoding: Option<String> = self.headers.get_joined("content-encoding");
+	encoding::decode_body(&self.body, content_encoding.as_deref(), ParserConfig::new().max_body_size())
+    }
+
+    pub fn set_body_compressed(&mut self, body: &[u8], encoding: Encoding) {
+	self.set_body_bytes(&encoding.encode(body));
+	if encoding == Encoding::Identity {
+	    self.remove_header("Content-Encoding");
+	} else {
+	    self.set_header("Content-Encoding", encoding.as_str());
+	}
+    }
+
+    pub fn version(&self) -> &Version {
+	&self.version
+    }
+
+    pub fn set_version(&mut self, version: Version) {
+	self.version = version;
+    }
+
+    pub fn status(&self) -> StatusCode {
+	self.status
+    }
+
+    pub fn set_status(&mut self, status: StatusCode) {
+	self.status = status;
+	self.reason = status.canonical_reason().to_string();
+    }
+
+    pub fn reason(&self) -> &String {
+	&self.reason
+    }
+
+    pub fn set_reason(&mut self, reason: &str) {
+	self.reason = reason.to_string();
+    }
+
+    // A plain-text view with credentials and cookies masked, for logs.
+    pub fn redacted(&self) -> MessageDisplay<'_> {
+	MessageDisplay::response(self).redacted()
+    }
+
+    // The `Display` output with ANSI-highlighted section titles.
+    pub fn colored(&self) -> MessageDisplay<'_> {
+	MessageDisplay::response(self).colored()
+    }
+
+    pub fn find_header(&self, name: &str) -> Option<&Header> {
+	self.headers.get(name)
+    }
+
+    pub fn find_headers(&self, name: &str) -> Vec<&Header> {
+	self.headers.get_all(name)
+    }
+
+    pub fn set_header(&mut self, header_name: &str, header_value: &str) {
+	self.headers.insert(header_name, header_value);
+    }
+
+    pub fn add_header(&mut self, header_name: &str, header_value: &str) {
+	self.set_header(header_name, header_value);
+    }
+
+    // Adds another value for the header, keeping any existing ones.
+    pub fn append_header(&mut self, header_name: &str, header_value: &str) {
+	self.headers.append(header_name, header_value);
+    }
+
+    pub fn remove_header(&mut self, header_name: &str) -> Vec<Header> {
+	self.headers.remove(header_name)
+    }
+
+    // Every `Set-Cookie` header; unlike other fields these are never combined.
+    pub fn cookies(&self) -> Vec<Cookie> {
+	self.headers.get_all("set-cookie").iter()
+	    .filter_map(|header| Cookie::parse_set_cookie(header.value()))
+	    .collect()
+    }
+
+    // Adds a `Set-Cookie` header, replacing an earlier one for the same cookie name.
+    pub fn set_cookie(&mut self, cookie: &Cookie) {
+	let mut values: Vec<String> = self.headers.remove("set-cookie").iter().map(|header| header.value().clone()).collect();
+	let existing = values.iter_mut().find(|value| Cookie::parse_set_cookie(value).is_some_and(|stored| stored.name() == cookie.name()));
+	match existing {
+	    Some(value) => *value = cookie.build(),
+	    None => values.push(cookie.build()),
+	}
+	for value in values {
+	    self.headers.append("Set-Cookie", &value);
+	}
+    }
+
+    pub fn content_type(&self) -> Result<Option<MediaType>, ParseError> {
+	self.find_header("content-type").map(|header| MediaType::parse(header.value())).transpose()
+    }
+
+    pub fn content_length(&self) -> Result<Option<u64>, ParseError> {
+	self.find_header("content-length").map(|header| headers::parse_content_length(header.value())).transpose()
+    }
+
+    pub fn cache_control(&self) -> Result<Option<CacheControl>, ParseError> {
+	self.headers.get_joined("cache-control").map(|value| CacheControl::parse(&value)).transpose()
+    }
+
+    pub fn etag(&self) -> Result<Option<ETag>, ParseError> {
+	self.find_header("etag").map(|header| ETag::parse(header.value())).transpose()
+    }
+
+    pub fn last_modified(&self) -> Result<Option<HttpDate>, ParseError> {
+	self.find_header("last-modified").map(|header| HttpDate::parse(header.value())).transpose()
+    }
+
+    pub fn keep_alive(&self) -> bool {
+	connection::is_keep_alive(&self.headers, self.version)
+    }
+
+    pub fn set_keep_alive(&mut self, keep_alive: bool) {
+	connection::set_keep_alive(&mut self.headers, self.version, keep_alive);
+    }
+
+    pub fn date(&self) -> Result<Option<HttpDate>, ParseError> {
+	self.find_header("date").map(|header| HttpDate::parse(header.value())).transpose()
+    }
+
+    pub fn set_date(&mut self, date: HttpDate) {
+	self.set_header("Date", &date.to_string());
+    }
+
+    #[cfg(feature = "std")]
+    pub fn set_date_now(&mut self) {
+	self.set_date(HttpDate::now());
+    }
+
+    pub fn auto_content_length(&self) -> bool {
+	self.auto_content_length
+    }
+
+    // When enabled (the default), `build`, `to_bytes` and `write_to` set the
+    // Content-Length header from the actual body size. Responses that cannot
+    // carry a body (1xx, 204, 304) are left untouched.
+    pub fn set_auto_content_length(&mut self, enabled: bool) {
+	self.auto_content_length = enabled;
+    }
+
+    pub fn parse_from_str(&mut self, response: &str) -> Result<(), ParseError> {
+	self.parse_response(response)
+    }
+
+    // A 1xx, 204 or 304 response is built without its body, and with
+    // chunked transfer coding the body is sent as a single chunk.
+    pub fn build(&self) -> String {
+	format!("{}{}", self.build_head(), String::from_utf8_lossy(&self.encoded_body()))
+    }
+
+    // Like `build`, but keeps a binary body intact.
+    pub fn to_bytes(&self) -> Vec<u8> {
+	let mut bytes: Vec<u8> = self.build_head().into_bytes();
+	bytes.extend_from_slice(&self.encoded_body());
+	bytes
+    }
+
+    // Writes the status line, headers and body without building the whole
+    // response in memory first. Returns the number of bytes written.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+	let head: String = self.build_head();
+	let body: Cow<'_, [u8]> = self.encoded_body();
+	writer.write_all(head.as_bytes())?;
+	writer.write_all(&body)?;
+	Ok(head.len() + body.len())
+    }
+
+    pub(crate) fn build_head(&self) -> String {
+	let mut head: String = format!("{} {} {}\r\n", self.version, self.status.as_u16(), self.reason);
+	headers::write_header_lines(&mut head, &self.headers, Some(self.body.len()).filter(|_| self.auto_content_length && !self.is_bodiless()));
+	head.push_str("\r\n");
+	head
+    }
+
+    fn encoded_body(&self) -> Cow<'_, [u8]> {
+	if self.is_bodiless() {
+	    Cow::Borrowed(&[])
+	} else if headers::is_chunked(&self.headers) {
+	    Cow::Owned(chunked::encode(&self.body, self.body.len(), &HeaderMap::new()))
+	} else {
+	    Cow::Borrowed(&self.body)
+	}
+    }
+
+    fn is_bodiless(&self) -> bool {
+	self.status.is_informational() || self.status == StatusCode::NoContent || self.status == StatusCode::NotModified
+    }
+
+    fn parse_response(&mut self, response: &str) -> Result<(), ParseError> {
+	if response.is_empty() {
+	    return Err(ParseError::InvalidStatusLine(String::new()));
+	}
+	let mut pending: Option<Cow<'_, str>> = None;
+	let mut lines: scan::Lines<'_> = scan::lines(response, false);
+	for (i, line) in lines.by_ref().enumerate() {
+	    if i == 0 {
+		self.parse_status_line(line)?;
+		continue;
+	    } else if line.is_empty() {
+		break;
+	    }
+	    if headers::is_obs_fold(line) {
+		if let Some(previous) = pending.as_mut() {
+		    headers::unfold(previous, line);
+		    continue;
+		}
+	    }
+	    if let Some(previous) = pending.replace(Cow::Borrowed(line)) {
+		self.parse_header_line(&previous)?;
+	    }
+	}
+	if let Some(previous) = pending {
+	    self.parse_header_line(&previous)?;
+	}
+	// The body is everything after the blank line, byte for byte.
+	self.body = lines.rest().as_bytes().to_vec();
+	Ok(())
+    }
+
+    fn parse_status_line(&mut self, line: &str) -> Result<(), ParseError> {
+	let parts: Vec<&str> = line.splitn(3, ' ').collect();
+	if parts.len() < 2 || !parts[0].starts_with("HTTP/") {
+	    return Err(ParseError::InvalidStatusLine(line.to_string()));
+	}
+	let version: Version = parts[0].parse()?;
+	let status: StatusCode = match parts[1].parse::<u16>() {
+	    Ok(code) => StatusCode::try_from(code)?,
+	    Err(_) => return Err(ParseError::UnsupportedStatusCode(parts[1].to_string())),
+	};
+	self.version = version;
+	self.status = status;
+	self.reason = parts.get(2).unwrap_or(&"").to_string();
+	Ok(())
+    }
+
+    fn parse_header_line(&mut self, line: &str) -> Result<(), ParseError> {
+	let (name, value) = match headers::split_header_line(line, false) {
+	    Some((name, value)) => (name, value),
+	    None => return Err(ParseError::MalformedHeader(line.to_string())),
+	};
+
+	headers::append_field(&mut self.headers, name, value);
+	Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_response {
+    use super::Response;
+    use crate::{CacheControl, Cookie, ETag, HttpDate, MediaType, ParseError, StatusCode, Version};
+
+    #[test]
+    fn test_new() {
+	let response: Response = Response::new();
+	assert_eq!(response.headers().len(), 0);
+	assert_eq!(response.body(), "");
+	assert_eq!(response.status(), StatusCode::Ok);
+	assert_eq!(response.reason(), "OK");
+	assert_eq!(*response.version(), Version::Http11);
+    }
+
+    #[test]
+    fn test_parse_from_str() {
+	let mut response: Response = Response::new();
+	response.parse_from_str("HTTP/1.0 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: 9\r\n\r\nnot found").unwrap();
+	assert_eq!(*response.version(), Version::Http10);
+	assert_eq!(response.status(), StatusCode::NotFound);
+	assert_eq!(response.reason(), "Not Found");
+	assert_eq!(response.headers().len(), 2);
+	assert_eq!(response.content_type(), Ok(Some(MediaType::new("text", "plain"))));
+	assert_eq!(response.content_length(), Ok(Some(9)));
+	assert_eq!(response.body(), "not found");
+
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nVary: Accept\r\nSet-Cookie: a=1\r\nX-Note: one\r\n  two\r\nvary: Origin\r\nSet-Cookie: b=2\r\n\r\n").unwrap();
+	assert_eq!(response.find_header("vary").unwrap().value(), "Accept, Origin");
+	assert_eq!(response.find_header("x-note").unwrap().value(), "one two");
+	assert_eq!(response.find_headers("set-cookie").len(), 2);
+
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nContent-Length: 12\r\n\r\na\r\n\r\nb\nc\r\n\r\n").unwrap();
+	assert_eq!(response.body(), "a\r\n\r\nb\nc\r\n\r\n");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+	assert_eq!(Response::parse("HTTP/1.1 200\r\n\r\n").unwrap().reason(), "");
+	assert_eq!(Response::parse("").err(), Some(ParseError::InvalidStatusLine(String::new())));
+	assert_eq!(Response::parse("200 OK\r\n\r\n").err(), Some(ParseError::InvalidStatusLine("200 OK".to_string())));
+	assert_eq!(Response::parse("HTTP/1.2 200 OK\r\n\r\n").err(), Some(ParseError::UnsupportedVersion("HTTP/1.2".to_string())));
+	assert_eq!(Response::parse("HTTP/1.1 abc OK\r\n\r\n").err(), Some(ParseError::UnsupportedStatusCode("abc".to_string())));
+	assert_eq!(Response::parse("HTTP/1.1 200 OK\r\nServer\r\n\r\n").err(), Some(ParseError::MalformedHeader("Server".to_string())));
+    }
+
+    #[test]
+    fn test_build() {
+	let mut response: Response = Response::new();
+	response.set_status(StatusCode::Created);
+	response.add_header("Location", "/items/1");
+	response.set_header("location", "/items/2");
+	response.set_body("created");
+	assert_eq!(response.build(), "HTTP/1.1 201 Created\r\nLocation: /items/2\r\nContent-Length: 7\r\n\r\ncreated");
+	assert_eq!(Response::parse(&response.build()).unwrap().build(), response.build());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_to() {
+	let mut response: Response = Response::new();
+	response.set_body_bytes(&[0x00, 0xff]);
+	let mut written: Vec<u8> = Vec::new();
+	assert_eq!(response.write_to(&mut written).unwrap(), 40);
+	assert_eq!(written, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n\x00\xff");
+	assert_eq!(response.to_bytes(), written);
+    }
+
+    #[test]
+    fn test_auto_content_length() {
+	let mut response: Response = Response::new();
+	response.set_header("Content-Length", "100");
+	response.set_body("short");
+	assert_eq!(response.build(), "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nshort");
+	assert_eq!(response.content_length(), Ok(Some(100)));
+
+	response.set_status(StatusCode::NotModified);
+	response.remove_header("Content-Length");
+	assert_eq!(response.build(), "HTTP/1.1 304 Not Modified\r\n\r\n");
+	response.set_status(StatusCode::NoContent);
+	assert_eq!(response.to_bytes(), b"HTTP/1.1 204 No Content\r\n\r\n");
+
+	response.set_status(StatusCode::Ok);
+	response.set_header("Content-Length", "5");
+	response.set_header("Transfer-Encoding", "chunked");
+	assert_eq!(response.build(), "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nshort\r\n0\r\n\r\n");
+	response.remove_header("Transfer-Encoding");
+	response.remove_header("Content-Length");
+
+	response.set_status(StatusCode::Ok);
+	response.set_auto_content_length(false);
+	assert_eq!(response.build(), "HTTP/1.1 200 OK\r\n\r\nshort");
+	response.set_header("Content-Length", "ten");
+	assert_eq!(response.content_length(), Err(ParseError::InvalidContentLength("ten".to_string())));
+    }
+
+    #[test]
+    fn test_continue_response() {
+	assert_eq!(Response::continue_response().to_bytes(), b"HTTP/1.1 100 Continue\r\n\r\n");
+    }
+
+    #[test]
+    fn test_validators() {
+	let response: Response = Response::parse("HTTP/1.1 200 OK\r\nETag: W/\"abc\"\r\nLast-Modified: Sun, 06 Nov 1994 08:49:37 GMT\r\nCache-Control: private, max-age=60\r\n\r\n").unwrap();
+	assert_eq!(response.etag(), Ok(Some(ETag::weak("abc"))));
+	assert_eq!(response.last_modified(), Ok(Some(HttpDate::from_unix_seconds(784111777))));
+	let cache_control: CacheControl = response.cache_control().unwrap().unwrap();
+	assert!(cache_control.is_private());
+	assert_eq!(cache_control.max_age(), Some(60));
+	assert_eq!(Response::new().etag(), Ok(None));
+
+	let mut response: Response = Response::parse("HTTP/1.0 200 OK\r\nConnection: keep-alive\r\n\r\n").unwrap();
+	assert!(response.keep_alive());
+	response.set_keep_alive(false);
+	assert!(!response.keep_alive());
+	assert_eq!(response.find_header("Connection").unwrap().value(), "close");
+
+	let mut response: Response = Response::new();
+	response.set_date(HttpDate::from_unix_seconds(0));
+	assert_eq!(response.build(), "HTTP/1.1 200 OK\r\nDate: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n");
+	assert_eq!(response.date(), Ok(Some(HttpDate::from_unix_seconds(0))));
+    }
+
+    #[test]
+    fn test_cookies() {
+	let mut response: Response = Response::parse("HTTP/1.1 200 OK\r\nSet-Cookie: a=1; Path=/\r\nSet-Cookie: b=2; Secure\r\n\r\n").unwrap();
+	assert_eq!(response.headers().len(), 2);
+	assert_eq!(response.cookies().len(), 2);
+	assert!(response.cookies()[1].secure());
+
+	let mut cookie: Cookie = Cookie::new("a", "3");
+	cookie.set_http_only(true);
+	response.set_cookie(&cookie);
+	response.set_cookie(&Cookie::new("c", "4"));
+	assert_eq!(response.build(), "HTTP/1.1 200 OK\r\nSet-Cookie: a=3; HttpOnly\r\nSet-Cookie: b=2; Secure\r\nSet-Cookie: c=4\r\n\r\n");
+    }
+}
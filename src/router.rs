@@ -0,0 +1,292 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{Method, Request};
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum RouteError {
+    InvalidPattern(String),
+    MissingParam(String),
+    InvalidParam(String, String),
+}
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    RouteError::InvalidPattern(pattern) => write!(f, "Invalid route pattern: `{}`", pattern),
+	    RouteError::MissingParam(name) => write!(f, "Missing path parameter: `{}`", name),
+	    RouteError::InvalidParam(name, value) => write!(f, "Invalid value for path parameter `{}`: `{}`", name, value),
+	}
+    }
+}
+impl core::error::Error for RouteError {}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Segment {
+    Static(String),
+    Param(String),
+    // `{*name}`, which captures the rest of the path and must come last.
+    CatchAll(String),
+}
+
+// A path pattern such as `/users/{id}/posts/{post_id}`. Each `{name}`
+// captures one whole segment; a final `{*name}` captures the remaining path,
+// slashes included.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Pattern {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Pattern, RouteError> {
+	let invalid = || RouteError::InvalidPattern(pattern.to_string());
+	let path: &str = pattern.strip_prefix('/').ok_or_else(invalid)?;
+	let mut segments: Vec<Segment> = Vec::new();
+	let mut names: Vec<&str> = Vec::new();
+	for part in path.split('/') {
+	    if matches!(segments.last(), Some(Segment::CatchAll(_))) {
+		return Err(invalid());
+	    }
+	    let segment: Segment = match part.strip_prefix('{').and_then(|part| part.strip_suffix('}')) {
+		Some(name) => {
+		    let (name, catch_all) = match name.strip_prefix('*') {
+			Some(name) => (name, true),
+			None => (name, false),
+		    };
+		    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || names.contains(&name) {
+			return Err(invalid());
+		    }
+		    names.push(name);
+		    if catch_all { Segment::CatchAll(name.to_string()) } else { Segment::Param(name.to_string()) }
+		}
+		None if part.contains(['{', '}']) => return Err(invalid()),
+		None => Segment::Static(part.to_string()),
+	    };
+	    segments.push(segment);
+	}
+	Ok(Pattern {
+	    pattern: pattern.to_string(),
+	    segments,
+	})
+    }
+
+    pub fn as_str(&self) -> &str {
+	&self.pattern
+    }
+
+    // The captured parameters when `path` matches. Trailing slashes are
+    // significant, so `/users` and `/users/` are different paths.
+    pub fn matches(&self, path: &str) -> Option<Params> {
+	let mut parts = path.strip_prefix('/')?.split('/');
+	let mut params: Params = Params::new();
+	for segment in &self.segments {
+	    match segment {
+		Segment::CatchAll(name) => {
+		    params.push(name, &parts.by_ref().collect::<Vec<&str>>().join("/"));
+		    return Some(params);
+		}
+		Segment::Static(expected) => {
+		    if parts.next()? != expected {
+			return None;
+		    }
+		}
+		Segment::Param(name) => {
+		    let part: &str = parts.next().filter(|part| !part.is_empty())?;
+		    params.push(name, part);
+		}
+	    }
+	}
+	match parts.next() {
+	    Some(_) => None,
+	    None => Some(params),
+	}
+    }
+}
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "{}", self.pattern)
+    }
+}
+
+// Path parameters captured by a match, in pattern order.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Params {
+    params: Vec<(String, String)>,
+}
+impl Params {
+    pub fn new() -> Params {
+	Params { params: Vec::new() }
+    }
+
+    fn push(&mut self, name: &str, value: &str) {
+	self.params.push((name.to_string(), value.to_string()));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+	self.params.iter().find(|(param, _)| param == name).map(|(_, value)| value.as_str())
+    }
+
+    // The parameter converted with `FromStr`, e.g. `params.parse::<u64>("id")`.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<T, RouteError> {
+	let value: &str = self.get(name).ok_or_else(|| RouteError::MissingParam(name.to_string()))?;
+	value.parse().map_err(|_| RouteError::InvalidParam(name.to_string(), value.to_string()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+	self.params.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+	self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+	self.params.is_empty()
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Match<'a, T> {
+    value: &'a T,
+    pattern: &'a Pattern,
+    params: Params,
+}
+impl<'a, T> Match<'a, T> {
+    // The value registered with the route, typically a handler.
+    pub fn value(&self) -> &'a T {
+	self.value
+    }
+
+    pub fn pattern(&self) -> &'a Pattern {
+	self.pattern
+    }
+
+    pub fn params(&self) -> &Params {
+	&self.params
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Route<T> {
+    method: Option<Method>,
+    pattern: Pattern,
+    value: T,
+}
+
+// Maps a method and path pattern to a value of the caller's choosing, such
+// as a handler function. Routes are tried in the order they were added and
+// the first match wins, so add specific patterns before general ones.
+#[derive(Clone, Debug)]
+pub struct Router<T> {
+    routes: Vec<Route<T>>,
+}
+impl<T> Default for Router<T> {
+    fn default() -> Router<T> {
+	Router::new()
+    }
+}
+impl<T> Router<T> {
+    pub fn new() -> Router<T> {
+	Router { routes: Vec::new() }
+    }
+
+    pub fn route(&mut self, method: Method, pattern: &str, value: T) -> Result<(), RouteError> {
+	self.add(Some(method), pattern, value)
+    }
+
+    // A route that matches the path with any method.
+    pub fn route_any(&mut self, pattern: &str, value: T) -> Result<(), RouteError> {
+	self.add(None, pattern, value)
+    }
+
+    fn add(&mut self, method: Option<Method>, pattern: &str, value: T) -> Result<(), RouteError> {
+	self.routes.push(Route {
+	    method,
+	    pattern: Pattern::parse(pattern)?,
+	    value,
+	});
+	Ok(())
+    }
+
+    pub fn find(&self, request: &Request) -> Option<Match<'_, T>> {
+	self.find_path(request.method(), request.path())
+    }
+
+    pub fn find_path(&self, method: &Method, path: &str) -> Option<Match<'_, T>> {
+	self.routes.iter()
+	    .filter(|route| route.method.as_ref().is_none_or(|route_method| route_method == method))
+	    .find_map(|route| {
+		route.pattern.matches(path).map(|params| Match {
+		    value: &route.value,
+		    pattern: &route.pattern,
+		    params,
+		})
+	    })
+    }
+
+    // The methods routed for `path`, for the `Allow` header of a
+    // 405 Method Not Allowed response when `find` returns None. Empty when
+    // no route matches the path at all (404), or when a route accepts any
+    // method.
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+	let mut methods: Vec<Method> = Vec::new();
+	for route in self.routes.iter().filter(|route| route.pattern.matches(path).is_some()) {
+	    match &route.method {
+		Some(method) if !methods.contains(method) => methods.push(method.clone()),
+		Some(_) => {}
+		None => return Vec::new(),
+	    }
+	}
+	methods
+    }
+}
+
+#[cfg(test)]
+mod test_router {
+    use super::{Pattern, RouteError, Router};
+    use crate::{Method, Request};
+
+    #[test]
+    fn test_pattern() {
+	let pattern: Pattern = Pattern::parse("/users/{id}/posts/{post_id}").unwrap();
+	let params = pattern.matches("/users/42/posts/7").unwrap();
+	assert_eq!(params.get("id"), Some("42"));
+	assert_eq!(params.parse::<u32>("post_id"), Ok(7));
+	assert_eq!(params.parse::<u32>("slug"), Err(RouteError::MissingParam("slug".to_string())));
+	assert!(pattern.matches("/users/42/posts").is_none());
+	assert!(pattern.matches("/users//posts/7").is_none());
+	assert!(pattern.matches("/users/42/posts/7/").is_none());
+
+	let pattern: Pattern = Pattern::parse("/static/{*file}").unwrap();
+	assert_eq!(pattern.matches("/static/css/site.css").unwrap().get("file"), Some("css/site.css"));
+	assert_eq!(Pattern::parse("/").unwrap().matches("/").map(|params| params.len()), Some(0));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+	for pattern in ["users", "/users/{}", "/users/{id", "/users/x{id}", "/{id}/{id}", "/{*rest}/more"] {
+	    assert_eq!(Pattern::parse(pattern), Err(RouteError::InvalidPattern(pattern.to_string())));
+	}
+    }
+
+    #[test]
+    fn test_router() {
+	let mut router: Router<&str> = Router::new();
+	router.route(Method::GET, "/users/me", "me").unwrap();
+	router.route(Method::GET, "/users/{id}", "show").unwrap();
+	router.route(Method::DELETE, "/users/{id}", "delete").unwrap();
+	router.route_any("/health", "health").unwrap();
+
+	let request: Request = Request::parse("GET /users/15 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	let found = router.find(&request).unwrap();
+	assert_eq!(*found.value(), "show");
+	assert_eq!(found.pattern().as_str(), "/users/{id}");
+	assert_eq!(found.params().parse::<u64>("id"), Ok(15));
+	assert_eq!(router.find_path(&Method::GET, "/users/me").map(|found| *found.value()), Some("me"));
+	assert_eq!(router.find_path(&Method::POST, "/health").map(|found| *found.value()), Some("health"));
+
+	assert!(router.find_path(&Method::PUT, "/users/15").is_none());
+	assert_eq!(router.allowed_methods("/users/15"), vec![Method::GET, Method::DELETE]);
+	assert!(router.allowed_methods("/posts").is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use memchr::{memchr, memmem};
+
+// Byte scanning for the parsers' hot paths. `memchr` searches with SIMD
+// where the target has it and word-at-a-time otherwise, so long header
+// blocks are not walked one byte at a time.
+
+// The position of the first `\r\n`.
+pub(crate) fn find_crlf(bytes: &[u8]) -> Option<usize> {
+    let mut start: usize = 0;
+    while let Some(offset) = memchr(b'\r', &bytes[start..]) {
+	let position: usize = start + offset;
+	if bytes.get(position + 1) == Some(&b'\n') {
+	    return Some(position);
+	}
+	start = position + 1;
+    }
+    None
+}
+
+// The position of the blank line ending a message head, i.e. of the first
+// `\r\n\r\n`.
+pub(crate) fn find_head_end(bytes: &[u8]) -> Option<usize> {
+    memmem::find(bytes, b"\r\n\r\n")
+}
+
+// Splits `text` into lines without collecting them. Strict lines end only at
+// `\r\n` and behave like `text.split("\r\n")`; lenient lines also end at a
+// bare `\n` and behave like `text.lines()`.
+pub(crate) fn lines(text: &str, strict: bool) -> Lines<'_> {
+    Lines { rest: Some(text), strict }
+}
+
+pub(crate) struct Lines<'a> {
+    rest: Option<&'a str>,
+    strict: bool,
+}
+impl<'a> Lines<'a> {
+    // The text after the last line returned, e.g. the body once the blank
+    // line ending a head has been reached.
+    pub(crate) fn rest(&self) -> &'a str {
+	self.rest.unwrap_or("")
+    }
+}
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+	let rest: &'a str = self.rest?;
+	if self.strict {
+	    return match find_crlf(rest.as_bytes()) {
+		Some(end) => {
+		    self.rest = Some(&rest[end + 2..]);
+		    Some(&rest[..end])
+		}
+		None => self.rest.take(),
+	    };
+	}
+	match memchr(b'\n', rest.as_bytes()) {
+	    Some(end) => {
+		self.rest = Some(&rest[end + 1..]);
+		let line: &'a str = &rest[..end];
+		Some(line.strip_suffix('\r').unwrap_or(line))
+	    }
+	    None => self.rest.take().filter(|rest| !rest.is_empty()),
+	}
+    }
+}
+
+#[cfg(test)]
+mod test_scan {
+    use super::{find_crlf, find_head_end, lines};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_find() {
+	assert_eq!(find_crlf(b"a\rb\r\nc"), Some(3));
+	assert_eq!(find_crlf(b"a\nb\r"), None);
+	assert_eq!(find_head_end(b"GET / HTTP/1.1\r\nA: 1\r\n\r\nbody"), Some(20));
+	assert_eq!(find_head_end(b"GET / HTTP/1.1\n\n"), None);
+    }
+
+    #[test]
+    fn test_lines() {
+	for text in ["", "a", "a\r\nb", "a\r\n\r\nb\nc\r\n", "a\rb\r\n", "\r\n"] {
+	    assert_eq!(lines(text, true).collect::<Vec<&str>>(), text.split("\r\n").collect::<Vec<&str>>());
+	    assert_eq!(lines(text, false).collect::<Vec<&str>>(), text.lines().collect::<Vec<&str>>());
+	}
+	let mut head = lines("A\r\n\r\nb\r\n\r\n", true);
+	assert_eq!((head.next(), head.next()), (Some("A"), Some("")));
+	assert_eq!(head.rest(), "b\r\n\r\n");
+    }
+}
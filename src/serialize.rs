@@ -0,0 +1,68 @@
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::{Header, HeaderMap, Method, Version};
+
+// Methods and versions serialize as their wire form, e.g. "PROPFIND" and
+// "HTTP/1.1", and headers as an ordered list of name/value pairs.
+impl Serialize for Method {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.serialize_str(self.as_str())
+    }
+}
+impl<'de> Deserialize<'de> for Method {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Method, D::Error> {
+	String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+	String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+impl Serialize for HeaderMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.collect_seq(self.iter())
+    }
+}
+impl<'de> Deserialize<'de> for HeaderMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+	let mut headers: HeaderMap = HeaderMap::new();
+	for header in Vec::<Header>::deserialize(deserializer)? {
+	    headers.append(header.name(), header.value());
+	}
+	Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod test_serialize {
+    use crate::{HeaderMap, Method, Request, Version};
+
+    #[test]
+    fn test_round_trip() {
+	let request: Request = Request::parse("PROPFIND /files?depth=1 HTTP/1.0\r\nHost: localhost\r\nAccept: */*\r\n\r\n").unwrap();
+	let json: String = serde_json::to_string(&request).unwrap();
+	assert!(json.contains("\"method\":\"PROPFIND\""));
+	assert!(json.contains("\"version\":\"HTTP/1.0\""));
+	assert!(json.contains("\"headers\":[{\"name\":\"Host\",\"value\":\"localhost\"},{\"name\":\"Accept\",\"value\":\"*/*\"}]"));
+
+	let parsed: Request = serde_json::from_str(&json).unwrap();
+	assert_eq!(parsed.build(), request.build());
+	assert_eq!(parsed.find_header("accept").unwrap().value(), "*/*");
+    }
+
+    #[test]
+    fn test_invalid() {
+	assert_eq!(serde_json::from_str::<Method>("\"GET\"").unwrap(), Method::GET);
+	assert!(serde_json::from_str::<Method>("\"BR@W\"").is_err());
+	assert!(serde_json::from_str::<Version>("\"HTTP/1.2\"").is_err());
+	assert!(serde_json::from_str::<HeaderMap>("[{\"name\":\"Host\"}]").is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::layer::{Chain, Layer};
+use crate::{reader, Method, ParseError, ParserConfig, Request, Response, StatusCode};
+
+// A blocking HTTP/1.x listener that passes each request to a handler and
+// writes back the response it returns. Connections are kept alive as the
+// request and response allow, so pipelined requests are answered in order.
+//
+//     Server::bind("0.0.0.0:8080")?.serve(|request: Request| -> Response { ... })
+//
+// On Unix it can also listen on a socket file with `bind_unix`, as local
+// daemons such as Docker do. The path is its own argument rather than a
+// `unix:` address passed to `bind`, since `ToSocketAddrs` cannot name one.
+pub struct Server {
+    listener: Listener,
+    threads: usize,
+    read_timeout: Option<Duration>,
+    shared: Shared,
+}
+
+// What every connection needs, cloned into the thread serving it.
+#[derive(Clone)]
+struct Shared {
+    config: ParserConfig,
+    layers: Arc<Chain>,
+    #[cfg(feature = "rustls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Server> {
+	Ok(Server::new(Listener::Tcp(TcpListener::bind(addr)?)))
+    }
+
+    // Listens on a Unix domain socket at `path`, which must not exist yet.
+    #[cfg(unix)]
+    pub fn bind_unix<P: AsRef<Path>>(path: P) -> io::Result<Server> {
+	Ok(Server::new(Listener::Unix(UnixListener::bind(path)?)))
+    }
+
+    fn new(listener: Listener) -> Server {
+	Server {
+	    listener,
+	    threads: 0,
+	    read_timeout: Some(Duration::from_secs(30)),
+	    shared: Shared {
+		config: ParserConfig::new(),
+		layers: Arc::new(Chain::new()),
+		#[cfg(feature = "rustls")]
+		tls: None,
+	    },
+	}
+    }
+
+    // The bound address, e.g. to find the port chosen for port 0. Fails for
+    // a Unix domain socket.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+	match &self.listener {
+	    Listener::Tcp(listener) => listener.local_addr(),
+	    #[cfg(unix)]
+	    Listener::Unix(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "not a TCP listener")),
+	}
+    }
+
+    pub fn config(&self) -> &ParserConfig {
+	&self.shared.config
+    }
+
+    // Limits and strictness used to parse every request.
+    pub fn set_config(&mut self, config: ParserConfig) {
+	self.shared.config = config;
+    }
+
+    pub fn threads(&self) -> usize {
+	self.threads
+    }
+
+    // The size of the worker pool. With 0 (the default) every connection
+    // gets its own thread; otherwise connections queue for a free worker.
+    pub fn set_threads(&mut self, threads: usize) {
+	self.threads = threads;
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+	self.read_timeout
+    }
+
+    // How long a connection may sit idle, or stall mid-request, before it is
+    // closed. Defaults to 30 seconds; None waits forever. A zero duration is
+    // rejected, as sockets cannot be given one.
+    pub fn set_read_timeout(&mut self, read_timeout: Option<Duration>) -> io::Result<()> {
+	if read_timeout == Some(Duration::ZERO) {
+	    return Err(io::Error::new(io::ErrorKind::InvalidInput, "read timeout must not be zero"));
+	}
+	self.read_timeout = read_timeout;
+	Ok(())
+    }
+
+    pub fn layers(&self) -> &Chain {
+	&self.shared.layers
+    }
+
+    // Run on every request once its head has been read, before any body is
+    // read or `100 Continue` sent, and again through `Layer::on_body` once
+    // the body is in. A rejection is sent as the response and the connection
+    // is closed.
+    pub fn set_layers(&mut self, layers: Chain) {
+	self.shared.layers = Arc::new(layers);
+    }
+
+    #[cfg(feature = "rustls")]
+    pub fn tls(&self) -> Option<&Arc<rustls::ServerConfig>> {
+	self.shared.tls.as_ref()
+    }
+
+    // Serves HTTPS: every accepted connection starts with a TLS handshake
+    // using `config`, which holds the certificate chain and key. A failed
+    // handshake only closes that connection.
+    #[cfg(feature = "rustls")]
+    pub fn set_tls(&mut self, config: Arc<rustls::ServerConfig>) {
+	self.shared.tls = Some(config);
+    }
+
+    // Accepts connections until accepting fails for a reason other than the
+    // connection itself, e.g. running out of file descriptors, and returns
+    // that error. A handler that panics gets a `500 Internal Server Error`
+    // sent on its behalf and the connection is closed.
+    pub fn serve<F>(&self, handler: F) -> io::Result<()>
+    where
+	F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+	let handler: Arc<F> = Arc::new(handler);
+	let pool: Option<Sender<Connection>> = if self.threads > 0 { Some(self.spawn_workers(&handler)) } else { None };
+	loop {
+	    let stream: Connection = match self.listener.accept() {
+		Ok(stream) => stream,
+		// A connection reset before it was accepted only affects that
+		// connection.
+		Err(err) if matches!(err.kind(), ErrorKind::ConnectionAborted | ErrorKind::ConnectionReset | ErrorKind::Interrupted | ErrorKind::WouldBlock) => continue,
+		Err(err) => return Err(err),
+	    };
+	    if stream.set_read_timeout(self.read_timeout).is_err() {
+		continue;
+	    }
+	    match &pool {
+		Some(sender) => sender.send(stream).map_err(|_| io::Error::other("worker pool stopped"))?,
+		None => {
+		    let handler: Arc<F> = Arc::clone(&handler);
+		    let shared: Shared = self.shared.clone();
+		    thread::spawn(move || accept(stream, &shared, &*handler));
+		}
+	    }
+	}
+    }
+
+    fn spawn_workers<F>(&self, handler: &Arc<F>) -> Sender<Connection>
+    where
+	F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+	let (sender, receiver) = mpsc::channel::<Connection>();
+	let receiver: Arc<Mutex<Receiver<Connection>>> = Arc::new(Mutex::new(receiver));
+	for _ in 0..self.threads {
+	    let receiver: Arc<Mutex<Receiver<Connection>>> = Arc::clone(&receiver);
+	    let handler: Arc<F> = Arc::clone(handler);
+	    let shared: Shared = self.shared.clone();
+	    thread::spawn(move || loop {
+		let stream: Connection = match receiver.lock().map(|receiver| receiver.recv()) {
+		    Ok(Ok(stream)) => stream,
+		    _ => return,
+		};
+		accept(stream, &shared, &*handler);
+	    });
+	}
+	sender
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+impl Listener {
+    fn accept(&self) -> io::Result<Connection> {
+	match self {
+	    Listener::Tcp(listener) => listener.accept().map(|(stream, _)| Connection::Tcp(stream)),
+	    #[cfg(unix)]
+	    Listener::Unix(listener) => listener.accept().map(|(stream, _)| Connection::Unix(stream)),
+	}
+    }
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+impl Connection {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+	match self {
+	    Connection::Tcp(stream) => stream.set_read_timeout(timeout),
+	    #[cfg(unix)]
+	    Connection::Unix(stream) => stream.set_read_timeout(timeout),
+	}
+    }
+}
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+	match self {
+	    Connection::Tcp(stream) => stream.read(buf),
+	    #[cfg(unix)]
+	    Connection::Unix(stream) => stream.read(buf),
+	}
+    }
+}
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+	match self {
+	    Connection::Tcp(stream) => stream.write(buf),
+	    #[cfg(unix)]
+	    Connection::Unix(stream) => stream.write(buf),
+	}
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+	match self {
+	    Connection::Tcp(stream) => stream.flush(),
+	    #[cfg(unix)]
+	    Connection::Unix(stream) => stream.flush(),
+	}
+    }
+}
+
+// Starts TLS on the connection when the server has it configured, then
+// serves it.
+fn accept<S: Read + Write, F: Fn(Request) -> Response>(stream: S, shared: &Shared, handler: &F) {
+    #[cfg(feature = "rustls")]
+    if let Some(tls) = &shared.tls {
+	if let Ok(connection) = rustls::ServerConnection::new(Arc::clone(tls)) {
+	    let mut stream = rustls::StreamOwned::new(connection, stream);
+	    handle_connection(&mut stream, &shared.config, &shared.layers, handler);
+	    stream.conn.send_close_notify();
+	    let _ = stream.flush();
+	}
+	return;
+    }
+    let mut stream: S = stream;
+    handle_connection(&mut stream, &shared.config, &shared.layers, handler);
+}
+
+// Serves requests on one connection until either side asks to close it or
+// the client goes away.
+fn handle_connection<S: Read + Write, F: Fn(Request) -> Response>(stream: &mut S, config: &ParserConfig, layers: &Chain, handler: &F) {
+    let mut reader: BufReader<&mut S> = BufReader::new(stream);
+    loop {
+	let mut request: Request = match reader::read_head(&mut reader, config) {
+	    Ok(request) => request,
+	    Err(ParseError::Io(_)) => return,
+	    Err(ParseError::LimitExceeded(_)) => return reject(reader.get_mut(), StatusCode::RequestHeaderFieldsTooLarge),
+	    Err(_) => return reject(reader.get_mut(), StatusCode::BadRequest),
+	};
+	// Responses to HEAD keep their headers, Content-Length included, but
+	// send no body.
+	let head: bool = *request.method() == Method::HEAD;
+	// Framing other parsers could read differently is refused outright
+	// rather than guessed at (RFC 9112 section 6.3).
+	if request.validate().is_err() {
+	    return reject(reader.get_mut(), StatusCode::BadRequest);
+	}
+	// Checked before `100 Continue` invites the client to send the body.
+	match reader::body_length(&request, config) {
+	    Ok(_) => {}
+	    Err(ParseError::LimitExceeded(_)) => return reject(reader.get_mut(), StatusCode::ContentTooLarge),
+	    Err(_) => return reject(reader.get_mut(), StatusCode::BadRequest),
+	}
+	if let Err(rejection) = layers.on_request(&mut request) {
+	    let _ = respond(reader.get_mut(), &rejection.to_response(), head);
+	    return;
+	}
+	if request.expects_continue() && respond(reader.get_mut(), &Response::continue_response(), false).is_err() {
+	    return;
+	}
+	match reader::read_body(&mut reader, &mut request) {
+	    Ok(()) => {}
+	    Err(ParseError::Io(_)) => return,
+	    Err(ParseError::LimitExceeded(_)) => return reject(reader.get_mut(), StatusCode::ContentTooLarge),
+	    Err(_) => return reject(reader.get_mut(), StatusCode::BadRequest),
+	}
+	if let Err(rejection) = layers.on_body(&mut request) {
+	    let _ = respond(reader.get_mut(), &rejection.to_response(), head);
+	    return;
+	}
+
+	let keep_alive: bool = request.keep_alive();
+	let mut response: Response = match panic::catch_unwind(AssertUnwindSafe(|| handler(request))) {
+	    Ok(response) => response,
+	    Err(_) => return reject(reader.get_mut(), StatusCode::InternalServerError),
+	};
+	let keep_alive: bool = keep_alive && response.keep_alive();
+	if !keep_alive {
+	    response.set_keep_alive(false);
+	}
+	if respond(reader.get_mut(), &response, head).is_err() || !keep_alive {
+	    return;
+	}
+    }
+}
+
+// Writes the response in one go, so the head and body are not split across
+// packets. With `head_only` the body is left out, as a HEAD response needs.
+fn respond<W: Write>(writer: &mut W, response: &Response, head_only: bool) -> io::Result<()> {
+    if head_only {
+	writer.write_all(response.build_head().as_bytes())?;
+    } else {
+	writer.write_all(&response.to_bytes())?;
+    }
+    writer.flush()
+}
+
+// Answers a request that could not be handled and closes the connection.
+fn reject<W: Write>(writer: &mut W, status: StatusCode) {
+    let mut response: Response = Response::new();
+    response.set_status(status);
+    response.set_keep_alive(false);
+    let _ = respond(writer, &response, false);
+}
+
+#[cfg(test)]
+mod test_server {
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::Server;
+    use crate::layer::{Chain, SizeLimit};
+    use crate::{client, ParserConfig, Request, Response, StatusCode};
+
+    fn start(threads: usize) -> SocketAddr {
+	let mut server: Server = Server::bind("127.0.0.1:0").unwrap();
+	server.set_threads(threads);
+	let mut config: ParserConfig = ParserConfig::new();
+	config.set_max_body_size(1024);
+	server.set_config(config);
+	let mut layers: Chain = Chain::new();
+	layers.push(SizeLimit::new(16, 1024));
+	server.set_layers(layers);
+	let address: SocketAddr = server.local_addr().unwrap();
+	thread::spawn(move || {
+	    server.serve(|request: Request| {
+		if request.path() == "/panic" {
+		    panic!("handler failed");
+		}
+		let mut response: Response = Response::new();
+		response.set_body(&format!("{} {}", request.method(), request.body()));
+		response
+	    })
+	});
+	address
+    }
+
+    #[test]
+    fn test_serve() {
+	for threads in [0, 2] {
+	    let address: SocketAddr = start(threads);
+	    let request: Request = Request::parse("POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+	    let response: Response = client::send(&request, address).unwrap();
+	    assert_eq!(response.status(), StatusCode::Ok);
+	    assert_eq!(response.body(), "POST hello");
+
+	    let request: Request = Request::parse("GET /panic HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	    assert_eq!(client::send(&request, address).unwrap().status(), StatusCode::InternalServerError);
+	    let request: Request = Request::parse("POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 17\r\n\r\nmore than sixteen").unwrap();
+	    assert_eq!(client::send(&request, address).unwrap().status(), StatusCode::ContentTooLarge);
+	}
+    }
+
+    #[test]
+    fn test_keep_alive() {
+	let address: SocketAddr = start(1);
+	let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	stream.write_all(b"GET /a HTTP/1.1\r\nHost: localhost\r\n\r\nPOST /b HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n2\r\nhi\r\n0\r\n\r\n").unwrap();
+	let mut responses: String = String::new();
+	stream.read_to_string(&mut responses).unwrap();
+	assert_eq!(responses, "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nGET HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 7\r\n\r\nPOST hi");
+
+	let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	stream.write_all(b"HEAD /a HTTP/1.1\r\nHost: localhost\r\n\r\nGET /b HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+	let mut responses: String = String::new();
+	stream.read_to_string(&mut responses).unwrap();
+	assert_eq!(responses, "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 4\r\n\r\nGET ");
+
+	let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	stream.write_all(b"GET / HTTP/1.1\r\nBad Header\r\n\r\n").unwrap();
+	let mut response: String = String::new();
+	stream.read_to_string(&mut response).unwrap();
+	assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+	for head in [
+	    "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\nTransfer-Encoding: chunked\r\n\r\n",
+	    "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\n",
+	    "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked, gzip\r\n\r\n",
+	] {
+	    let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	    stream.write_all(head.as_bytes()).unwrap();
+	    let mut response: String = String::new();
+	    stream.read_to_string(&mut response).unwrap();
+	    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+	}
+    }
+
+    #[test]
+    fn test_expect_continue() {
+	let address: SocketAddr = start(0);
+	let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	stream.write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Length: 2000\r\n\r\n").unwrap();
+	let mut response: String = String::new();
+	stream.read_to_string(&mut response).unwrap();
+	assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+
+	let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	stream.write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Length: 2\r\nConnection: close\r\n\r\nhi").unwrap();
+	let mut response: String = String::new();
+	stream.read_to_string(&mut response).unwrap();
+	assert!(response.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n"));
+
+	// The size limit layer refuses from the head alone, and chunked bodies
+	// are checked once read.
+	for request in [
+	    "POST / HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Length: 17\r\n\r\n",
+	    "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n11\r\nmore than sixteen\r\n0\r\n\r\n",
+	] {
+	    let mut stream: TcpStream = TcpStream::connect(address).unwrap();
+	    stream.write_all(request.as_bytes()).unwrap();
+	    let mut response: String = String::new();
+	    stream.read_to_string(&mut response).unwrap();
+	    assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+	}
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_tls() {
+	use std::sync::Arc;
+
+	let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+	let key = rustls::pki_types::PrivateKeyDer::Pkcs8(certified.signing_key.serialize_der().into());
+	let config = rustls::ServerConfig::builder().with_no_client_auth().with_single_cert(vec![certified.cert.der().clone()], key).unwrap();
+	let mut server: Server = Server::bind("127.0.0.1:0").unwrap();
+	server.set_tls(Arc::new(config));
+	let address: SocketAddr = server.local_addr().unwrap();
+	thread::spawn(move || {
+	    server.serve(|request: Request| {
+		let mut response: Response = Response::new();
+		response.set_body(request.path());
+		response
+	    })
+	});
+
+	let mut roots = rustls::RootCertStore::empty();
+	roots.add(certified.cert.der().clone()).unwrap();
+	let config = Arc::new(rustls::ClientConfig::builder().with_root_certificates(roots).with_no_client_auth());
+	let request: Request = Request::parse("GET /secure HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+	assert_eq!(client::send_tls_with_config(&request, address, Arc::clone(&config)).unwrap().body(), "/secure");
+	// A client that does not trust the certificate fails the handshake
+	// without stopping the server.
+	assert!(client::send_tls(&request, address).is_err());
+	assert_eq!(client::send_tls_with_config(&request, address, config).unwrap().status(), StatusCode::Ok);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix() {
+	let path: std::path::PathBuf = std::env::temp_dir().join(format!("http_parse-{}.sock", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	let server: Server = Server::bind_unix(&path).unwrap();
+	assert!(server.local_addr().is_err());
+	thread::spawn(move || server.serve(|request: Request| {
+	    let mut response: Response = Response::new();
+	    response.set_body(&format!("{} {}", request.path(), request.find_header("host").unwrap().value()));
+	    response
+	}));
+
+	let request: Request = Request::parse("GET /version HTTP/1.1\r\nHost: docker\r\n\r\n").unwrap();
+	assert_eq!(client::send_unix(&request, &path).unwrap().body(), "/version docker");
+	let client: client::Client = client::Client::new();
+	for _ in 0..2 {
+	    let request: Request = Request::parse("GET /containers/json HTTP/1.1\r\n\r\n").unwrap();
+	    assert_eq!(client.send_unix(&request, &path).unwrap().body(), "/containers/json localhost");
+	}
+	std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_timeout() {
+	let mut server: Server = Server::bind("127.0.0.1:0").unwrap();
+	assert!(server.set_read_timeout(Some(Duration::ZERO)).is_err());
+	assert_eq!(server.read_timeout(), Some(Duration::from_secs(30)));
+	assert!(server.set_read_timeout(None).is_ok());
+	assert_eq!(server.read_timeout(), None);
+    }
+}
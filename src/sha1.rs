@@ -0,0 +1,66 @@
+use crate::prelude::*;
+
+// SHA-1 (RFC 3174). Only used for the WebSocket accept key, where it is
+// mandated by the protocol rather than relied on for security.
+pub(crate) fn digest(input: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message: Vec<u8> = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+	message.push(0);
+    }
+    message.extend_from_slice(&(input.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+	let mut words: [u32; 80] = [0; 80];
+	for (i, word) in block.chunks(4).enumerate() {
+	    words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+	}
+	for i in 16..80 {
+	    words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+	}
+
+	let [mut a, mut b, mut c, mut d, mut e] = state;
+	for (i, word) in words.iter().enumerate() {
+	    let (f, k): (u32, u32) = match i {
+		0..=19 => ((b & c) | (!b & d), 0x5A827999),
+		20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+		40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+		_ => (b ^ c ^ d, 0xCA62C1D6),
+	    };
+	    let temp: u32 = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+	    e = d;
+	    d = c;
+	    c = b.rotate_left(30);
+	    b = a;
+	    a = temp;
+	}
+	for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+	    *value = value.wrapping_add(add);
+	}
+    }
+
+    let mut output: [u8; 20] = [0; 20];
+    for (chunk, value) in output.chunks_mut(4).zip(state) {
+	chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    output
+}
+
+#[cfg(test)]
+mod test_sha1 {
+    use super::digest;
+
+    fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_digest() {
+	assert_eq!(hex(&digest(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+	assert_eq!(hex(&digest(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+	assert_eq!(hex(&digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+	assert_eq!(hex(&digest(&[b'a'; 1000])), "291e9a6c66994949b57ba5e650361e98fc36b1ba");
+    }
+}
@@ -0,0 +1,160 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ParseError;
+
+macro_rules! status_codes {
+    ($($variant:ident = $code:literal, $reason:literal;)+) => {
+	#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+	pub enum StatusCode {
+	    $($variant,)+
+	}
+	impl StatusCode {
+	    pub fn as_u16(&self) -> u16 {
+		match self {
+		    $(StatusCode::$variant => $code,)+
+		}
+	    }
+
+	    pub fn canonical_reason(&self) -> &'static str {
+		match self {
+		    $(StatusCode::$variant => $reason,)+
+		}
+	    }
+	}
+	impl TryFrom<u16> for StatusCode {
+	    type Error = ParseError;
+
+	    fn try_from(code: u16) -> Result<StatusCode, ParseError> {
+		match code {
+		    $($code => Ok(StatusCode::$variant),)+
+		    _ => Err(ParseError::UnsupportedStatusCode(code.to_string())),
+		}
+	    }
+	}
+    };
+}
+
+// Every code in the IANA HTTP Status Code Registry, except those marked unused.
+status_codes! {
+    Continue = 100, "Continue";
+    SwitchingProtocols = 101, "Switching Protocols";
+    Processing = 102, "Processing";
+    EarlyHints = 103, "Early Hints";
+    Ok = 200, "OK";
+    Created = 201, "Created";
+    Accepted = 202, "Accepted";
+    NonAuthoritativeInformation = 203, "Non-Authoritative Information";
+    NoContent = 204, "No Content";
+    ResetContent = 205, "Reset Content";
+    PartialContent = 206, "Partial Content";
+    MultiStatus = 207, "Multi-Status";
+    AlreadyReported = 208, "Already Reported";
+    ImUsed = 226, "IM Used";
+    MultipleChoices = 300, "Multiple Choices";
+    MovedPermanently = 301, "Moved Permanently";
+    Found = 302, "Found";
+    SeeOther = 303, "See Other";
+    NotModified = 304, "Not Modified";
+    UseProxy = 305, "Use Proxy";
+    TemporaryRedirect = 307, "Temporary Redirect";
+    PermanentRedirect = 308, "Permanent Redirect";
+    BadRequest = 400, "Bad Request";
+    Unauthorized = 401, "Unauthorized";
+    PaymentRequired = 402, "Payment Required";
+    Forbidden = 403, "Forbidden";
+    NotFound = 404, "Not Found";
+    MethodNotAllowed = 405, "Method Not Allowed";
+    NotAcceptable = 406, "Not Acceptable";
+    ProxyAuthenticationRequired = 407, "Proxy Authentication Required";
+    RequestTimeout = 408, "Request Timeout";
+    Conflict = 409, "Conflict";
+    Gone = 410, "Gone";
+    LengthRequired = 411, "Length Required";
+    PreconditionFailed = 412, "Precondition Failed";
+    ContentTooLarge = 413, "Content Too Large";
+    UriTooLong = 414, "URI Too Long";
+    UnsupportedMediaType = 415, "Unsupported Media Type";
+    RangeNotSatisfiable = 416, "Range Not Satisfiable";
+    ExpectationFailed = 417, "Expectation Failed";
+    MisdirectedRequest = 421, "Misdirected Request";
+    UnprocessableContent = 422, "Unprocessable Content";
+    Locked = 423, "Locked";
+    FailedDependency = 424, "Failed Dependency";
+    TooEarly = 425, "Too Early";
+    UpgradeRequired = 426, "Upgrade Required";
+    PreconditionRequired = 428, "Precondition Required";
+    TooManyRequests = 429, "Too Many Requests";
+    RequestHeaderFieldsTooLarge = 431, "Request Header Fields Too Large";
+    UnavailableForLegalReasons = 451, "Unavailable For Legal Reasons";
+    InternalServerError = 500, "Internal Server Error";
+    NotImplemented = 501, "Not Implemented";
+    BadGateway = 502, "Bad Gateway";
+    ServiceUnavailable = 503, "Service Unavailable";
+    GatewayTimeout = 504, "Gateway Timeout";
+    HttpVersionNotSupported = 505, "HTTP Version Not Supported";
+    VariantAlsoNegotiates = 506, "Variant Also Negotiates";
+    InsufficientStorage = 507, "Insufficient Storage";
+    LoopDetected = 508, "Loop Detected";
+    NotExtended = 510, "Not Extended";
+    NetworkAuthenticationRequired = 511, "Network Authentication Required";
+}
+
+impl StatusCode {
+    pub fn is_informational(&self) -> bool {
+	(100..200).contains(&self.as_u16())
+    }
+
+    pub fn is_success(&self) -> bool {
+	(200..300).contains(&self.as_u16())
+    }
+
+    pub fn is_redirection(&self) -> bool {
+	(300..400).contains(&self.as_u16())
+    }
+
+    pub fn is_client_error(&self) -> bool {
+	(400..500).contains(&self.as_u16())
+    }
+
+    pub fn is_server_error(&self) -> bool {
+	(500..600).contains(&self.as_u16())
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "{} {}", self.as_u16(), self.canonical_reason())
+    }
+}
+
+#[cfg(test)]
+mod test_status_code {
+    use super::StatusCode;
+    use crate::ParseError;
+
+    #[test]
+    fn test_try_from() {
+	assert_eq!(StatusCode::try_from(404), Ok(StatusCode::NotFound));
+	assert_eq!(StatusCode::try_from(451), Ok(StatusCode::UnavailableForLegalReasons));
+	assert_eq!(StatusCode::try_from(299), Err(ParseError::UnsupportedStatusCode("299".to_string())));
+	assert_eq!(StatusCode::NotFound.as_u16(), 404);
+	assert_eq!(StatusCode::NotFound.canonical_reason(), "Not Found");
+    }
+
+    #[test]
+    fn test_classes() {
+	assert!(StatusCode::EarlyHints.is_informational());
+	assert!(StatusCode::NoContent.is_success());
+	assert!(StatusCode::PermanentRedirect.is_redirection());
+	assert!(StatusCode::TooManyRequests.is_client_error());
+	assert!(!StatusCode::TooManyRequests.is_server_error());
+	assert!(StatusCode::NetworkAuthenticationRequired.is_server_error());
+    }
+
+    #[test]
+    fn test_display() {
+	assert_eq!(format!("{}", StatusCode::Ok), "200 OK");
+	assert_eq!(format!("{}", StatusCode::InternalServerError), "500 Internal Server Error");
+    }
+}
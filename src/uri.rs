@@ -0,0 +1,286 @@
+use core::fmt;
+use core::net::Ipv6Addr;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::ParseError;
+
+// A request target in any of the four RFC 9112 forms:
+//   origin-form     /where?q=now
+//   absolute-form   http://www.example.org/pub/WWW/TheProject.html
+//   authority-form  www.example.com:80 (CONNECT only)
+//   asterisk-form   * (server-wide OPTIONS)
+// Components are kept as they appear on the wire, without percent-decoding.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Uri {
+    scheme: Option<String>,
+    authority: Option<String>,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+impl Uri {
+    pub fn parse(target: &str) -> Result<Uri, ParseError> {
+	if target.is_empty() || target.bytes().any(|byte| byte <= b' ' || byte == 0x7f) {
+	    return Err(ParseError::InvalidUri(target.to_string()));
+	}
+	if target == "*" {
+	    return Ok(Uri::new(None, None, "*"));
+	}
+	if target.starts_with('/') {
+	    return Ok(Uri::new(None, None, target));
+	}
+
+	if let Some((scheme, rest)) = target.split_once("://") {
+	    let mut chars = scheme.chars();
+	    let valid_scheme: bool = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+		&& chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+	    let end: usize = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+	    if !valid_scheme || end == 0 {
+		return Err(ParseError::InvalidUri(target.to_string()));
+	    }
+	    let path: String = match &rest[end..] {
+		path if path.starts_with('/') => path.to_string(),
+		path => format!("/{}", path),
+	    };
+	    return Ok(Uri::new(Some(scheme.to_lowercase()), Some(&rest[..end]), &path));
+	}
+
+	// authority-form requires an explicit port.
+	match target.rsplit_once(':') {
+	    Some((host, port)) if !host.is_empty() && !target.contains(['/', '?', '#', '@']) && port.parse::<u16>().is_ok() => {
+		Ok(Uri::new(None, Some(target), ""))
+	    }
+	    _ => Err(ParseError::InvalidUri(target.to_string())),
+	}
+    }
+
+    fn new(scheme: Option<String>, authority: Option<&str>, rest: &str) -> Uri {
+	let (rest, fragment) = match rest.split_once('#') {
+	    Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+	    None => (rest, None),
+	};
+	let (path, query) = match rest.split_once('?') {
+	    Some((path, query)) => (path, Some(query.to_string())),
+	    None => (rest, None),
+	};
+	Uri {
+	    scheme,
+	    authority: authority.map(str::to_string),
+	    path: path.to_string(),
+	    query,
+	    fragment,
+	}
+    }
+
+    pub fn scheme(&self) -> Option<&str> {
+	self.scheme.as_deref()
+    }
+
+    // `userinfo@host:port`, as present in absolute- and authority-form.
+    pub fn authority(&self) -> Option<&str> {
+	self.authority.as_deref()
+    }
+
+    // The host without userinfo and port. IPv6 literals keep their brackets.
+    pub fn host(&self) -> Option<&str> {
+	let authority: &str = self.authority.as_deref()?;
+	let host_port: &str = authority.rsplit_once('@').map_or(authority, |(_, host_port)| host_port);
+	match host_port.rfind(':') {
+	    Some(colon) if !host_port[colon..].contains(']') => Some(&host_port[..colon]),
+	    _ => Some(host_port),
+	}
+    }
+
+    pub fn port(&self) -> Option<u16> {
+	let authority: &str = self.authority.as_deref()?;
+	let host: &str = self.host()?;
+	let port: &str = authority[authority.rfind(host)? + host.len()..].strip_prefix(':')?;
+	port.parse().ok()
+    }
+
+    // Empty for authority-form and `*` for asterisk-form.
+    pub fn path(&self) -> &str {
+	&self.path
+    }
+
+    pub fn query(&self) -> Option<&str> {
+	self.query.as_deref()
+    }
+
+    pub fn fragment(&self) -> Option<&str> {
+	self.fragment.as_deref()
+    }
+
+    pub fn is_absolute(&self) -> bool {
+	self.scheme.is_some()
+    }
+
+    pub fn is_authority_form(&self) -> bool {
+	self.scheme.is_none() && self.authority.is_some()
+    }
+
+    pub fn is_asterisk(&self) -> bool {
+	self.path == "*"
+    }
+}
+// Splits a `Host` header value into host and port, checking the RFC 3986
+// syntax: a bracketed IPv6 literal or a reg-name/IPv4 address, optionally
+// followed by `:port`. IPv6 literals keep their brackets.
+pub(crate) fn split_host(value: &str) -> Option<(&str, Option<u16>)> {
+    let (host, port) = if value.starts_with('[') {
+	let end: usize = value.find(']')? + 1;
+	value[1..end - 1].parse::<Ipv6Addr>().ok()?;
+	match &value[end..] {
+	    "" => (&value[..end], None),
+	    rest => (&value[..end], Some(rest.strip_prefix(':')?)),
+	}
+    } else {
+	match value.split_once(':') {
+	    Some((host, port)) => (host, Some(port)),
+	    None => (value, None),
+	}
+    };
+    let valid_host: bool = host.starts_with('[')
+	|| (!host.is_empty() && host.chars().all(|c| c.is_ascii_alphanumeric() || "-._~%!$&'()*+,;=".contains(c)));
+    if !valid_host {
+	return None;
+    }
+    // An empty port is allowed by the grammar and means the default.
+    match port {
+	Some(port) if !port.is_empty() => {
+	    if !port.bytes().all(|byte| byte.is_ascii_digit()) {
+		return None;
+	    }
+	    Some((host, Some(port.parse().ok()?)))
+	}
+	_ => Some((host, None)),
+    }
+}
+
+// Resolves `.` and `..` segments and collapses empty ones, as in RFC 3986
+// section 5.2.4. `..` never climbs above the root, and a trailing slash is
+// kept so `/docs/` and `/docs` stay distinct.
+pub(crate) fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+	match segment {
+	    "" | "." => {}
+	    ".." => {
+		segments.pop();
+	    }
+	    segment => segments.push(segment),
+	}
+    }
+    let trailing_slash: bool = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+    let mut normalized: String = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+	normalized.push('/');
+    }
+    normalized
+}
+
+impl FromStr for Uri {
+    type Err = ParseError;
+
+    fn from_str(target: &str) -> Result<Uri, ParseError> {
+	Uri::parse(target)
+    }
+}
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match (&self.scheme, &self.authority) {
+	    (Some(scheme), Some(authority)) => write!(f, "{}://{}", scheme, authority)?,
+	    (None, Some(authority)) => write!(f, "{}", authority)?,
+	    _ => {}
+	}
+	write!(f, "{}", self.path)?;
+	if let Some(query) = &self.query {
+	    write!(f, "?{}", query)?;
+	}
+	if let Some(fragment) = &self.fragment {
+	    write!(f, "#{}", fragment)?;
+	}
+	Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_uri {
+    use super::{remove_dot_segments, split_host, Uri};
+    use crate::ParseError;
+
+    #[test]
+    fn test_origin_form() {
+	let uri: Uri = Uri::parse("/search?q=rust&page=2#top").unwrap();
+	assert_eq!(uri.scheme(), None);
+	assert_eq!(uri.authority(), None);
+	assert_eq!(uri.path(), "/search");
+	assert_eq!(uri.query(), Some("q=rust&page=2"));
+	assert_eq!(uri.fragment(), Some("top"));
+	assert_eq!(uri.to_string(), "/search?q=rust&page=2#top");
+    }
+
+    #[test]
+    fn test_absolute_form() {
+	let uri: Uri = Uri::parse("HTTP://user@example.com:8080/a/b?x=1").unwrap();
+	assert!(uri.is_absolute());
+	assert_eq!(uri.scheme(), Some("http"));
+	assert_eq!(uri.authority(), Some("user@example.com:8080"));
+	assert_eq!(uri.host(), Some("example.com"));
+	assert_eq!(uri.port(), Some(8080));
+	assert_eq!(uri.path(), "/a/b");
+	assert_eq!(uri.query(), Some("x=1"));
+
+	let uri: Uri = Uri::parse("http://[::1]?x").unwrap();
+	assert_eq!(uri.host(), Some("[::1]"));
+	assert_eq!(uri.port(), None);
+	assert_eq!(uri.to_string(), "http://[::1]/?x");
+    }
+
+    #[test]
+    fn test_authority_and_asterisk_form() {
+	let uri: Uri = Uri::parse("example.com:443").unwrap();
+	assert!(uri.is_authority_form());
+	assert_eq!(uri.host(), Some("example.com"));
+	assert_eq!(uri.port(), Some(443));
+	assert_eq!(uri.path(), "");
+	assert_eq!(uri.to_string(), "example.com:443");
+
+	let uri: Uri = Uri::parse("[2001:db8::1]:8443").unwrap();
+	assert_eq!(uri.host(), Some("[2001:db8::1]"));
+	assert_eq!(uri.port(), Some(8443));
+
+	assert!(Uri::parse("*").unwrap().is_asterisk());
+    }
+
+    #[test]
+    fn test_split_host() {
+	assert_eq!(split_host("example.com"), Some(("example.com", None)));
+	assert_eq!(split_host("example.com:8080"), Some(("example.com", Some(8080))));
+	assert_eq!(split_host("example.com:"), Some(("example.com", None)));
+	assert_eq!(split_host("192.0.2.1:80"), Some(("192.0.2.1", Some(80))));
+	assert_eq!(split_host("[::1]:8080"), Some(("[::1]", Some(8080))));
+	assert_eq!(split_host("[2001:db8::1]"), Some(("[2001:db8::1]", None)));
+	for value in ["", ":80", "exa mple.com", "example.com:http", "example.com:+80", "example.com:99999", "a:1:2", "[::1", "[::1]x", "[not-ip]", "::1"] {
+	    assert_eq!(split_host(value), None, "{}", value);
+	}
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+	assert_eq!(remove_dot_segments("/a/b/../c/./d"), "/a/c/d");
+	assert_eq!(remove_dot_segments("//a///b/"), "/a/b/");
+	assert_eq!(remove_dot_segments("/../../etc/passwd"), "/etc/passwd");
+	assert_eq!(remove_dot_segments("/a/.."), "/");
+	assert_eq!(remove_dot_segments("/a/b/."), "/a/b/");
+	assert_eq!(remove_dot_segments(""), "/");
+    }
+
+    #[test]
+    fn test_invalid() {
+	for target in ["", "example.com", "example.com:http", "1http://x", "http:///path", "/a b"] {
+	    assert_eq!(Uri::parse(target), Err(ParseError::InvalidUri(target.to_string())));
+	}
+    }
+}
@@ -0,0 +1,97 @@
+use crate::prelude::*;
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+// Percent-encodes everything except the RFC 3986 unreserved characters.
+pub fn url_encode(input: &str) -> String {
+    encode(input, b"")
+}
+
+// Decodes `%XX` escapes. Malformed escapes are kept as-is and invalid UTF-8
+// is replaced with U+FFFD.
+pub fn url_decode(input: &str) -> String {
+    decode(input, false)
+}
+
+pub(crate) fn encode_path(input: &str) -> String {
+    encode(input, b"/")
+}
+
+pub(crate) fn decode_query(input: &str) -> String {
+    decode(input, true)
+}
+
+// application/x-www-form-urlencoded serialization, where spaces become `+`.
+pub(crate) fn encode_form(input: &str) -> String {
+    input.split(' ').map(url_encode).collect::<Vec<String>>().join("+")
+}
+
+fn encode(input: &str, keep: &[u8]) -> String {
+    let mut encoded: String = String::with_capacity(input.len());
+    for byte in input.bytes() {
+	if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || keep.contains(&byte) {
+	    encoded.push(byte as char);
+	} else {
+	    encoded.push('%');
+	    encoded.push(HEX[(byte >> 4) as usize] as char);
+	    encoded.push(HEX[(byte & 0x0F) as usize] as char);
+	}
+    }
+    encoded
+}
+
+fn decode(input: &str, plus_as_space: bool) -> String {
+    let bytes: &[u8] = input.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0;
+    while i < bytes.len() {
+	if bytes[i] == b'%' && i + 2 < bytes.len() {
+	    if let (Some(high), Some(low)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+		decoded.push(high << 4 | low);
+		i += 3;
+		continue;
+	    }
+	}
+	if bytes[i] == b'+' && plus_as_space {
+	    decoded.push(b' ');
+	} else {
+	    decoded.push(bytes[i]);
+	}
+	i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+	b'0'..=b'9' => Some(byte - b'0'),
+	b'a'..=b'f' => Some(byte - b'a' + 10),
+	b'A'..=b'F' => Some(byte - b'A' + 10),
+	_ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_url {
+    use super::{decode_query, encode_form, encode_path, url_decode, url_encode};
+
+    #[test]
+    fn test_url_encode() {
+	assert_eq!(url_encode("hello world"), "hello%20world");
+	assert_eq!(url_encode("a&b=c/d"), "a%26b%3Dc%2Fd");
+	assert_eq!(url_encode("ünï-._~"), "%C3%BCn%C3%AF-._~");
+	assert_eq!(encode_path("/my files/a+b"), "/my%20files/a%2Bb");
+	assert_eq!(encode_form("a b+c"), "a+b%2Bc");
+    }
+
+    #[test]
+    fn test_url_decode() {
+	assert_eq!(url_decode("hello%20world"), "hello world");
+	assert_eq!(url_decode("a%26b"), "a&b");
+	assert_eq!(url_decode("%C3%BCn"), "ün");
+	assert_eq!(url_decode("100%"), "100%");
+	assert_eq!(url_decode("%zz%4"), "%zz%4");
+	assert_eq!(url_decode("a+b"), "a+b");
+	assert_eq!(decode_query("a+b%2B"), "a b+");
+    }
+}
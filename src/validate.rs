@@ -0,0 +1,103 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{headers, HeaderMap, Request};
+
+// A construct that two parsers may frame differently, letting a request be
+// smuggled past a proxy (RFC 9112 section 11.2).
+#[derive(PartialEq, Clone, Debug)]
+pub enum ValidationError {
+    ContentLengthWithTransferEncoding,
+    ConflictingContentLength(String, String),
+    UnchunkedTransferEncoding(String),
+    InvalidHeaderValue(String),
+    WhitespaceBeforeColon(String),
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ValidationError::ContentLengthWithTransferEncoding => write!(f, "Both Content-Length and Transfer-Encoding are present"),
+	    ValidationError::ConflictingContentLength(first, second) => write!(f, "Conflicting Content-Length values: `{}` and `{}`", first, second),
+	    ValidationError::UnchunkedTransferEncoding(codings) => write!(f, "Transfer-Encoding `{}` does not end with chunked", codings),
+	    ValidationError::InvalidHeaderValue(name) => write!(f, "CR, LF or NUL in the value of header `{}`", name),
+	    ValidationError::WhitespaceBeforeColon(name) => write!(f, "Whitespace between header name `{}` and the colon", name),
+	}
+    }
+}
+impl core::error::Error for ValidationError {}
+
+pub(crate) fn validate(request: &Request) -> Result<(), Vec<ValidationError>> {
+    // Constructs the lenient parser accepted but did not keep.
+    let mut errors: Vec<ValidationError> = request.anomalies.clone();
+
+    if request.headers.contains("content-length") && request.headers.contains("transfer-encoding") {
+	errors.push(ValidationError::ContentLengthWithTransferEncoding);
+    }
+    let lengths: Vec<&str> = request.headers.get_all("content-length").iter()
+	.flat_map(|header| header.value().split(','))
+	.map(|value| value.trim_matches([' ', '\t']))
+	.collect();
+    if let Some(other) = lengths.iter().find(|value| **value != lengths[0]) {
+	errors.push(ValidationError::ConflictingContentLength(lengths[0].to_string(), other.to_string()));
+    }
+    // Without chunked last, a request body has no end (RFC 9112 section 6.3).
+    if let Some(codings) = request.headers.get_joined("transfer-encoding").filter(|_| !headers::is_chunked(&request.headers)) {
+	errors.push(ValidationError::UnchunkedTransferEncoding(codings));
+    }
+    for header in request.headers.iter().chain(request.trailers.iter()) {
+	if header.name().ends_with([' ', '\t']) {
+	    errors.push(ValidationError::WhitespaceBeforeColon(header.name().trim_end_matches([' ', '\t']).to_string()));
+	}
+	if header.value().bytes().any(|byte| byte == b'\r' || byte == b'\n' || byte == 0) {
+	    errors.push(ValidationError::InvalidHeaderValue(header.name().clone()));
+	}
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+// The anomaly to record when a duplicate header is dropped during parsing:
+// a second Content-Length that disagrees with the one kept.
+pub(crate) fn dropped_duplicate(headers: &HeaderMap, name: &str, value: &str) -> Option<ValidationError> {
+    if !name.eq_ignore_ascii_case("content-length") {
+	return None;
+    }
+    let kept: &str = headers.get(name)?.value();
+    if kept == value { None } else { Some(ValidationError::ConflictingContentLength(kept.to_string(), value.to_string())) }
+}
+
+#[cfg(test)]
+mod test_validate {
+    use super::ValidationError;
+    use crate::Request;
+
+    #[test]
+    fn test_validate() {
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\n\r\nhi").unwrap();
+	assert_eq!(request.validate(), Ok(()));
+
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n").unwrap();
+	assert_eq!(request.validate(), Err(vec![ValidationError::ContentLengthWithTransferEncoding]));
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked, gzip\r\n\r\n").unwrap();
+	assert_eq!(request.validate(), Err(vec![ValidationError::UnchunkedTransferEncoding("chunked, gzip".to_string())]));
+
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\nContent-Length: 5\r\n\r\nhi").unwrap();
+	assert_eq!(request.validate(), Err(vec![ValidationError::ConflictingContentLength("2".to_string(), "5".to_string())]));
+	let request: Request = Request::parse("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\nContent-Length: 2\r\n\r\nhi").unwrap();
+	assert_eq!(request.validate(), Ok(()));
+
+	let request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\nX-Id : 1\r\n\r\n").unwrap();
+	assert_eq!(request.validate(), Err(vec![ValidationError::WhitespaceBeforeColon("X-Id".to_string())]));
+    }
+
+    #[test]
+    fn test_validate_built() {
+	let mut request: Request = Request::parse("GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+	request.set_header("X-Note", "a\r\nX-Injected: 1");
+	request.headers.append("Content-Length", "1, 2");
+	assert_eq!(request.validate(), Err(vec![
+	    ValidationError::ConflictingContentLength("1".to_string(), "2".to_string()),
+	    ValidationError::InvalidHeaderValue("X-Note".to_string()),
+	]));
+    }
+}
@@ -0,0 +1,60 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::ParseError;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Version {
+    Http09,
+    Http10,
+    Http11,
+    Http2,
+    Http3,
+}
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    Version::Http09 => write!(f, "HTTP/0.9"),
+	    Version::Http10 => write!(f, "HTTP/1.0"),
+	    Version::Http11 => write!(f, "HTTP/1.1"),
+	    Version::Http2 => write!(f, "HTTP/2"),
+	    Version::Http3 => write!(f, "HTTP/3"),
+	}
+    }
+}
+impl FromStr for Version {
+    type Err = ParseError;
+
+    fn from_str(version: &str) -> Result<Version, ParseError> {
+	match version {
+	    "HTTP/0.9" => Ok(Version::Http09),
+	    "HTTP/1.0" => Ok(Version::Http10),
+	    "HTTP/1.1" => Ok(Version::Http11),
+	    "HTTP/2" | "HTTP/2.0" => Ok(Version::Http2),
+	    "HTTP/3" | "HTTP/3.0" => Ok(Version::Http3),
+	    _ => Err(ParseError::UnsupportedVersion(version.to_string())),
+	}
+    }
+}
+
+#[cfg(test)]
+mod test_version {
+    use super::Version;
+    use crate::ParseError;
+
+    #[test]
+    fn test_from_str() {
+	assert_eq!("HTTP/1.0".parse::<Version>(), Ok(Version::Http10));
+	assert_eq!("HTTP/2.0".parse::<Version>(), Ok(Version::Http2));
+	assert_eq!("HTTP/11".parse::<Version>(), Err(ParseError::UnsupportedVersion("HTTP/11".to_string())));
+	assert_eq!("http/1.1".parse::<Version>(), Err(ParseError::UnsupportedVersion("http/1.1".to_string())));
+    }
+
+    #[test]
+    fn test_display() {
+	assert_eq!(format!("{}", Version::Http09), "HTTP/0.9");
+	assert_eq!(format!("{}", Version::Http11), "HTTP/1.1");
+	assert_eq!(format!("{}", Version::Http3), "HTTP/3");
+    }
+}
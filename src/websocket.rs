@@ -0,0 +1,164 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{base64, sha1, Method, Request, Response, StatusCode, Version};
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const SUPPORTED_VERSION: &str = "13";
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum WebSocketError {
+    NotUpgrade,
+    MissingKey,
+    InvalidKey(String),
+    UnsupportedVersion(String),
+}
+impl fmt::Display for WebSocketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    WebSocketError::NotUpgrade => write!(f, "Request is not a WebSocket upgrade"),
+	    WebSocketError::MissingKey => write!(f, "Missing Sec-WebSocket-Key header"),
+	    WebSocketError::InvalidKey(key) => write!(f, "Invalid Sec-WebSocket-Key: `{}`", key),
+	    WebSocketError::UnsupportedVersion(version) => write!(f, "Unsupported WebSocket version: `{}`", version),
+	}
+    }
+}
+impl core::error::Error for WebSocketError {}
+
+// The client side of an opening handshake (RFC 6455 section 4.2.1), taken
+// from an upgrade request.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Handshake {
+    key: String,
+    version: String,
+    protocols: Vec<String>,
+    extensions: Vec<String>,
+}
+impl Handshake {
+    pub fn from_request(request: &Request) -> Result<Handshake, WebSocketError> {
+	if !is_upgrade(request) {
+	    return Err(WebSocketError::NotUpgrade);
+	}
+	let key: String = request.headers().get("sec-websocket-key").ok_or(WebSocketError::MissingKey)?.value().trim().to_string();
+	if base64::decode(&key).is_none_or(|nonce| nonce.len() != 16) {
+	    return Err(WebSocketError::InvalidKey(key));
+	}
+	let version: String = request.headers().get("sec-websocket-version").map(|header| header.value().trim().to_string()).unwrap_or_default();
+	if version != SUPPORTED_VERSION {
+	    return Err(WebSocketError::UnsupportedVersion(version));
+	}
+	Ok(Handshake {
+	    key,
+	    version,
+	    protocols: list(request, "sec-websocket-protocol"),
+	    extensions: list(request, "sec-websocket-extensions"),
+	})
+    }
+
+    pub fn key(&self) -> &String {
+	&self.key
+    }
+
+    pub fn version(&self) -> &String {
+	&self.version
+    }
+
+    // Subprotocols offered by the client, in order of preference.
+    pub fn protocols(&self) -> &Vec<String> {
+	&self.protocols
+    }
+
+    // Extension offers, each with its parameters left as sent.
+    pub fn extensions(&self) -> &Vec<String> {
+	&self.extensions
+    }
+
+    pub fn accept_key(&self) -> String {
+	accept_key(&self.key)
+    }
+
+    // The 101 Switching Protocols response completing the handshake. The
+    // chosen subprotocol, if any, should be one of `protocols`.
+    pub fn response(&self, protocol: Option<&str>) -> Response {
+	let mut response: Response = Response::new();
+	response.set_status(StatusCode::SwitchingProtocols);
+	response.set_header("Upgrade", "websocket");
+	response.set_header("Connection", "Upgrade");
+	response.set_header("Sec-WebSocket-Accept", &self.accept_key());
+	if let Some(protocol) = protocol {
+	    response.set_header("Sec-WebSocket-Protocol", protocol);
+	}
+	response
+    }
+}
+
+// The `Sec-WebSocket-Accept` value for a client key: the base64-encoded
+// SHA-1 of the key followed by the protocol GUID.
+pub fn accept_key(key: &str) -> String {
+    base64::encode(&sha1::digest(format!("{}{}", key.trim(), ACCEPT_GUID).as_bytes()))
+}
+
+// An HTTP/1.1 GET asking to upgrade the connection to `websocket`.
+// The key and version are checked by `Handshake::from_request`.
+pub fn is_upgrade(request: &Request) -> bool {
+    *request.method() == Method::GET
+	&& *request.version() == Version::Http11
+	&& has_token(request, "upgrade", "websocket")
+	&& has_token(request, "connection", "upgrade")
+}
+
+fn has_token(request: &Request, name: &str, token: &str) -> bool {
+    list(request, name).iter().any(|value| value.eq_ignore_ascii_case(token))
+}
+
+fn list(request: &Request, name: &str) -> Vec<String> {
+    request.find_headers(name).iter()
+	.flat_map(|header| header.value().split(','))
+	.map(str::trim)
+	.filter(|value| !value.is_empty())
+	.map(str::to_string)
+	.collect()
+}
+
+#[cfg(test)]
+mod test_websocket {
+    use super::{accept_key, Handshake, WebSocketError};
+    use crate::{Request, StatusCode};
+
+    const UPGRADE: &str = "GET /chat HTTP/1.1\r\nHost: server.example.com\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Protocol: chat, superchat\r\nSec-WebSocket-Version: 13\r\n\r\n";
+
+    #[test]
+    fn test_accept_key() {
+	assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_handshake() {
+	let request: Request = Request::parse(UPGRADE).unwrap();
+	assert!(request.is_websocket_upgrade());
+	let handshake: Handshake = Handshake::from_request(&request).unwrap();
+	assert_eq!(handshake.key(), "dGhlIHNhbXBsZSBub25jZQ==");
+	assert_eq!(handshake.protocols(), &vec!["chat".to_string(), "superchat".to_string()]);
+	assert!(handshake.extensions().is_empty());
+	assert_eq!(
+	    handshake.response(Some("chat")).build(),
+	    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\nSec-WebSocket-Protocol: chat\r\n\r\n"
+	);
+	assert_eq!(handshake.response(None).status(), StatusCode::SwitchingProtocols);
+    }
+
+    #[test]
+    fn test_handshake_errors() {
+	let request: Request = Request::parse(&UPGRADE.replace("GET", "POST")).unwrap();
+	assert!(!request.is_websocket_upgrade());
+	assert_eq!(Handshake::from_request(&request), Err(WebSocketError::NotUpgrade));
+	let request: Request = Request::parse(&UPGRADE.replace("HTTP/1.1", "HTTP/1.0")).unwrap();
+	assert_eq!(Handshake::from_request(&request), Err(WebSocketError::NotUpgrade));
+	let request: Request = Request::parse(&UPGRADE.replace("Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n", "")).unwrap();
+	assert_eq!(Handshake::from_request(&request), Err(WebSocketError::MissingKey));
+	let request: Request = Request::parse(&UPGRADE.replace("dGhlIHNhbXBsZSBub25jZQ==", "c2hvcnQ=")).unwrap();
+	assert_eq!(Handshake::from_request(&request), Err(WebSocketError::InvalidKey("c2hvcnQ=".to_string())));
+	let request: Request = Request::parse(&UPGRADE.replace("Version: 13", "Version: 8")).unwrap();
+	assert_eq!(Handshake::from_request(&request), Err(WebSocketError::UnsupportedVersion("8".to_string())));
+    }
+}